@@ -0,0 +1,78 @@
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use aoc_2024::{day2::Tolerance, *};
+
+/// Builds a routine running a parsing phase on the input it's given
+type Phase = fn(&'static str) -> Box<dyn FnMut()>;
+
+/// Runs a method on a solver built once, so the benchmark doesn't include building it, on the
+/// input followed by any other arguments
+macro_rules! solver {
+    ($solver:path, $method:ident $(, $arg:expr)*) => {
+        |input| {
+            let mut solver = <$solver>::new();
+            Box::new(move || {
+                black_box(solver.$method(black_box(input) $(, black_box($arg))*));
+            })
+        }
+    };
+}
+
+/// Checked by the general path rather than the one [`day2::part2`] uses
+const TWO_REMOVALS: Tolerance = Tolerance {
+    removals: 2,
+    ..Tolerance::PART2
+};
+
+/// The parsing phases benchmarked on their own, by day and name, as well as in the parts in
+/// [`SOLUTIONS`], and general versions of the parts, which should be as fast as them with the
+/// puzzle's settings
+const PHASES: &[(u8, &str, Phase)] = &[
+    (1, "input_handling", solver!(day1::Solver, parse_lists)),
+    (
+        2,
+        "count_safe_part1",
+        solver!(day2::Solver, count_safe, Tolerance::PART1),
+    ),
+    (
+        2,
+        "count_safe_part2",
+        solver!(day2::Solver, count_safe, Tolerance::PART2),
+    ),
+    (
+        2,
+        "count_safe_k2",
+        solver!(day2::Solver, count_safe, TWO_REMOVALS),
+    ),
+    (5, "parse_rules", solver!(day5::Solver, parse_rules)),
+    (14, "parse_robots", solver!(day14::Solver, parse_robots)),
+    (15, "read_field_p1", solver!(day15::Solver, read_field_p1)),
+    (15, "read_field_p2", solver!(day15::Solver, read_field_p2)),
+];
+
+/// Reads `input/2024/dayN.txt`
+fn input(day: u8) -> &'static str {
+    let path = format!("{}/input/2024/day{day}.txt", env!("CARGO_MANIFEST_DIR"));
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("reading {path}: {err}"))
+        .leak()
+}
+
+fn solutions(c: &mut Criterion) {
+    for part in SOLUTIONS {
+        let input = input(part.day);
+        c.bench_function(&format!("day{}:part{}", part.day, part.part), |b| {
+            b.iter(|| (part.solve)(black_box(input)))
+        });
+    }
+
+    for &(day, name, phase) in PHASES {
+        let mut routine = phase(input(day));
+        c.bench_function(&format!("day{day}:{name}"), |b| b.iter(&mut routine));
+    }
+}
+
+criterion_group!(benches, solutions);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+//! Generates `LUT14.bin`, day 14's table of the number starting with each three bytes, in
+//! `OUT_DIR`. The `small-lut14` feature builds a smaller table in `src/day14.rs` instead.
+
+use std::{env, fs, path::PathBuf};
+
+#[path = "src/lut14.rs"]
+mod lut14;
+
+fn main() {
+    println!("cargo::rerun-if-changed=build.rs");
+    println!("cargo::rerun-if-changed=src/lut14.rs");
+    if env::var_os("CARGO_FEATURE_SMALL_LUT14").is_some() {
+        return;
+    }
+
+    // -1 for bytes that don't start a number, as its byte doesn't start one either
+    let lut: Vec<u8> = (0..1 << 24)
+        .map(|key: u32| {
+            let text = [(key >> 16) as u8, (key >> 8) as u8, key as u8];
+            lut14::number(&text).map_or(u8::MAX, |(num, _)| num as u8)
+        })
+        .collect();
+
+    let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap());
+    fs::write(out_dir.join("LUT14.bin"), lut).unwrap();
+}
@@ -0,0 +1,351 @@
+//! Known answers for each day, part and input, read from `input/2024/answers.toml`.
+
+use std::{
+    collections::BTreeMap,
+    error::Error,
+    fmt::{self, Display},
+};
+
+use crate::{batch::Outcome, ParseError};
+
+/// The answers file couldn't be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AnswersError {
+    Toml(toml::de::Error),
+    /// `key` isn't what the file's layout has at that level
+    Invalid {
+        key: String,
+        expected: &'static str,
+    },
+}
+
+impl From<toml::de::Error> for AnswersError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
+
+impl Display for AnswersError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Toml(err) => write!(f, "{err}"),
+            Self::Invalid { key, expected } => write!(f, "expected {expected} at `{key}`"),
+        }
+    }
+}
+
+impl Error for AnswersError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Toml(err) => Some(err),
+            Self::Invalid { .. } => None,
+        }
+    }
+}
+
+/// Known answers, keyed by day, part and input name. The file has a `[dayN.partN]` table for
+/// each part, mapping input names to answers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Answers(BTreeMap<(u8, u8, String), String>);
+
+/// A solver's answer that doesn't match the known one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mismatch {
+    pub user: String,
+    pub day: u8,
+    pub part: u8,
+    pub expected: String,
+    pub found: Result<String, ParseError>,
+}
+
+impl Answers {
+    pub fn parse(text: &str) -> Result<Self, AnswersError> {
+        /// Parses `N` from the last part of `key`, `prefixN`
+        fn number(key: &str, prefix: &str, expected: &'static str) -> Result<u8, AnswersError> {
+            key.rsplit('.')
+                .next()
+                .and_then(|name| name.strip_prefix(prefix))
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(|| AnswersError::Invalid {
+                    key: key.to_owned(),
+                    expected,
+                })
+        }
+
+        fn table<'a>(
+            value: &'a toml::Value,
+            key: &str,
+            expected: &'static str,
+        ) -> Result<&'a toml::Table, AnswersError> {
+            value.as_table().ok_or_else(|| AnswersError::Invalid {
+                key: key.to_owned(),
+                expected,
+            })
+        }
+
+        let mut answers = BTreeMap::new();
+        for (day_key, parts) in &text.parse::<toml::Table>()? {
+            let day = number(day_key, "day", "a `dayN` table")?;
+            for (part_key, names) in table(parts, day_key, "a `dayN` table")? {
+                let key = format!("{day_key}.{part_key}");
+                let part = number(&key, "part", "a `partN` table")?;
+                for (name, answer) in table(names, &key, "a `partN` table")? {
+                    let answer = match answer {
+                        toml::Value::Integer(answer) => answer.to_string(),
+                        toml::Value::String(answer) => answer.clone(),
+                        _ => {
+                            return Err(AnswersError::Invalid {
+                                key: format!("{key}.{name}"),
+                                expected: "an integer or string answer",
+                            })
+                        }
+                    };
+                    answers.insert((day, part, name.clone()), answer);
+                }
+            }
+        }
+
+        Ok(Self(answers))
+    }
+
+    pub fn get(&self, day: u8, part: u8, name: &str) -> Option<&str> {
+        self.0
+            .get(&(day, part, name.to_owned()))
+            .map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: u8, part: u8, name: &str, answer: &str) {
+        self.0
+            .insert((day, part, name.to_owned()), answer.to_owned());
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The day, part and input name of each known answer without an outcome, in order.
+    pub fn unchecked<'a>(&'a self, outcomes: &[Outcome]) -> Vec<(u8, u8, &'a str)> {
+        self.0
+            .keys()
+            .filter(|(day, part, name)| {
+                !outcomes
+                    .iter()
+                    .any(|o| (o.day, o.part, o.user.as_str()) == (*day, *part, name.as_str()))
+            })
+            .map(|(day, part, name)| (*day, *part, name.as_str()))
+            .collect()
+    }
+
+    /// Compares each outcome with its known answer, returning those that differ. Outcomes without
+    /// a known answer are skipped.
+    pub fn check(&self, outcomes: &[Outcome]) -> Vec<Mismatch> {
+        outcomes
+            .iter()
+            .filter_map(|outcome| {
+                let expected = self.get(outcome.day, outcome.part, &outcome.user)?;
+                (outcome.answer.as_deref() != Ok(expected)).then(|| Mismatch {
+                    user: outcome.user.clone(),
+                    day: outcome.day,
+                    part: outcome.part,
+                    expected: expected.to_owned(),
+                    found: outcome.answer.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Writes the answers in the layout [`Answers::parse`] reads, with numeric answers as integers.
+impl Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut table = None;
+        for ((day, part, name), answer) in &self.0 {
+            if table != Some((day, part)) {
+                if table.is_some() {
+                    writeln!(f)?;
+                }
+                writeln!(f, "[day{day}.part{part}]")?;
+                table = Some((day, part));
+            }
+
+            if name
+                .bytes()
+                .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+            {
+                write!(f, "{name} = ")?;
+            } else {
+                write!(f, "{} = ", toml::Value::String(name.clone()))?;
+            }
+            match answer.parse::<i64>() {
+                Ok(number) if number.to_string() == *answer => writeln!(f, "{answer}")?,
+                _ => writeln!(f, "{}", toml::Value::String(answer.clone()))?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Displays mismatches as an aligned table of input, day, part, expected and found answers.
+pub struct DiffTable<'a>(pub &'a [Mismatch]);
+
+impl Display for DiffTable<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let user_width = self
+            .0
+            .iter()
+            .map(|m| m.user.len())
+            .chain([5])
+            .max()
+            .unwrap();
+        let expected_width = self
+            .0
+            .iter()
+            .map(|m| m.expected.len())
+            .chain([8])
+            .max()
+            .unwrap();
+
+        writeln!(
+            f,
+            "{:user_width$}  day  part  {:expected_width$}  found",
+            "input", "expected"
+        )?;
+        for mismatch in self.0 {
+            write!(
+                f,
+                "{:user_width$}  {:>3}  {:>4}  {:expected_width$}  ",
+                mismatch.user, mismatch.day, mismatch.part, mismatch.expected
+            )?;
+            match &mismatch.found {
+                Ok(found) => writeln!(f, "{found}")?,
+                Err(err) => writeln!(f, "error: {err}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use indoc::indoc;
+
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let answers = Answers::parse(indoc! {r#"
+            [day1.part1]
+            local = 12
+            alice = "34"
+
+            [day16.part2]
+            local = 500335179214836
+        "#})
+        .unwrap();
+        assert_eq!(answers.len(), 3);
+        assert_eq!(answers.get(1, 1, "alice"), Some("34"));
+        assert_eq!(answers.get(16, 2, "local"), Some("500335179214836"));
+        assert_eq!(answers.get(1, 2, "local"), None);
+
+        assert_eq!(
+            Answers::parse("[day1.second]\nlocal = 1\n"),
+            Err(AnswersError::Invalid {
+                key: "day1.second".to_owned(),
+                expected: "a `partN` table"
+            })
+        );
+        assert_eq!(
+            Answers::parse("[day1.part1]\nlocal = 1.5\n"),
+            Err(AnswersError::Invalid {
+                key: "day1.part1.local".to_owned(),
+                expected: "an integer or string answer"
+            })
+        );
+        assert!(matches!(
+            Answers::parse("[day1"),
+            Err(AnswersError::Toml(_))
+        ));
+    }
+
+    #[test]
+    fn display() {
+        let mut answers = Answers::default();
+        answers.insert(16, 2, "local", "500335179214836");
+        answers.insert(1, 1, "alice", "34");
+        answers.insert(1, 1, "bob smith", "-x\"y");
+        answers.insert(1, 1, "alice", "12");
+        answers.insert(1, 2, "alice", "007");
+        let text = answers.to_string();
+        assert_eq!(
+            text,
+            indoc! {r#"
+                [day1.part1]
+                alice = 12
+                "bob smith" = '-x"y'
+
+                [day1.part2]
+                alice = "007"
+
+                [day16.part2]
+                local = 500335179214836
+            "#}
+        );
+        assert_eq!(Answers::parse(&text), Ok(answers));
+    }
+
+    #[test]
+    fn real_file() {
+        let answers = Answers::parse(include_str!("../input/2024/answers.toml")).unwrap();
+        assert_eq!(answers.len(), crate::SOLUTIONS.len());
+        assert_eq!(answers.get(3, 1, "local"), Some("182619815"));
+    }
+
+    #[test]
+    fn check() {
+        let answers = Answers::parse("[day1.part1]\nlocal = 12\nalice = 34\n").unwrap();
+        let outcome = |user: &str, answer: Result<&str, ParseError>| Outcome {
+            user: user.to_owned(),
+            day: 1,
+            part: 1,
+            answer: answer.map(str::to_owned),
+            validate_time: Duration::ZERO,
+            solve_time: Duration::ZERO,
+            input_hash: 0,
+            input_size: 0,
+        };
+        let err = ParseError::new(b"x", 0, "digit");
+        let outcomes = [
+            outcome("local", Ok("12")),
+            outcome("alice", Err(err)),
+            outcome("bob", Ok("56")),
+        ];
+        assert_eq!(answers.unchecked(&outcomes), []);
+        assert_eq!(answers.unchecked(&outcomes[1..]), [(1, 1, "local")]);
+
+        let mismatches = answers.check(&outcomes);
+        assert_eq!(
+            mismatches,
+            [Mismatch {
+                user: "alice".to_owned(),
+                day: 1,
+                part: 1,
+                expected: "34".to_owned(),
+                found: Err(err),
+            }]
+        );
+        assert_eq!(
+            DiffTable(&mismatches).to_string(),
+            format!(
+                "input  day  part  expected  found\nalice    1     1  34        error: {err}\n"
+            )
+        );
+    }
+}
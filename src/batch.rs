@@ -0,0 +1,435 @@
+//! Solving every part for many people's inputs at once, to cross-check answers between them.
+
+use std::{
+    fmt::{self, Display, Write as _},
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::{ParseError, Part, SOLUTIONS};
+
+/// A 64-bit FNV-1a hash, which unlike [`std::hash::DefaultHasher`] is stable between builds.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// One person's input for one day.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Input {
+    pub user: String,
+    pub day: u8,
+    pub text: String,
+}
+
+/// Reads every `<user>/2024/dayN.txt` under `root`, sorted by user then day.
+pub fn discover(root: &Path) -> io::Result<Vec<Input>> {
+    let mut inputs = Vec::new();
+    for user in fs::read_dir(root)? {
+        let user = user?;
+        let year = user.path().join("2024");
+        if year.is_dir() {
+            inputs.extend(read_year(&year, &user.file_name().to_string_lossy())?);
+        }
+    }
+
+    inputs.sort_by(|a, b| (&a.user, a.day).cmp(&(&b.user, b.day)));
+    Ok(inputs)
+}
+
+/// Reads the local inputs in `root/2024/dayN.txt` as user `local`, then [`discover`]s the rest.
+pub fn discover_all(root: &Path) -> io::Result<Vec<Input>> {
+    let local = root.join("2024");
+    let mut inputs = if local.is_dir() {
+        read_year(&local, "local")?
+    } else {
+        Vec::new()
+    };
+    inputs.extend(discover(root)?);
+    Ok(inputs)
+}
+
+/// Reads every `dayN.txt` in `dir` as `user`'s inputs, sorted by day.
+pub fn read_year(dir: &Path, user: &str) -> io::Result<Vec<Input>> {
+    let mut inputs = Vec::new();
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        let day = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix("day")?.strip_suffix(".txt"))
+            .and_then(|day| day.parse().ok());
+        if let Some(day) = day {
+            inputs.push(Input {
+                user: user.to_owned(),
+                day,
+                text: fs::read_to_string(&path)?,
+            });
+        }
+    }
+
+    inputs.sort_by_key(|input| input.day);
+    Ok(inputs)
+}
+
+/// The answer to one part for one input, and how long it took.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outcome {
+    pub user: String,
+    pub day: u8,
+    pub part: u8,
+    pub answer: Result<String, ParseError>,
+    /// How long validating the input's structure took, without solving it
+    pub validate_time: Duration,
+    /// How long solving the input took, after checking it
+    pub solve_time: Duration,
+    /// The [`fnv1a`] hash of the input
+    pub input_hash: u64,
+    pub input_size: usize,
+}
+
+/// Solves every part of every input on `threads` threads, returning the outcomes sorted by user,
+/// day then part. Inputs for days without a solver are skipped.
+pub fn solve_all(inputs: &[Input], threads: usize) -> Vec<Outcome> {
+    let jobs: Vec<(&Input, &Part)> = inputs
+        .iter()
+        .flat_map(|input| {
+            SOLUTIONS
+                .iter()
+                .filter(move |part| part.day == input.day)
+                .map(move |part| (input, part))
+        })
+        .collect();
+
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(Vec::with_capacity(jobs.len()));
+    thread::scope(|scope| {
+        for _ in 0..threads.clamp(1, jobs.len().max(1)) {
+            scope.spawn(|| {
+                while let Some(&(input, part)) = jobs.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let (answer, validate_time, solve_time) = part.run(&input.text);
+                    outcomes.lock().unwrap().push(Outcome {
+                        user: input.user.clone(),
+                        day: part.day,
+                        part: part.part,
+                        answer,
+                        validate_time,
+                        solve_time,
+                        input_hash: fnv1a(input.text.as_bytes()),
+                        input_size: input.text.len(),
+                    });
+                }
+            });
+        }
+    });
+
+    let mut outcomes = outcomes.into_inner().unwrap();
+    outcomes.sort_by(|a, b| (&a.user, a.day, a.part).cmp(&(&b.user, b.day, b.part)));
+    outcomes
+}
+
+/// Displays outcomes as an aligned table of user, day, part, answer and times.
+pub struct Table<'a>(pub &'a [Outcome]);
+
+impl Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let answers: Vec<String> = self
+            .0
+            .iter()
+            .map(|outcome| match &outcome.answer {
+                Ok(answer) => answer.clone(),
+                Err(err) => format!("error: {err}"),
+            })
+            .collect();
+        let user_width = self
+            .0
+            .iter()
+            .map(|o| o.user.len())
+            .chain([4])
+            .max()
+            .unwrap();
+        let answer_width = answers.iter().map(String::len).chain([6]).max().unwrap();
+
+        writeln!(
+            f,
+            "{:user_width$}  day  part  {:answer_width$}    validate       solve",
+            "user", "answer"
+        )?;
+        for (outcome, answer) in self.0.iter().zip(&answers) {
+            writeln!(
+                f,
+                "{:user_width$}  {:>3}  {:>4}  {answer:answer_width$}  {:>10?}  {:>10?}",
+                outcome.user, outcome.day, outcome.part, outcome.validate_time, outcome.solve_time
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The fields of each record of [`JsonLines`] and [`Csv`].
+pub const FIELDS: [&str; 9] = [
+    "user",
+    "day",
+    "part",
+    "answer",
+    "error",
+    "validate_ns",
+    "solve_ns",
+    "input_hash",
+    "input_size",
+];
+
+impl Outcome {
+    /// The values of [`FIELDS`], with `None` for a missing answer or error, and whether each is a
+    /// string
+    fn fields(&self) -> [(Option<String>, bool); 9] {
+        let (answer, error) = match &self.answer {
+            Ok(answer) => (Some(answer.clone()), None),
+            Err(err) => (None, Some(err.to_string())),
+        };
+        [
+            (Some(self.user.clone()), true),
+            (Some(self.day.to_string()), false),
+            (Some(self.part.to_string()), false),
+            (answer, true),
+            (error, true),
+            (Some(self.validate_time.as_nanos().to_string()), false),
+            (Some(self.solve_time.as_nanos().to_string()), false),
+            (Some(format!("{:016x}", self.input_hash)), true),
+            (Some(self.input_size.to_string()), false),
+        ]
+    }
+}
+
+/// Displays outcomes as JSON Lines, one object per outcome with the keys in [`FIELDS`].
+pub struct JsonLines<'a>(pub &'a [Outcome]);
+
+impl Display for JsonLines<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for outcome in self.0 {
+            write!(f, "{{")?;
+            for (i, (key, (value, string))) in FIELDS.iter().zip(outcome.fields()).enumerate() {
+                let sep = if i == 0 { "" } else { "," };
+                match value {
+                    None => write!(f, "{sep}\"{key}\":null")?,
+                    Some(value) if string => {
+                        write!(f, "{sep}\"{key}\":")?;
+                        json_string(f, &value)?;
+                    }
+                    Some(value) => write!(f, "{sep}\"{key}\":{value}")?,
+                }
+            }
+            writeln!(f, "}}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes `value` as a JSON string, escaping quotes, backslashes and control characters.
+fn json_string(f: &mut fmt::Formatter<'_>, value: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in value.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Displays outcomes as CSV with a header of [`FIELDS`], leaving a missing answer or error empty.
+pub struct Csv<'a>(pub &'a [Outcome]);
+
+impl Display for Csv<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", FIELDS.join(","))?;
+        for outcome in self.0 {
+            for (i, (value, _)) in outcome.fields().into_iter().enumerate() {
+                let sep = if i == 0 { "" } else { "," };
+                let value = value.unwrap_or_default();
+                if value.contains([',', '"', '\n']) {
+                    write!(f, "{sep}\"{}\"", value.replace('"', "\"\""))?;
+                } else {
+                    write!(f, "{sep}{value}")?;
+                }
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(user: &str, day: u8, text: &str) -> Input {
+        Input {
+            user: user.to_owned(),
+            day,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn solve_all_matches_serial() {
+        let inputs = [
+            input("b", 7, include_str!("../input/2024/day7.txt")),
+            input("a", 5, include_str!("../input/2024/day5.txt")),
+            input("a", 14, include_str!("../input/2024/day14.txt")),
+            input("b", 5, include_str!("../input/2024/day5.txt")),
+            input("c", 3, "not an input"),
+            input("c", 25, "no solver"),
+        ];
+
+        let outcomes = solve_all(&inputs, 4);
+        let order: Vec<_> = outcomes
+            .iter()
+            .map(|o| (o.user.as_str(), o.day, o.part))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("a", 5, 1),
+                ("a", 5, 2),
+                ("a", 14, 1),
+                ("a", 14, 2),
+                ("b", 5, 1),
+                ("b", 5, 2),
+                ("b", 7, 1),
+                ("b", 7, 2),
+                ("c", 3, 1),
+                ("c", 3, 2),
+            ]
+        );
+
+        let answers_of = |outcomes: &[Outcome]| -> Vec<_> {
+            outcomes.iter().map(|o| o.answer.clone()).collect()
+        };
+        let answers = answers_of(&outcomes);
+        assert_eq!(answers, answers_of(&solve_all(&inputs, 1)));
+        assert_eq!(answers[0], Ok("5391".to_owned()));
+        assert_eq!(answers[4], answers[0]);
+        assert_eq!(answers[7], Ok("500335179214836".to_owned()));
+        assert_eq!(answers[8], Ok("0".to_owned()));
+    }
+
+    #[test]
+    fn discovers_user_inputs() {
+        let root = std::env::temp_dir().join(format!("aoc-batch-{}", std::process::id()));
+        fs::create_dir_all(root.join("bob/2024")).unwrap();
+        fs::create_dir_all(root.join("alice/2024")).unwrap();
+        fs::create_dir_all(root.join("alice/2023")).unwrap();
+        fs::write(root.join("bob/2024/day10.txt"), "b10").unwrap();
+        fs::write(root.join("bob/2024/day2.txt"), "b2").unwrap();
+        fs::write(root.join("bob/2024/notes.md"), "").unwrap();
+        fs::write(root.join("alice/2024/day1.txt"), "a1").unwrap();
+        fs::write(root.join("alice/2023/day1.txt"), "old").unwrap();
+
+        let inputs = discover(&root).unwrap();
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(
+            inputs,
+            [
+                input("alice", 1, "a1"),
+                input("bob", 2, "b2"),
+                input("bob", 10, "b10"),
+            ]
+        );
+    }
+
+    fn outcomes() -> [Outcome; 2] {
+        [
+            Outcome {
+                user: "alice".to_owned(),
+                day: 3,
+                part: 1,
+                answer: Ok("42".to_owned()),
+                validate_time: Duration::from_nanos(1_500),
+                solve_time: Duration::from_micros(12),
+                input_hash: 0xabc,
+                input_size: 100,
+            },
+            Outcome {
+                user: "bob".to_owned(),
+                day: 16,
+                part: 2,
+                answer: Err(ParseError::new(b"1,x", 2, "digit")),
+                validate_time: Duration::from_nanos(20),
+                solve_time: Duration::from_nanos(800),
+                input_hash: fnv1a(b"1,x"),
+                input_size: 3,
+            },
+        ]
+    }
+
+    #[test]
+    fn table() {
+        assert_eq!(
+            Table(&outcomes()).to_string(),
+            "user   day  part  answer                                                         validate       solve\n\
+             alice    3     1  42                                                                1.5µs        12µs\n\
+             bob     16     2  error: line 1, column 3 (byte 2): expected digit, found 'x'        20ns       800ns\n"
+        );
+    }
+
+    #[test]
+    fn fnv1a_is_stable() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn json_lines() {
+        assert_eq!(
+            JsonLines(&outcomes()).to_string(),
+            format!(
+                "{{\"user\":\"alice\",\"day\":3,\"part\":1,\"answer\":\"42\",\"error\":null,\"validate_ns\":1500,\
+                 \"solve_ns\":12000,\"input_hash\":\"0000000000000abc\",\"input_size\":100}}\n\
+                 {{\"user\":\"bob\",\"day\":16,\"part\":2,\"answer\":null,\"error\":\"line 1, column 3 (byte 2): \
+                 expected digit, found 'x'\",\"validate_ns\":20,\"solve_ns\":800,\"input_hash\":\"{:016x}\",\"input_size\":3}}\n",
+                fnv1a(b"1,x")
+            )
+        );
+    }
+
+    #[test]
+    fn json_escapes() {
+        let [mut outcome, _] = outcomes();
+        outcome.user = "é \"quoted\" back\\slash\ttab\nline\u{1b}[0m\u{7f}\u{85}".to_owned();
+        assert_eq!(
+            JsonLines(&[outcome]).to_string(),
+            "{\"user\":\"é \\\"quoted\\\" back\\\\slash\\ttab\\nline\\u001b[0m\\u007f\\u0085\",\
+             \"day\":3,\"part\":1,\"answer\":\"42\",\"error\":null,\"validate_ns\":1500,\"solve_ns\":12000,\
+             \"input_hash\":\"0000000000000abc\",\"input_size\":100}\n"
+        );
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            Csv(&outcomes()).to_string(),
+            format!(
+                "user,day,part,answer,error,validate_ns,solve_ns,input_hash,input_size\n\
+                 alice,3,1,42,,1500,12000,0000000000000abc,100\n\
+                 bob,16,2,,\"line 1, column 3 (byte 2): expected digit, found 'x'\",20,800,{:016x},3\n",
+                fnv1a(b"1,x")
+            )
+        );
+    }
+}
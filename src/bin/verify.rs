@@ -0,0 +1,73 @@
+//! Solves every input with a known answer in `input/2024/answers.toml`, exiting with an error and
+//! a table of differences if any answer doesn't match.
+//!
+//! usage: `verify [DIR] [--threads N] [--allow-missing]`
+//!
+//! `DIR` (default `input`) holds `2024/answers.toml`, the local inputs in `2024/dayN.txt`, and
+//! other people's in `<user>/2024/dayN.txt`. A known answer whose input is missing is an error
+//! unless `--allow-missing` is given.
+
+use std::{fs, path::PathBuf, process::ExitCode, thread};
+
+use aoc_2024::{
+    answers::{Answers, DiffTable},
+    batch,
+};
+
+fn run() -> Result<bool, String> {
+    let mut args = std::env::args().skip(1);
+    let mut root = PathBuf::from("input");
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut allow_missing = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                threads = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--threads needs a number")?;
+            }
+            "--allow-missing" => allow_missing = true,
+            _ => root = PathBuf::from(arg),
+        }
+    }
+
+    let path = root.join("2024/answers.toml");
+    let answers = fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|text| Answers::parse(&text).map_err(|err| err.to_string()))
+        .map_err(|err| format!("reading {}: {err}", path.display()))?;
+
+    let mut inputs = batch::discover_all(&root).map_err(|err| format!("reading inputs: {err}"))?;
+    inputs.retain(|input| (1..=2).any(|part| answers.get(input.day, part, &input.user).is_some()));
+
+    let outcomes = batch::solve_all(&inputs, threads);
+    let mismatches = answers.check(&outcomes);
+    let missing = answers.unchecked(&outcomes);
+    println!(
+        "checked {} of {} known answers: {} mismatched, {} missing their input",
+        answers.len() - missing.len(),
+        answers.len(),
+        mismatches.len(),
+        missing.len()
+    );
+
+    if !mismatches.is_empty() {
+        print!("{}", DiffTable(&mismatches));
+    }
+    for (day, part, user) in &missing {
+        println!("missing input: day {day} part {part} for {user}");
+    }
+    Ok(mismatches.is_empty() && (allow_missing || missing.is_empty()))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
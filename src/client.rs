@@ -0,0 +1,432 @@
+//! Talking to an Advent of Code compatible server, to download inputs and submit answers.
+//!
+//! The base URL is configurable so tests and CI can use a local server rather than the real site.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+/// The real site, used unless another base URL is given.
+pub const DEFAULT_BASE_URL: &str = "https://adventofcode.com";
+
+/// A request to the server failed, or its result couldn't be cached.
+#[derive(Debug)]
+pub enum ClientError {
+    Http(ureq::Error),
+    /// The server answered with an error status, e.g. 400 for a missing or expired session, or
+    /// 404 for a puzzle that isn't unlocked yet
+    Status(u16),
+    Io(io::Error),
+    /// The cached file at this path differs from what the server sent
+    Differs(PathBuf),
+    /// The server's answer to a submission didn't say whether it was right
+    UnknownFeedback,
+}
+
+impl From<ureq::Error> for ClientError {
+    fn from(err: ureq::Error) -> Self {
+        Self::Http(err)
+    }
+}
+
+impl From<io::Error> for ClientError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Http(err) => write!(f, "{err}"),
+            Self::Status(status) => write!(f, "the server answered with status {status}"),
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Differs(path) => write!(
+                f,
+                "{} differs from the server's input, so wasn't overwritten",
+                path.display()
+            ),
+            Self::UnknownFeedback => {
+                write!(f, "the server's answer didn't say whether it was right")
+            }
+        }
+    }
+}
+
+impl Error for ClientError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Http(err) => Some(err),
+            Self::Io(err) => Some(err),
+            Self::Status(_) | Self::Differs(_) | Self::UnknownFeedback => None,
+        }
+    }
+}
+
+/// A logged in session with a server.
+pub struct Client {
+    base_url: String,
+    session: String,
+    agent: ureq::Agent,
+}
+
+impl Client {
+    /// Talks to the server at `base_url`, e.g. [`DEFAULT_BASE_URL`], with the `session` cookie
+    /// of a logged in browser.
+    pub fn new(base_url: &str, session: &str) -> Self {
+        let agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .user_agent(concat!(
+                env!("CARGO_PKG_NAME"),
+                "/",
+                env!("CARGO_PKG_VERSION")
+            ))
+            .build()
+            .into();
+
+        Self {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            session: session.to_owned(),
+            agent,
+        }
+    }
+
+    /// Downloads the input for `day` of `year`.
+    pub fn input(&self, year: u16, day: u8) -> Result<String, ClientError> {
+        let response = self
+            .agent
+            .get(format!("{}/{year}/day/{day}/input", self.base_url))
+            .header("Cookie", format!("session={}", self.session))
+            .call()?;
+        Self::text(response)
+    }
+
+    /// Submits `answer` for part `part` of `day` of `year`.
+    pub fn submit(
+        &self,
+        year: u16,
+        day: u8,
+        part: u8,
+        answer: &str,
+    ) -> Result<Feedback, ClientError> {
+        let response = self
+            .agent
+            .post(format!("{}/{year}/day/{day}/answer", self.base_url))
+            .header("Cookie", format!("session={}", self.session))
+            .send_form([("level", part.to_string().as_str()), ("answer", answer)])?;
+        Feedback::parse(&Self::text(response)?).ok_or(ClientError::UnknownFeedback)
+    }
+
+    fn text(mut response: ureq::http::Response<ureq::Body>) -> Result<String, ClientError> {
+        let status = response.status().as_u16();
+        if status != 200 {
+            return Err(ClientError::Status(status));
+        }
+        Ok(response.body_mut().read_to_string()?)
+    }
+}
+
+/// What the server made of a submitted answer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Right,
+    /// Wrong, without a hint
+    Wrong,
+    TooHigh,
+    TooLow,
+    /// Not checked, as the last answer was too recent
+    TooRecent,
+    /// Not checked, as the part is already solved or not unlocked yet
+    WrongLevel,
+}
+
+impl Verdict {
+    /// Whether the answer is known to be wrong
+    pub fn is_wrong(self) -> bool {
+        matches!(self, Self::Wrong | Self::TooHigh | Self::TooLow)
+    }
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Right => "right",
+            Self::Wrong => "wrong",
+            Self::TooHigh => "too high",
+            Self::TooLow => "too low",
+            Self::TooRecent => "too recent",
+            Self::WrongLevel => "wrong level",
+        })
+    }
+}
+
+impl FromStr for Verdict {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "right" => Self::Right,
+            "wrong" => Self::Wrong,
+            "too high" => Self::TooHigh,
+            "too low" => Self::TooLow,
+            "too recent" => Self::TooRecent,
+            "wrong level" => Self::WrongLevel,
+            _ => return Err(()),
+        })
+    }
+}
+
+/// The server's answer to a submission.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Feedback {
+    pub verdict: Verdict,
+    /// How long until the server will take another answer, if it said
+    pub wait: Option<Duration>,
+}
+
+impl Feedback {
+    /// Reads the feedback from the page the server sends back after a submission.
+    pub fn parse(page: &str) -> Option<Self> {
+        let verdict = if page.contains("That's the right answer") {
+            Verdict::Right
+        } else if page.contains("That's not the right answer") {
+            if page.contains("your answer is too high") {
+                Verdict::TooHigh
+            } else if page.contains("your answer is too low") {
+                Verdict::TooLow
+            } else {
+                Verdict::Wrong
+            }
+        } else if page.contains("You gave an answer too recently") {
+            Verdict::TooRecent
+        } else if page.contains("You don't seem to be solving the right level") {
+            Verdict::WrongLevel
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            verdict,
+            wait: Self::wait(page),
+        })
+    }
+
+    /// Reads "You have 1m 30s left to wait" or "Please wait 5 minutes before trying again"
+    fn wait(page: &str) -> Option<Duration> {
+        if let Some((_, rest)) = page.split_once("You have ") {
+            let (left, _) = rest.split_once(" left to wait")?;
+            let mut secs = 0;
+            for amount in left.split_whitespace() {
+                let (n, unit) = amount.split_at(amount.find(|c: char| !c.is_ascii_digit())?);
+                let n: u64 = n.parse().ok()?;
+                secs += n * match unit {
+                    "h" => 3600,
+                    "m" => 60,
+                    "s" => 1,
+                    _ => return None,
+                };
+            }
+            return Some(Duration::from_secs(secs));
+        }
+
+        let (_, rest) = page.split_once("lease wait ")?;
+        let (n, rest) = rest.split_once(' ')?;
+        let n = match n {
+            "one" => 1,
+            n => n.parse().ok()?,
+        };
+        rest.starts_with("minute")
+            .then(|| Duration::from_secs(60 * n))
+    }
+}
+
+/// Where [`fetch`] got an input from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fetched {
+    /// The server, as it wasn't cached
+    Downloaded,
+    /// The cache, without contacting the server
+    Cached,
+    /// The server, matching the cache
+    Unchanged,
+}
+
+/// Caches the input for `day` of `year` in `root/<year>/day<day>.txt`, downloading it unless
+/// it's already there. With `refresh`, a cached input is downloaded again to check it matches.
+pub fn fetch(
+    client: &Client,
+    root: &Path,
+    year: u16,
+    day: u8,
+    refresh: bool,
+) -> Result<Fetched, ClientError> {
+    let dir = root.join(year.to_string());
+    let path = dir.join(format!("day{day}.txt"));
+    let cached = match fs::read_to_string(&path) {
+        Ok(cached) => Some(cached),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err.into()),
+    };
+    if cached.is_some() && !refresh {
+        return Ok(Fetched::Cached);
+    }
+
+    let input = client.input(year, day)?;
+    match cached {
+        Some(cached) if cached == input => Ok(Fetched::Unchanged),
+        Some(_) => Err(ClientError::Differs(path)),
+        None => {
+            fs::create_dir_all(&dir)?;
+            fs::write(&path, input)?;
+            Ok(Fetched::Downloaded)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use super::*;
+
+    /// Serves one `(status, body)` response per connection on a local port, returning the base
+    /// URL and a handle to the requests received
+    pub(crate) fn serve(responses: Vec<(u16, &'static str)>) -> (String, JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let mut request = String::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            length = value.trim().parse().unwrap();
+                        }
+                    }
+                    request.push_str(&line);
+                    if line == "\r\n" {
+                        break;
+                    }
+                }
+                let mut content = vec![0; length];
+                reader.read_exact(&mut content).unwrap();
+                request.push_str(&String::from_utf8(content).unwrap());
+                requests.push(request);
+
+                write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+            }
+            requests
+        });
+
+        (base_url, server)
+    }
+
+    #[test]
+    fn input() {
+        let (base_url, server) = serve(vec![(200, "1   2\n"), (400, "log in")]);
+        let client = Client::new(&format!("{base_url}/"), "abc");
+        assert_eq!(client.input(2024, 1).unwrap(), "1   2\n");
+        assert!(matches!(
+            client.input(2024, 2),
+            Err(ClientError::Status(400))
+        ));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("GET /2024/day/1/input HTTP/1.1\r\n"));
+        assert!(requests[0].contains("\r\ncookie: session=abc\r\n"));
+        assert!(requests[1].starts_with("GET /2024/day/2/input HTTP/1.1\r\n"));
+    }
+
+    #[test]
+    fn feedback() {
+        let feedback = |page| Feedback::parse(page).map(|f| (f.verdict, f.wait));
+        assert_eq!(
+            feedback("<p>That's the right answer! You are one gold star closer.</p>"),
+            Some((Verdict::Right, None))
+        );
+        assert_eq!(
+            feedback(
+                "<p>That's not the right answer; your answer is too low. Please wait 5 minutes \
+                 before trying again.</p>"
+            ),
+            Some((Verdict::TooLow, Some(Duration::from_secs(300))))
+        );
+        assert_eq!(
+            feedback(
+                "<p>That's not the right answer. Please wait one minute before trying again.</p>"
+            ),
+            Some((Verdict::Wrong, Some(Duration::from_secs(60))))
+        );
+        assert_eq!(
+            feedback("<p>You gave an answer too recently. You have 1m 5s left to wait.</p>"),
+            Some((Verdict::TooRecent, Some(Duration::from_secs(65))))
+        );
+        assert_eq!(
+            feedback("<p>You don't seem to be solving the right level.</p>"),
+            Some((Verdict::WrongLevel, None))
+        );
+        assert_eq!(feedback("<p>Puzzle inputs differ by user.</p>"), None);
+
+        for verdict in [Verdict::Right, Verdict::TooHigh, Verdict::WrongLevel] {
+            assert_eq!(verdict.to_string().parse(), Ok(verdict));
+        }
+    }
+
+    #[test]
+    fn caches() {
+        let root = std::env::temp_dir().join(format!("aoc-fetch-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let (base_url, server) = serve(vec![(200, "1\n"), (200, "1\n"), (200, "2\n")]);
+        let client = Client::new(&base_url, "abc");
+
+        assert_eq!(
+            fetch(&client, &root, 2024, 3, false).unwrap(),
+            Fetched::Downloaded
+        );
+        assert_eq!(
+            fs::read_to_string(root.join("2024/day3.txt")).unwrap(),
+            "1\n"
+        );
+        assert_eq!(
+            fetch(&client, &root, 2024, 3, false).unwrap(),
+            Fetched::Cached
+        );
+        assert_eq!(
+            fetch(&client, &root, 2024, 3, true).unwrap(),
+            Fetched::Unchanged
+        );
+        assert!(matches!(
+            fetch(&client, &root, 2024, 3, true),
+            Err(ClientError::Differs(path)) if path == root.join("2024/day3.txt")
+        ));
+        assert_eq!(
+            fs::read_to_string(root.join("2024/day3.txt")).unwrap(),
+            "1\n"
+        );
+
+        // The cached fetch didn't contact the server
+        assert_eq!(server.join().unwrap().len(), 3);
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
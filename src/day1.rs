@@ -1,24 +1,22 @@
 use std::{
     cmp::Ordering,
-    hint::unreachable_unchecked,
-    mem::MaybeUninit,
     simd::{num::SimdInt as _, Simd},
 };
 
 use aoc_runner_derive::aoc;
 
-use crate::Assume as _;
+use crate::{
+    digits, ensure, Assume as _, Checked, InputError, InputShape, Mode, ParseError, Unchecked, EOL,
+};
 
-/// Number of datapoints expected
-const DATA_COUNT: usize = if cfg!(test) { 6 } else { 1_000 };
-/// Number of digits for each pair of numbers in each datapoint
-const NUM_DIGIT_COUNT: usize = if cfg!(test) { 1 } else { 5 };
-/// Number of characters separating the pair of numbers in each datapoint
+/// Number of digits in each number on a standard line, which [`parse`] handles
+const NUM_DIGIT_COUNT: usize = 5;
+/// Number of characters separating the pair of numbers on a standard line
 const SEP_CHAR_COUNT: usize = 3;
 
-/// The length of each line in the data, not including the newline character
+/// The length of a standard line, not including the newline character
 const LINE_LENGTH: usize = NUM_DIGIT_COUNT + SEP_CHAR_COUNT + NUM_DIGIT_COUNT;
-/// The position in each line where the second of the numbers starts
+/// The position in a standard line where the second of the numbers starts
 const NUM2_START: usize = NUM_DIGIT_COUNT + SEP_CHAR_COUNT;
 
 // SWAR
@@ -27,23 +25,40 @@ const MASK: u64 = 0x000000FF000000FF;
 const MUL1: u64 = 0x000F424000000064;
 const MUL2: u64 = 0x0000271000000001;
 
+/// Checks every line of `input` is a pair of numbers separated by spaces, as [`part1`] and
+/// [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let shape = InputShape::of(input.as_bytes())?;
+    input_handling::<Checked>(input, &mut Vec::new(), &mut Vec::new())?;
+
+    Ok(shape)
+}
+
 #[aoc(day1, part1)]
 pub fn part1(input: &str) -> i32 {
-    let (mut left, mut right) = input_handling(input);
-    left.sort_unstable();
-    right.sort_unstable();
+    Solver::new().part1(input)
+}
 
-    simd_count(&left, &right) + iter_count(&left, &right)
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part1(input)
+}
+
+/// The parsing phase shared by [`part1`] and [`part2`], reading the left and right lists.
+pub fn parse_lists(input: &str) -> (Vec<i32>, Vec<i32>) {
+    let mut solver = Solver::new();
+    solver.parse_lists(input);
+    (solver.left, solver.right)
 }
 
 // For profiling
-fn simd_count(left: &[i32; DATA_COUNT], right: &[i32; DATA_COUNT]) -> i32 {
+#[inline]
+fn simd_count(left: &[i32], right: &[i32]) -> i32 {
     let mut sum = 0;
 
-    for count in 0..(DATA_COUNT / 64) {
-        let min = count * 64;
-        let left = Simd::<_, 64>::from_slice(&left[min..]);
-        let right = Simd::<_, 64>::from_slice(&right[min..]);
+    for (left, right) in left.chunks_exact(64).zip(right.chunks_exact(64)) {
+        let left = Simd::<_, 64>::from_slice(left);
+        let right = Simd::<_, 64>::from_slice(right);
 
         sum += (left - right).abs().reduce_sum()
     }
@@ -52,113 +67,205 @@ fn simd_count(left: &[i32; DATA_COUNT], right: &[i32; DATA_COUNT]) -> i32 {
 }
 
 // For profiling
-fn iter_count(left: &[i32; DATA_COUNT], right: &[i32; DATA_COUNT]) -> i32 {
-    left.iter()
-        .zip(right)
-        .skip((DATA_COUNT / 64) * 64)
+#[inline]
+fn iter_count(left: &[i32], right: &[i32]) -> i32 {
+    let start = left.len() / 64 * 64;
+    left[start..]
+        .iter()
+        .zip(&right[start..])
         .map(|(left, right)| (left - right).abs())
         .sum()
 }
 
 #[aoc(day1, part2)]
 pub fn part2(input: &str) -> i32 {
-    let (mut left, mut right) = input_handling(input);
-    left.sort_unstable();
-    right.sort_unstable();
-    let left = &mut left.into_iter();
-    let right = &mut right.into_iter();
-
-    let mut similarity = 0;
-    let mut curr_left_similarity = 0;
-    let mut curr_left = left.next().assume();
-    let mut curr_right = right.next().assume();
-
-    loop {
-        match curr_left.cmp(&curr_right) {
-            Ordering::Less => {
-                let mut new_left = Some(curr_left);
-                while new_left == Some(curr_left) {
-                    similarity += curr_left_similarity;
-                    new_left = left.next();
-                }
+    Solver::new().part2(input)
+}
 
-                curr_left_similarity = 0;
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part2(input)
+}
 
-                if let Some(new_left) = new_left {
-                    curr_left = new_left;
-                } else {
-                    break;
-                }
-            }
-            Ordering::Greater => {
-                if let Some(new_right) = right.next() {
-                    curr_right = new_right;
-                } else {
-                    break;
-                }
-            }
-            Ordering::Equal => {
-                curr_left_similarity += curr_left;
-                if let Some(new_right) = right.next() {
-                    curr_right = new_right;
-                } else {
-                    break;
-                }
-            }
-        }
+/// Solves day 1, keeping the left and right lists in buffers it reuses between inputs
+#[derive(Clone, Debug, Default)]
+pub struct Solver {
+    left: Vec<i32>,
+    right: Vec<i32>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    similarity + curr_left_similarity
-}
+    pub fn part1(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| self.inner_p1::<Unchecked>(input).assume())
+    }
 
-fn input_handling(input: &str) -> ([i32; DATA_COUNT], [i32; DATA_COUNT]) {
-    let input = input.as_bytes();
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| self.inner_p1::<Checked>(input))
+    }
 
-    let mut left = [MaybeUninit::uninit(); DATA_COUNT];
-    let mut right = [MaybeUninit::uninit(); DATA_COUNT];
+    pub fn part2(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| self.inner_p2::<Unchecked>(input).assume())
+    }
 
-    let chunks = &mut input.chunks_exact(LINE_LENGTH + 1);
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| self.inner_p2::<Checked>(input))
+    }
 
-    for (index, line) in chunks.enumerate() {
-        // Strip new line character
-        let line = &line[..LINE_LENGTH];
-        let num1 = parse(&line[..NUM_DIGIT_COUNT]);
-        let num2 = parse(&line[NUM2_START..]);
+    /// The parsing phase of [`Solver::part1`] and [`Solver::part2`], reading the left and right
+    /// lists into the solver's buffers.
+    pub fn parse_lists(&mut self, input: &str) {
+        crate::dispatch(|| {
+            input_handling::<Unchecked>(input, &mut self.left, &mut self.right).assume()
+        })
+    }
 
-        left[index].write(num1);
-        right[index].write(num2);
+    /// Reads the left and right lists, sorted
+    #[inline(always)]
+    fn read<M: Mode>(&mut self, input: &str) -> Result<(), ParseError> {
+        input_handling::<M>(input, &mut self.left, &mut self.right)?;
+        self.left.sort_unstable();
+        self.right.sort_unstable();
+        Ok(())
     }
 
-    // End '\n' might be stripped
-    let remainder: Option<[_; LINE_LENGTH]> = chunks.remainder().try_into().ok();
-    if let Some(line) = remainder {
-        let num1 = parse(&line[..NUM_DIGIT_COUNT]);
-        let num2 = parse(&line[NUM2_START..]);
+    #[inline(always)]
+    fn inner_p1<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
+        self.read::<M>(input)?;
+        Ok(simd_count(&self.left, &self.right) + iter_count(&self.left, &self.right))
+    }
 
-        left.last_mut().assume().write(num1);
-        right.last_mut().assume().write(num2);
-    } else {
-        debug_assert!(
-            chunks.remainder().is_empty(),
-            "Remainder: {}",
-            std::str::from_utf8(chunks.remainder()).unwrap()
-        );
+    #[inline(always)]
+    fn inner_p2<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
+        self.read::<M>(input)?;
+        let left = &mut self.left.iter().copied();
+        let right = &mut self.right.iter().copied();
+
+        let mut similarity = 0;
+        let mut curr_left_similarity = 0;
+        let (Some(mut curr_left), Some(mut curr_right)) = (left.next(), right.next()) else {
+            return Ok(0);
+        };
+
+        loop {
+            match curr_left.cmp(&curr_right) {
+                Ordering::Less => {
+                    let mut new_left = Some(curr_left);
+                    while new_left == Some(curr_left) {
+                        similarity += curr_left_similarity;
+                        new_left = left.next();
+                    }
+
+                    curr_left_similarity = 0;
+
+                    if let Some(new_left) = new_left {
+                        curr_left = new_left;
+                    } else {
+                        break;
+                    }
+                }
+                Ordering::Greater => {
+                    if let Some(new_right) = right.next() {
+                        curr_right = new_right;
+                    } else {
+                        break;
+                    }
+                }
+                Ordering::Equal => {
+                    curr_left_similarity += curr_left;
+                    if let Some(new_right) = right.next() {
+                        curr_right = new_right;
+                    } else {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(similarity + curr_left_similarity)
+    }
+}
+
+/// Checks the line starting at `start` is two numbers separated by spaces
+#[inline(always)]
+fn check_line<M: Mode>(input: &[u8], start: usize) -> Result<(), ParseError> {
+    digits::<M>(input, start, NUM_DIGIT_COUNT)?;
+    for pos in start + NUM_DIGIT_COUNT..start + NUM2_START {
+        ensure!(M, input.get(pos) == Some(&b' '), input, pos, "' '");
     }
+    digits::<M>(input, start + NUM2_START, NUM_DIGIT_COUNT)
+}
+
+/// Whether the line starting at `start` has the standard layout, so [`parse`] can read it
+#[inline(always)]
+fn is_standard(input: &[u8], start: usize) -> bool {
+    let end = start + LINE_LENGTH;
+    input.get(end).map_or(end == input.len(), |&c| c == EOL)
+        && input[start + NUM_DIGIT_COUNT - 1].is_ascii_digit()
+        && input[start + NUM_DIGIT_COUNT..start + NUM2_START] == [b' '; SEP_CHAR_COUNT]
+        && input[start + NUM2_START].is_ascii_digit()
+        && input[end - 1].is_ascii_digit()
+}
+
+#[inline(always)]
+fn input_handling<M: Mode>(
+    input: &str,
+    left: &mut Vec<i32>,
+    right: &mut Vec<i32>,
+) -> Result<(), ParseError> {
+    let input = input.as_bytes();
 
-    unsafe {
-        (
-            MaybeUninit::array_assume_init(left),
-            MaybeUninit::array_assume_init(right),
-        )
+    let capacity = input.len() / (LINE_LENGTH + 1) + 1;
+    left.clear();
+    right.clear();
+    left.reserve(capacity);
+    right.reserve(capacity);
+
+    let mut pos = 0;
+    while pos < input.len() {
+        if is_standard(input, pos) {
+            check_line::<M>(input, pos)?;
+            let line = &input[pos..pos + LINE_LENGTH];
+            left.push(parse(line[..NUM_DIGIT_COUNT].try_into().assume()));
+            right.push(parse(line[NUM2_START..].try_into().assume()));
+            pos += LINE_LENGTH + 1;
+        } else {
+            let (num1, end) = parse_general::<M>(input, pos)?;
+            pos = end;
+            while input.get(pos) == Some(&b' ') {
+                pos += 1;
+            }
+            ensure!(M, pos > end, input, pos, "' '");
+
+            let (num2, end) = parse_general::<M>(input, pos)?;
+            // End '\n' might be stripped
+            ensure!(
+                M,
+                matches!(input.get(end), None | Some(&EOL)),
+                input,
+                end,
+                "'\\n'"
+            );
+
+            left.push(num1);
+            right.push(num2);
+            pos = end + 1;
+        }
     }
+
+    Ok(())
 }
 
-fn parse(s: &[u8]) -> i32 {
-    let arr = match s {
-        [num] => [b'0', b'0', b'0', b'0', b'0', b'0', b'0', *num],
-        [n1, n2, n3, n4, n5] => [b'0', b'0', b'0', *n1, *n2, *n3, *n4, *n5],
-        _ => unsafe { unreachable_unchecked() },
-    };
+/// Parses a standard width number with SWAR
+#[inline]
+fn parse(s: [u8; NUM_DIGIT_COUNT]) -> i32 {
+    let [n1, n2, n3, n4, n5] = s;
+    let arr = [b'0', b'0', b'0', n1, n2, n3, n4, n5];
 
     let mut val = u64::from_le_bytes(arr);
     val -= ALL_0;
@@ -168,6 +275,30 @@ fn parse(s: &[u8]) -> i32 {
     val as i32
 }
 
+/// Parses a number of any width starting at `start`, returning it and the offset just past it
+#[inline]
+fn parse_general<M: Mode>(input: &[u8], start: usize) -> Result<(i32, usize), ParseError> {
+    let mut val: i32 = 0;
+    let mut pos = start;
+    while let Some(&c @ b'0'..=b'9') = input.get(pos) {
+        let next = val
+            .checked_mul(10)
+            .and_then(|val| val.checked_add((c - b'0') as i32));
+        ensure!(
+            M,
+            next.is_some(),
+            input,
+            start,
+            "a number that fits in an i32"
+        );
+        val = next.unwrap_or_default();
+        pos += 1;
+    }
+    ensure!(M, pos > start, input, pos, "digit");
+
+    Ok((val, pos))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,6 +320,14 @@ mod tests {
         assert_eq!(part2(INPUT), 31)
     }
 
+    #[test]
+    fn example_scalar() {
+        crate::with_scalar(|| {
+            assert_eq!(part1(INPUT), 11);
+            assert_eq!(part2(INPUT), 31);
+        });
+    }
+
     #[test]
     fn swar_test() {
         for (input, result) in [
@@ -203,4 +342,90 @@ mod tests {
             assert_eq!(val, result);
         }
     }
+
+    #[test]
+    fn checked() {
+        assert_eq!(try_part1(INPUT), Ok(11));
+        assert_eq!(try_part2(INPUT), Ok(31));
+
+        let err = try_part1(&INPUT.replace("2   5", "2  x5")).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (3, 4, "digit", Some(b'x'))
+        );
+
+        let err = try_part2(&INPUT.replace("3   9", "3   9 ")).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (5, 6, "'\\n'", Some(b' '))
+        );
+    }
+
+    #[test]
+    fn validate_example() {
+        let input = format!("{INPUT}\n");
+        assert_eq!(
+            validate(&input),
+            Ok(InputShape {
+                lines: 6,
+                width: Some(5),
+                records: 6
+            })
+        );
+        assert_eq!(validate(INPUT), Err(InputError::MissingTrailingNewline));
+    }
+
+    const REAL_INPUT: &str = include_str!("../input/2024/day1.txt");
+
+    #[test]
+    fn real() {
+        assert_eq!(part1(REAL_INPUT), 1_938_424);
+        assert_eq!(part2(REAL_INPUT), 22_014_209);
+    }
+
+    #[test]
+    fn real_scalar() {
+        crate::with_scalar(|| {
+            assert_eq!(part1(REAL_INPUT), 1_938_424);
+            assert_eq!(part2(REAL_INPUT), 22_014_209);
+        });
+    }
+
+    #[test]
+    fn solver_reuse() {
+        let mut solver = Solver::new();
+        assert_eq!(solver.part1(REAL_INPUT), 1_938_424);
+        assert_eq!(solver.part2(INPUT), 31);
+        assert!(solver.try_part2("1   x\n").is_err());
+        assert_eq!(solver.part2(REAL_INPUT), 22_014_209);
+        assert_eq!(solver.part1(INPUT), 11);
+        assert_eq!(
+            parse_lists(INPUT),
+            (vec![3, 4, 2, 1, 3, 3], vec![4, 3, 5, 3, 9, 3])
+        );
+    }
+
+    #[test]
+    fn mixed_widths() {
+        let input = "3   4\n400000 3\n2\t5\n1 1234567\n3   9\n3   3\n";
+        assert_eq!(try_part1(input).unwrap_err().expected, "' '");
+
+        let input = input.replace('\t', "  ");
+        assert_eq!(try_part1(&input), Ok(834_579));
+        assert_eq!(part2(&input), 18);
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(part1(""), 0);
+        assert_eq!(part2(""), 0);
+    }
+
+    #[test]
+    fn standard_and_general_lines() {
+        let lines = ["12345   67890\n", "12345   6789\n", "1 345   78901\n"];
+        assert_eq!(try_part1(lines[0]), Ok(55_545));
+        assert_eq!(try_part1(lines[1]), Ok(5_556));
+        assert_eq!(try_part1(lines[2]).unwrap_err().expected, "digit");
+    }
 }
@@ -2,13 +2,12 @@ use std::intrinsics::{unchecked_add, unchecked_div, unchecked_rem, unchecked_shr
 
 use aoc_runner_derive::aoc;
 
-use crate::{assume, debug, p, ZERO};
-
-const BUTTON_FIRST_NUM_OFFSET: usize = 12;
-const SECOND_NUM_OFFSET: usize = 4;
-
-const PRIZE_FIRST_NUM_OFFSET: usize = 9;
+use crate::{
+    assume, debug, digits, ensure, p, Assume as _, Checked, InputError, InputShape, Mode,
+    ParseError, Unchecked, EOL, ZERO,
+};
 
+#[inline]
 unsafe fn calc_cost(a_x: i64, a_y: i64, b_x: i64, b_y: i64, target_x: i64, target_y: i64) -> i64 {
     let denom = a_x.unchecked_mul(b_y).unchecked_sub(a_y.unchecked_mul(b_x));
     assume!(denom != 0);
@@ -31,6 +30,7 @@ unsafe fn calc_cost(a_x: i64, a_y: i64, b_x: i64, b_y: i64, target_x: i64, targe
     i.unchecked_add(j.unchecked_mul(3))
 }
 
+#[inline]
 unsafe fn swar(s: [u8; 8]) -> i64 {
     const ALL_0: u64 = 0x3030303030303030;
     const MASK: u64 = 0x000000FF000000FF;
@@ -56,14 +56,15 @@ unsafe fn swar(s: [u8; 8]) -> i64 {
     val as i64
 }
 
+#[inline]
 unsafe fn read_target(input: &[u8], pos: usize) -> (i64, usize) {
     let input = input.get_unchecked(pos..);
     debug!(
         "Reading line: {}",
-        std::str::from_utf8(&input).unwrap().lines().next().unwrap()
+        std::str::from_utf8(input).unwrap().lines().next().unwrap()
     );
-    if input.get_unchecked(3).is_ascii_digit() {
-        if input.get_unchecked(4).is_ascii_digit() {
+    if input.get(3).is_some_and(u8::is_ascii_digit) {
+        if input.get(4).is_some_and(u8::is_ascii_digit) {
             let [n1, n2, n3, n4, n5] = input.as_ptr().cast::<[u8; _]>().read_unaligned();
             (swar([ZERO, ZERO, ZERO, n1, n2, n3, n4, n5]), 5)
         } else {
@@ -76,105 +77,182 @@ unsafe fn read_target(input: &[u8], pos: usize) -> (i64, usize) {
     }
 }
 
+/// Checks `input[pos..]` starts with `tag`, returning the offset just past it
+#[inline(always)]
+fn tag<M: Mode>(input: &[u8], pos: usize, tag: &'static str) -> Result<usize, ParseError> {
+    for (i, b) in tag.bytes().enumerate() {
+        ensure!(M, input.get(pos + i) == Some(&b), input, pos + i, tag);
+    }
+    Ok(pos + tag.len())
+}
+
+/// Checks the byte at `pos` ends a line, which the end of the input does if `last` is set, as
+/// `aoc-runner` trims the trailing new line. Returns the offset of the next line
+#[inline(always)]
+fn eol<M: Mode>(input: &[u8], pos: usize, last: bool) -> Result<usize, ParseError> {
+    ensure!(
+        M,
+        input.get(pos) == Some(&EOL) || (last && pos == input.len()),
+        input,
+        pos,
+        "'\\n'"
+    );
+    Ok(pos + 1)
+}
+
+/// Reads a button line at `pos` of `name` then 2 digit movements, returning the movements and the
+/// offset of the next line
+#[inline(always)]
+unsafe fn button<M: Mode>(
+    input: &[u8],
+    pos: usize,
+    name: &'static str,
+) -> Result<(i64, i64, usize), ParseError> {
+    let pos = tag::<M>(input, pos, name)?;
+    digits::<M>(input, pos, 2)?;
+    let x = p!(
+        i64,
+        *input.get_unchecked(pos),
+        *input.get_unchecked(pos + 1)
+    );
+
+    let pos = tag::<M>(input, pos + 2, ", Y+")?;
+    digits::<M>(input, pos, 2)?;
+    let y = p!(
+        i64,
+        *input.get_unchecked(pos),
+        *input.get_unchecked(pos + 1)
+    );
+
+    Ok((x, y, eol::<M>(input, pos + 2, false)?))
+}
+
+/// Reads a prize coordinate at `pos` of 3 to 5 digits, returning it and the offset just past it
+#[inline(always)]
+unsafe fn target<M: Mode>(input: &[u8], pos: usize) -> Result<(i64, usize), ParseError> {
+    digits::<M>(input, pos, 3)?;
+    let (target, len) = read_target(input, pos);
+    Ok((target, pos + len))
+}
+
+/// Reads the claw machine at `pos`, two button lines and a prize line, followed by a blank line or
+/// the end of the input. Returns the buttons' movements, the prize and the offset of the next
+/// machine
+#[inline(always)]
+unsafe fn machine<M: Mode>(input: &[u8], pos: usize) -> Result<([i64; 6], usize), ParseError> {
+    let (a_x, a_y, pos) = button::<M>(input, pos, "Button A: X+")?;
+    let (b_x, b_y, pos) = button::<M>(input, pos, "Button B: X+")?;
+
+    let pos = tag::<M>(input, pos, "Prize: X=")?;
+    let (target_x, pos) = target::<M>(input, pos)?;
+    let pos = tag::<M>(input, pos, ", Y=")?;
+    let (target_y, pos) = target::<M>(input, pos)?;
+    let mut pos = eol::<M>(input, pos, true)?;
+    if pos < input.len() {
+        pos = eol::<M>(input, pos, false)?;
+    }
+
+    Ok(([a_x, a_y, b_x, b_y, target_x, target_y], pos))
+}
+
+/// Checks `input` is claw machines separated by blank lines, as [`part1`] and [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let mut shape = InputShape::of(input.as_bytes())?;
+    let machines = shape.lines.div_ceil(4);
+    shape = shape.expect_lines(machines * 4 - 1)?;
+    shape.records = machines;
+
+    let input = input.as_bytes();
+    let mut pos = 0;
+    while pos < input.len() {
+        let start = pos;
+        let ([a_x, a_y, b_x, b_y, ..], next) = unsafe { machine::<Checked>(input, pos)? };
+        pos = next;
+        ensure!(
+            Checked,
+            a_x * b_y != a_y * b_x,
+            input,
+            start,
+            "buttons moving in different directions"
+        );
+    }
+
+    Ok(shape)
+}
+
 #[aoc(day13, part1)]
 pub fn part1(input: &str) -> i64 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> i64 {
-        let input = input.as_bytes();
-        let mut sum = 0;
-        let mut pos = 0;
-
-        while input.len() > pos + 2 {
-            pos += BUTTON_FIRST_NUM_OFFSET;
-            let a_x = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-            pos += SECOND_NUM_OFFSET + 2;
-            let a_y = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-
-            pos += 3 + BUTTON_FIRST_NUM_OFFSET;
-            let b_x = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-            pos += SECOND_NUM_OFFSET + 2;
-            let b_y = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-
-            pos += 3 + PRIZE_FIRST_NUM_OFFSET;
-            let (target_x, offset) = read_target(input, pos);
-            pos += SECOND_NUM_OFFSET + offset;
-            let (target_y, offset) = read_target(input, pos);
-            pos += 2 + offset;
-
-            sum += calc_cost(a_x, a_y, b_x, b_y, target_x, target_y);
-        }
+    crate::dispatch(|| unsafe { inner_p1::<Unchecked>(input).assume() })
+}
+
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+///
+/// The two buttons of each machine must not move the claw in the same direction.
+pub fn try_part1(input: &str) -> Result<i64, ParseError> {
+    crate::dispatch(|| unsafe { inner_p1::<Checked>(input) })
+}
 
-        sum
+#[inline(always)]
+unsafe fn inner_p1<M: Mode>(input: &str) -> Result<i64, ParseError> {
+    let input = input.as_bytes();
+    let mut sum = 0;
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let start = pos;
+        let ([a_x, a_y, b_x, b_y, target_x, target_y], next) = machine::<M>(input, pos)?;
+        pos = next;
+
+        ensure!(
+            M,
+            a_x * b_y != a_y * b_x,
+            input,
+            start,
+            "buttons moving in different directions"
+        );
+        sum += calc_cost(a_x, a_y, b_x, b_y, target_x, target_y);
     }
-    unsafe { inner(input) }
+
+    Ok(sum)
 }
 
 #[aoc(day13, part2)]
 pub fn part2(input: &str) -> i64 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> i64 {
-        let input = input.as_bytes();
-        let mut sum = 0;
-        let mut pos = 0;
-
-        while input.len() > pos + 2 {
-            pos += BUTTON_FIRST_NUM_OFFSET;
-            let a_x = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-            pos += SECOND_NUM_OFFSET + 2;
-            let a_y = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-
-            pos += 3 + BUTTON_FIRST_NUM_OFFSET;
-            let b_x = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-            pos += SECOND_NUM_OFFSET + 2;
-            let b_y = p!(
-                i64,
-                *input.get_unchecked(pos),
-                *input.get_unchecked(pos + 1)
-            );
-
-            pos += 3 + PRIZE_FIRST_NUM_OFFSET;
-            let (target_x, offset) = read_target(input, pos);
-            pos += SECOND_NUM_OFFSET + offset;
-            let (target_y, offset) = read_target(input, pos);
-            pos += 2 + offset;
-
-            let target_x = target_x.unchecked_add(10_000_000_000_000);
-            let target_y = target_y.unchecked_add(10_000_000_000_000);
-
-            sum += calc_cost(a_x, a_y, b_x, b_y, target_x, target_y);
-        }
+    crate::dispatch(|| unsafe { inner_p2::<Unchecked>(input).assume() })
+}
 
-        sum
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+///
+/// The two buttons of each machine must not move the claw in the same direction.
+pub fn try_part2(input: &str) -> Result<i64, ParseError> {
+    crate::dispatch(|| unsafe { inner_p2::<Checked>(input) })
+}
+
+#[inline(always)]
+unsafe fn inner_p2<M: Mode>(input: &str) -> Result<i64, ParseError> {
+    let input = input.as_bytes();
+    let mut sum = 0;
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let start = pos;
+        let ([a_x, a_y, b_x, b_y, target_x, target_y], next) = machine::<M>(input, pos)?;
+        pos = next;
+
+        let target_x = target_x.unchecked_add(10_000_000_000_000);
+        let target_y = target_y.unchecked_add(10_000_000_000_000);
+
+        ensure!(
+            M,
+            a_x * b_y != a_y * b_x,
+            input,
+            start,
+            "buttons moving in different directions"
+        );
+        sum += calc_cost(a_x, a_y, b_x, b_y, target_x, target_y);
     }
-    unsafe { inner(input) }
+
+    Ok(sum)
 }
 
 #[cfg(test)]
@@ -222,4 +300,59 @@ mod tests {
         let input = include_str!("../input/2024/day13.txt");
         assert_eq!(part2(input), 99_423_413_811_305);
     }
+
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day13.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 29_877);
+            assert_eq!(part2(input), 99_423_413_811_305);
+        });
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day13.txt");
+        assert_eq!(try_part1(input), Ok(part1(input)));
+        assert_eq!(try_part2(input), Ok(part2(input)));
+
+        let mut input = input.to_owned();
+        input.replace_range(7..8, "C");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 8, "Button A: X+", Some(b'C'))
+        );
+
+        let err = try_part2(&INPUT.replacen("\n", " \n", 1)).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 21, "'\\n'", Some(b' '))
+        );
+        let err = try_part1(&INPUT.replacen("Y=5400", "Y=54", 1)).unwrap_err();
+        assert_eq!((err.line, err.column, err.expected), (3, 20, "digit"));
+        let err = try_part1(&INPUT.replacen("X=8400", "X=840000", 1)).unwrap_err();
+        assert_eq!((err.line, err.column, err.expected), (3, 15, ", Y="));
+    }
+
+    #[test]
+    fn missing_trailing_newline() {
+        assert_eq!(try_part1(INPUT.trim_end()), Ok(480));
+        assert_eq!(try_part2(INPUT.trim_end()), Ok(875_318_608_908));
+        // The last prize cut to 3 digits, the fewest there can be, right at the end of the input
+        assert_eq!(try_part1(&INPUT[..INPUT.len() - 3]), Ok(480));
+
+        let err = try_part1(&INPUT[..INPUT.len() - 4]).unwrap_err();
+        assert_eq!((err.line, err.expected, err.found), (15, "digit", None));
+    }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day13.txt");
+        assert_eq!(validate(input).map(|shape| shape.records), Ok(320));
+        assert_eq!(
+            validate(&input[..input.len() - 1]),
+            Err(InputError::MissingTrailingNewline)
+        );
+    }
 }
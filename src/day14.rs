@@ -1,13 +1,55 @@
-use std::{cmp::Ordering, mem::transmute};
+use std::cmp::Ordering;
 
 use aoc_runner_derive::aoc;
 
-use crate::{ptr_add, Assume, Unreachable};
-
-static LUT: [i8; 256 * 256 * 256] = unsafe { transmute(*include_bytes!("LUT14.bin")) };
+use crate::{
+    ensure, ptr_add, Assume, Checked, InputError, InputShape, Mode, ParseError, Unchecked,
+    Unreachable, EOL,
+};
+
+/// The number starting with each three bytes, or -1, generated by `build.rs`
+#[cfg(not(feature = "small-lut14"))]
+static LUT: [i8; 256 * 256 * 256] =
+    unsafe { std::mem::transmute(*include_bytes!(concat!(env!("OUT_DIR"), "/LUT14.bin"))) };
+
+/// The number starting with each two bytes and how many of them it takes up, or -1 and 0. A
+/// number taking up both might have a third digit, which [`lookup`] adds
+#[cfg(feature = "small-lut14")]
+static LUT: [(i8, u8); 256 * 256] = {
+    let mut lut = [(-1, 0); 256 * 256];
+    let mut key = 0;
+    while key < lut.len() {
+        if let Some((num, len)) = crate::lut14::number(&[(key >> 8) as u8, key as u8]) {
+            lut[key] = (num, len as u8);
+        }
+        key += 1;
+    }
+    lut
+};
 
 const LUT_LOOKUP_MASK: u32 = 0x00FFFFFF;
 
+/// The number starting with the three bytes of `key`, most significant first
+#[cfg(not(feature = "small-lut14"))]
+#[inline(always)]
+unsafe fn lookup(key: u32) -> i8 {
+    *LUT.get_unchecked(key as usize)
+}
+
+/// The number starting with the three bytes of `key`, most significant first
+#[cfg(feature = "small-lut14")]
+#[inline(always)]
+unsafe fn lookup(key: u32) -> i8 {
+    let (num, len) = *LUT.get_unchecked((key >> 8) as usize);
+    let digit = (key as u8).wrapping_sub(b'0');
+    if len == 2 && digit < 10 {
+        let digit = if num < 0 { -(digit as i8) } else { digit as i8 };
+        num.wrapping_mul(10).wrapping_add(digit)
+    } else {
+        num
+    }
+}
+
 #[inline]
 fn calc_q<const WIDTH: i32, const HEIGHT: i32>(final_x: i32, final_y: i32) -> usize {
     let final_x = final_x.cmp(&(WIDTH / 2));
@@ -30,16 +72,6 @@ struct Robot {
     dy: i8,
 }
 
-#[inline]
-unsafe fn parse_num(input: &[u8], pos: usize) -> i8 {
-    let index = ptr_add(input.as_ptr(), pos)
-        .cast::<u32>()
-        .read_unaligned()
-        .swap_bytes()
-        & LUT_LOOKUP_MASK;
-    *LUT.get_unchecked(index as usize)
-}
-
 impl Robot {
     #[inline]
     unsafe fn x_at<const WIDTH: i32>(self, timestep: i32) -> i32 {
@@ -74,142 +106,257 @@ const fn len(num: i8) -> usize {
     }
 }
 
-#[aoc(day14, part1)]
-pub fn part1(input: &str) -> i32 {
-    unsafe { inner_p1::<500, 101, 103>(input) }
-}
-
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p1<const INPUT_LINES: usize, const WIDTH: i32, const HEIGHT: i32>(
-    input: &str,
-) -> i32 {
-    let input = input.as_bytes();
-    let mut quadrants = [0; 5];
-    let mut pos = 1;
-
-    for _ in 0..INPUT_LINES - 1 {
-        let x = parse_num(input, pos);
-        pos = pos.unchecked_add(len(x)).unchecked_add(1);
+/// Reads the number at `pos`, then the `term` byte after it, returning the number and the offset
+/// past `term`. The end of the input may stand in for a final new line, as `aoc-runner` trims it.
+/// The number is looked up from the three bytes at `pos`, which are read one at a time if `last`
+/// is set, or in checked mode, so as not to read past the end of the input.
+///
+/// In checked mode, also checks the number is in `min..=max`, building the error for a number
+/// outside it with `out_of_range`
+#[inline(always)]
+unsafe fn parse_num<M: Mode, E: From<ParseError>>(
+    input: &[u8],
+    pos: usize,
+    last: bool,
+    (min, max): (i32, i32),
+    expected: &'static str,
+    term: u8,
+    out_of_range: &impl Fn(usize, i64, (i32, i32), &'static str) -> E,
+) -> Result<(i8, usize), E> {
+    let key = if M::CHECKED || last {
+        u32::from_be_bytes([
+            0,
+            *input.get(pos).unwrap_or(&0),
+            *input.get(pos.unchecked_add(1)).unwrap_or(&0),
+            *input.get(pos.unchecked_add(2)).unwrap_or(&0),
+        ])
+    } else {
+        // Numbers always follow a `=` or `,`, so the read can start from the byte before
+        ptr_add(input.as_ptr(), pos.unchecked_sub(1))
+            .cast::<u32>()
+            .read_unaligned()
+            .swap_bytes()
+            & LUT_LOOKUP_MASK
+    };
+    let num = lookup(key);
+    let end = pos.unchecked_add(len(num));
+
+    if M::CHECKED {
+        // The table has -1 for anything that isn't a number in range of an `i8`
+        if num == -1 && input.get(pos..pos + 2) != Some(b"-1") {
+            return Err(ParseError::new(input, pos, expected).into());
+        }
+        if !(min..=max).contains(&(num as i32)) {
+            return Err(out_of_range(pos, num as i64, (min, max), expected));
+        }
 
-        let y = parse_num(input, pos);
-        pos = pos.unchecked_add(len(y)).unchecked_add(3);
+        let term_expected = match term {
+            b',' => "','",
+            b' ' => "' '",
+            _ => "'\\n'",
+        };
+        ensure!(
+            M,
+            input.get(end) == Some(&term) || (term == EOL && end == input.len()),
+            input,
+            end,
+            term_expected
+        );
+    }
 
-        let dx = parse_num(input, pos);
-        pos = pos.unchecked_add(len(dx)).unchecked_add(1);
+    Ok((num, end.unchecked_add(1)))
+}
 
-        let dy = parse_num(input, pos);
-        pos = pos.unchecked_add(len(dy)).unchecked_add(3);
+/// Checks `input[pos..]` starts with `tag`, returning the offset just past it
+#[inline(always)]
+fn tag<M: Mode>(
+    input: &[u8],
+    pos: usize,
+    tag: &[u8],
+    expected: &'static str,
+) -> Result<usize, ParseError> {
+    for (i, &c) in tag.iter().enumerate() {
+        ensure!(M, input.get(pos + i) == Some(&c), input, pos + i, expected);
+    }
+    Ok(pos + tag.len())
+}
 
-        let robot = Robot { x, y, dx, dy };
-        let final_x = robot.x_at::<WIDTH>(100);
-        let final_y = robot.y_at::<HEIGHT>(100);
+/// Reads the robot on the line at `pos`, `p=x,y v=dx,dy`, returning it and the offset of the next
+/// line. `last` marks the last line, which may end without a new line. In checked mode, also checks
+/// the robot is inside the room and moving less than 100 tiles a second, building the error for a
+/// number outside that with `out_of_range`
+#[inline(always)]
+unsafe fn read_robot<M: Mode, E: From<ParseError>, const WIDTH: i32, const HEIGHT: i32>(
+    input: &[u8],
+    pos: usize,
+    last: bool,
+    out_of_range: &impl Fn(usize, i64, (i32, i32), &'static str) -> E,
+) -> Result<(Robot, usize), E> {
+    const VELOCITY: &str = "a velocity between -99 and 99";
+    const V_RANGE: (i32, i32) = (-99, 99);
+
+    let pos = tag::<M>(input, pos, b"p=", "\"p=\"")?;
+    let (x, pos) = parse_num::<M, E>(
+        input,
+        pos,
+        false,
+        (0, WIDTH - 1),
+        "an x inside the room",
+        b',',
+        out_of_range,
+    )?;
+    let (y, pos) = parse_num::<M, E>(
+        input,
+        pos,
+        false,
+        (0, HEIGHT - 1),
+        "a y inside the room",
+        b' ',
+        out_of_range,
+    )?;
+    let pos = tag::<M>(input, pos, b"v=", "\"v=\"")?;
+    let (dx, pos) = parse_num::<M, E>(input, pos, false, V_RANGE, VELOCITY, b',', out_of_range)?;
+    let (dy, pos) = parse_num::<M, E>(input, pos, last, V_RANGE, VELOCITY, EOL, out_of_range)?;
+
+    Ok((Robot { x, y, dx, dy }, pos))
+}
 
-        let quadrant = calc_q::<WIDTH, HEIGHT>(final_x, final_y);
-        *quadrants.get_unchecked_mut(quadrant) += 1;
-    }
+/// Checks `input` is 500 robots inside a 101 by 103 room, as [`part1`] and [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let input = input.as_bytes();
+    let shape = InputShape::of(input)?.expect_lines(500)?;
+    unsafe {
+        Solver::new().read_robots::<Checked, _>(input, |pos, found, (min, max), _| {
+            InputError::out_of_range(input, pos, found, (min as i64, max as i64))
+        })?
+    };
+
+    Ok(shape)
+}
 
-    crate::debug!(
-        "Remaining text: {}",
-        std::str::from_utf8(&input[pos..input.len() - 1]).unwrap()
-    );
+/// The error [`parse_num`] reports for an out of range number in [`try_part1`] and
+/// [`try_part2`]
+fn parse_error(input: &[u8]) -> impl Fn(usize, i64, (i32, i32), &'static str) -> ParseError + '_ {
+    move |pos, _, _, expected| ParseError::new(input, pos, expected)
+}
 
-    // Last line may not have 3 bytes for final number
-    {
-        let x = parse_num(input, pos);
-        pos = pos.unchecked_add(len(x)).unchecked_add(1);
+#[aoc(day14, part1)]
+pub fn part1(input: &str) -> i32 {
+    Solver::new().part1(input)
+}
 
-        let y = parse_num(input, pos);
-        pos = pos.unchecked_add(len(y)).unchecked_add(3);
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part1(input)
+}
 
-        let dx = parse_num(input, pos);
-        pos = pos.unchecked_add(len(dx)).unchecked_add(1);
+#[inline(always)]
+unsafe fn inner_p1<M: Mode, const INPUT_LINES: usize, const WIDTH: i32, const HEIGHT: i32>(
+    input: &str,
+) -> Result<i32, ParseError> {
+    let input = input.as_bytes();
+    let out_of_range = parse_error(input);
+    let mut quadrants = [0; 5];
+    let mut pos = 0;
 
-        let index = u32::from_ne_bytes([
-            *input.get(pos.unchecked_add(3)).unwrap_or(&0),
-            *input.get(pos.unchecked_add(2)).unwrap_or(&0),
-            *input.get_unchecked(pos.unchecked_add(1)),
-            0,
-        ]);
-        let dy = *LUT.get_unchecked(index as usize);
+    for n in 0..INPUT_LINES {
+        // The last line may not have 3 bytes for its final number
+        let robot;
+        (robot, pos) =
+            read_robot::<M, _, WIDTH, HEIGHT>(input, pos, n == INPUT_LINES - 1, &out_of_range)?;
 
-        let robot = Robot { x, y, dx, dy };
         let final_x = robot.x_at::<WIDTH>(100);
         let final_y = robot.y_at::<HEIGHT>(100);
         let quadrant = calc_q::<WIDTH, HEIGHT>(final_x, final_y);
         *quadrants.get_unchecked_mut(quadrant) += 1;
     }
+    ensure!(M, pos >= input.len(), input, pos, "end of input");
 
     crate::debug!("Quadrants: {quadrants:?}");
-    quadrants[0] * quadrants[1] * quadrants[2] * quadrants[3]
+    Ok(quadrants[0] * quadrants[1] * quadrants[2] * quadrants[3])
 }
 
-static mut ROBOT_X: [i8; 500] = [0; 500];
-static mut ROBOT_DX: [i8; 500] = [0; 500];
-static mut ROBOT_Y: [i8; 500] = [0; 500];
-static mut ROBOT_DY: [i8; 500] = [0; 500];
-
-unsafe fn parse_robots(input: &str) {
-    let input = input.as_bytes();
-    let mut pos = 1;
-
-    for n in 0..499 {
-        let x = parse_num(input, pos);
-        pos = pos.unchecked_add(len(x)).unchecked_add(1);
-        *ROBOT_X.get_unchecked_mut(n) = x;
+/// Solves day 14, keeping the robots in buffers it reuses between inputs
+#[derive(Clone, Debug)]
+pub struct Solver {
+    robot_x: [i8; 500],
+    robot_dx: [i8; 500],
+    robot_y: [i8; 500],
+    robot_dy: [i8; 500],
+}
 
-        let y = parse_num(input, pos);
-        pos = pos.unchecked_add(len(y)).unchecked_add(3);
-        *ROBOT_Y.get_unchecked_mut(n) = y;
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let dx = parse_num(input, pos);
-        pos = pos.unchecked_add(len(dx)).unchecked_add(1);
-        *ROBOT_DX.get_unchecked_mut(n) = dx;
+impl Solver {
+    pub const fn new() -> Self {
+        Self {
+            robot_x: [0; 500],
+            robot_dx: [0; 500],
+            robot_y: [0; 500],
+            robot_dy: [0; 500],
+        }
+    }
 
-        let dy = parse_num(input, pos);
-        pos = pos.unchecked_add(len(dy)).unchecked_add(3);
-        *ROBOT_DY.get_unchecked_mut(n) = dy;
+    pub fn part1(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { inner_p1::<Unchecked, 500, 101, 103>(input).assume() })
     }
 
-    crate::debug!(
-        "Remaining text: {}",
-        std::str::from_utf8(&input[pos..input.len() - 1]).unwrap()
-    );
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { inner_p1::<Checked, 500, 101, 103>(input) })
+    }
 
-    // Last line may not have 3 bytes for final number
-    {
-        let n = 499;
+    pub fn part2(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { self.inner_p2::<Unchecked>(input).assume() })
+    }
 
-        let x = parse_num(input, pos);
-        pos = pos.unchecked_add(len(x)).unchecked_add(1);
-        *ROBOT_X.get_unchecked_mut(n) = x;
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { self.inner_p2::<Checked>(input) })
+    }
 
-        let y = parse_num(input, pos);
-        pos = pos.unchecked_add(len(y)).unchecked_add(3);
-        *ROBOT_Y.get_unchecked_mut(n) = y;
+    /// The parsing phase of [`Solver::part2`], reading the robots into the solver's buffers.
+    pub fn parse_robots(&mut self, input: &str) {
+        crate::dispatch(|| unsafe {
+            self.read_robots::<Unchecked, _>(input.as_bytes(), parse_error(input.as_bytes()))
+                .assume()
+        })
+    }
 
-        let dx = parse_num(input, pos);
-        pos = pos.unchecked_add(len(dx)).unchecked_add(1);
-        *ROBOT_DX.get_unchecked_mut(n) = dx;
+    /// Reads the 500 robots in `input` into the solver's buffers. In checked mode, also checks
+    /// there are no more lines, building the error for a number out of range with `out_of_range`
+    #[inline(always)]
+    unsafe fn read_robots<M: Mode, E: From<ParseError>>(
+        &mut self,
+        input: &[u8],
+        out_of_range: impl Fn(usize, i64, (i32, i32), &'static str) -> E,
+    ) -> Result<(), E> {
+        let mut pos = 0;
+
+        for n in 0..500 {
+            // The last line may not have 3 bytes for its final number
+            let robot;
+            (robot, pos) = read_robot::<M, E, 101, 103>(input, pos, n == 499, &out_of_range)?;
+            *self.robot_x.get_unchecked_mut(n) = robot.x;
+            *self.robot_y.get_unchecked_mut(n) = robot.y;
+            *self.robot_dx.get_unchecked_mut(n) = robot.dx;
+            *self.robot_dy.get_unchecked_mut(n) = robot.dy;
+        }
+        ensure!(M, pos >= input.len(), input, pos, "end of input");
 
-        let index = u32::from_ne_bytes([
-            *input.get(pos.unchecked_add(3)).unwrap_or(&0),
-            *input.get(pos.unchecked_add(2)).unwrap_or(&0),
-            *input.get_unchecked(pos.unchecked_add(1)),
-            0,
-        ]);
-        let dy = *LUT.get_unchecked(index as usize);
-        *ROBOT_DY.get_unchecked_mut(n) = dy;
+        Ok(())
     }
-}
 
-#[aoc(day14, part2)]
-pub fn part2(input: &str) -> i32 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> i32 {
+    #[inline(always)]
+    unsafe fn inner_p2<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
         const WIDTH: i32 = 101;
         const HEIGHT: i32 = 103;
 
-        parse_robots(input);
+        self.read_robots::<M, _>(input.as_bytes(), parse_error(input.as_bytes()))?;
 
         let mut x_timestep = 0;
         let mut y_timestep = 0;
@@ -217,7 +364,7 @@ pub fn part2(input: &str) -> i32 {
         'outer: for timestep in 0..WIDTH {
             let mut arr = [0u8; WIDTH as _];
 
-            for (&x, &dx) in ROBOT_X.iter().zip(ROBOT_DX.iter()) {
+            for (&x, &dx) in self.robot_x.iter().zip(&self.robot_dx) {
                 let x = timestep
                     .unchecked_mul(dx as i32)
                     .unchecked_add(x as i32)
@@ -239,7 +386,7 @@ pub fn part2(input: &str) -> i32 {
         'outer: for timestep in 0..HEIGHT {
             let mut arr = [0u8; HEIGHT as _];
 
-            for (&y, &dy) in ROBOT_Y.iter().zip(ROBOT_DY.iter()) {
+            for (&y, &dy) in self.robot_y.iter().zip(&self.robot_dy) {
                 let y = timestep
                     .unchecked_mul(dy as i32)
                     .unchecked_add(y as i32)
@@ -274,9 +421,18 @@ pub fn part2(input: &str) -> i32 {
             .unchecked_mul(factor % 2)
             .unchecked_add(factor);
 
-        factor.unchecked_shr(1)
+        Ok(factor.unchecked_shr(1))
     }
-    unsafe { inner(input) }
+}
+
+#[aoc(day14, part2)]
+pub fn part2(input: &str) -> i32 {
+    Solver::new().part2(input)
+}
+
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part2(input)
 }
 
 #[cfg(test)]
@@ -302,7 +458,7 @@ mod tests {
 
     #[test]
     fn p1_example() {
-        assert_eq!(unsafe { inner_p1::<12, 11, 7>(INPUT) }, 12);
+        assert_eq!(unsafe { inner_p1::<Unchecked, 12, 11, 7>(INPUT) }, Ok(12));
     }
 
     #[test]
@@ -317,13 +473,85 @@ mod tests {
         assert_eq!(part2(input), 6_752);
     }
 
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day14.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 225_810_288);
+            assert_eq!(part2(input), 6_752);
+        });
+    }
+
+    #[test]
+    fn solver_reuse() {
+        let input = include_str!("../input/2024/day14.txt");
+        let mut solver = Solver::new();
+        assert_eq!(solver.part2(input), 6_752);
+        assert!(solver.try_part2(&input.replacen("p=", "q=", 1)).is_err());
+        assert_eq!(solver.part2(input), 6_752);
+        assert_eq!(solver.part1(input), 225_810_288);
+    }
+
     #[test]
     fn lut_check() {
         for (bytes, val) in [("0,4", 0), ("-1,", -1), ("66,", 66), ("101", 101)] {
             let bytes = bytes.as_bytes();
-            let lookup =
-                ((bytes[0] as usize) << 16) + ((bytes[1] as usize) << 8) + bytes[2] as usize;
-            assert_eq!(LUT[lookup] as i8, val);
+            let key = ((bytes[0] as u32) << 16) + ((bytes[1] as u32) << 8) + bytes[2] as u32;
+            assert_eq!(unsafe { lookup(key) }, val);
         }
     }
+
+    /// Whichever table is built, it must read every number the input can have
+    #[test]
+    fn lut_numbers() {
+        for key in 0..1 << 24 {
+            let text = [(key >> 16) as u8, (key >> 8) as u8, key as u8];
+            if let Some((num, _)) = crate::lut14::number(&text) {
+                assert_eq!(unsafe { lookup(key) }, num, "{:?}", text.map(char::from));
+            }
+        }
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day14.txt");
+        assert_eq!(try_part1(input), Ok(225_810_288));
+        assert_eq!(try_part2(input), Ok(6_752));
+        assert_eq!(try_part1(input.trim_end()), Ok(225_810_288));
+        assert_eq!(try_part2(input.trim_end()), Ok(6_752));
+
+        let err = try_part1(&input[..input.len() - 3]).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (500, 15, "a velocity between -99 and 99", Some(b'-'))
+        );
+        let err = try_part2(&(input.to_owned() + "p=0,0 v=0,0\n")).unwrap_err();
+        assert_eq!((err.line, err.expected), (501, "end of input"));
+
+        let mut input = input.to_owned();
+        input.replace_range(2..4, "200");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 3, "an x inside the room", Some(b'2'))
+        );
+    }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day14.txt");
+        assert_eq!(validate(input).map(|shape| shape.records), Ok(500));
+
+        let input = input.replacen("p=10,22", "p=101,22", 1);
+        assert_eq!(
+            validate(&input),
+            Err(InputError::NumberOutOfRange {
+                line: 1,
+                column: 3,
+                found: 101,
+                min: 0,
+                max: 100
+            })
+        );
+    }
 }
@@ -1,18 +1,22 @@
 use std::{
     fmt::Display,
-    mem::transmute,
     ops::{Index, IndexMut},
     simd::{cmp::SimdPartialEq, Simd},
 };
 
 use aoc_runner_derive::aoc;
 
-use crate::{ArrayVec, Assume, BitIterU64, IndexI8, Unreachable};
+use crate::{
+    ensure, push, ArrayVec, Assume, BitIterU64, Checked, IndexI8, InputError, InputShape, Mode,
+    ParseError, Unchecked, Unreachable, EOL,
+};
 
 const WALL: u8 = b'#';
 const EMPTY: u8 = b'.';
 const OBJECT: u8 = b'O';
 const ROBOT: u8 = b'@';
+/// What a move in part 2 is expected to be when it pushes more tiles than its stack holds
+const PUSHED: &str = "a move pushing at most 1000 tiles";
 
 #[derive(Debug, Clone, Copy)]
 #[repr(u8)]
@@ -131,12 +135,74 @@ impl<const DIM: usize> IndexMut<IndexI8<DIM>> for FieldP1<DIM> {
     }
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn read_field_p1<const DIM: usize>(input: &[u8], field: &mut FieldP1<DIM>) -> IndexI8<DIM> {
-    let input: &[CellP1] = transmute(input);
+/// Checks the cell at `(x, y)` of a `DIM` square warehouse is one of `#.O@`, and a wall on the
+/// warehouse's edges, and that it is the only robot. Returns the cell
+#[inline(always)]
+fn cell<M: Mode, const DIM: usize>(
+    input: &[u8],
+    x: usize,
+    y: usize,
+    robot: &mut bool,
+) -> Result<u8, ParseError> {
+    let pos = y * (DIM + 1) + x;
+    let c = input.get(pos).copied();
+    if x == 0 || y == 0 || x == DIM - 1 || y == DIM - 1 {
+        ensure!(
+            M,
+            c == Some(WALL),
+            input,
+            pos,
+            "a wall around the warehouse"
+        );
+    } else if c == Some(ROBOT) {
+        ensure!(M, !*robot, input, pos, "a single robot");
+        *robot = true;
+    } else {
+        ensure!(
+            M,
+            matches!(c, Some(WALL | EMPTY | OBJECT)),
+            input,
+            pos,
+            "one of \"#.O@\""
+        );
+    }
+    ensure!(
+        M,
+        x < DIM - 1 || input.get(pos + 1) == Some(&EOL),
+        input,
+        pos + 1,
+        "'\\n'"
+    );
+
+    Ok(c.unwrap_or_default())
+}
+
+/// Checks the warehouse had a robot, then the blank line after it
+#[inline(always)]
+fn blank_line<M: Mode, const DIM: usize>(input: &[u8], robot: bool) -> Result<(), ParseError> {
+    let len = DIM * (DIM + 1);
+    ensure!(M, robot, input, len, "a robot");
+    ensure!(M, input.get(len) == Some(&EOL), input, len, "blank line");
+    Ok(())
+}
+
+/// Reads the `DIM` square warehouse at the start of `input` into `field`, returning the robot's
+/// position. In checked mode, also checks each cell with [`cell`] before copying its line
+#[inline(always)]
+unsafe fn read_field_p1<M: Mode, const DIM: usize>(
+    input: &[u8],
+    field: &mut FieldP1<DIM>,
+) -> Result<IndexI8<DIM>, ParseError> {
     let mut start_index = IndexI8 { x: 0, y: 0 };
+    let mut robot = false;
 
     for line in 0..DIM {
+        if M::CHECKED {
+            for x in 0..DIM {
+                cell::<M, DIM>(input, x, line, &mut robot)?;
+            }
+        }
+
         let pos = line * (DIM + 1);
         let cell_line = &mut field[line];
         *cell_line = input
@@ -152,32 +218,131 @@ unsafe fn read_field_p1<const DIM: usize>(input: &[u8], field: &mut FieldP1<DIM>
             };
         }
     }
+    blank_line::<M, DIM>(input, robot)?;
 
     debug_assert!(start_index != IndexI8 { x: 0, y: 0 });
     field[start_index] = CellP1::Empty;
-    start_index
+    Ok(start_index)
 }
 
-#[aoc(day15, part1)]
-pub fn part1(input: &str) -> usize {
-    static mut FIELD: FieldP1<50> = FieldP1::new();
+/// Checks `input` is a 50 by 50 warehouse followed by the robot's moves, as [`part1`] and
+/// [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
     let input = input.as_bytes();
+    let mut shape = InputShape::of(input)?.expect_width(input, 50, 50)?;
+
+    let mut robot = false;
+    for y in 0..50 {
+        for x in 0..50 {
+            cell::<Checked, 50>(input, x, y, &mut robot)?;
+        }
+    }
+    blank_line::<Checked, 50>(input, robot)?;
+
+    let start = 50 * 51 + 1;
+    for (i, &c) in input[start..].iter().enumerate() {
+        ensure!(
+            Checked,
+            matches!(c, b'>' | b'^' | b'<' | b'V' | b'v' | EOL),
+            input,
+            start + i,
+            "one of \"<>^v\""
+        );
+    }
+    shape.records = input[start..].iter().filter(|&&c| c != EOL).count();
+
+    Ok(shape)
+}
+
+/// Solves day 15, keeping the warehouse and the stack of boxes to push in buffers it reuses
+/// between inputs
+pub struct Solver {
+    field_p1: FieldP1<50>,
+    field_p2: FieldP2<50>,
+    stack: ArrayVec<1_000, IndexI8<100>>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub const fn new() -> Self {
+        Self {
+            field_p1: FieldP1::new(),
+            field_p2: FieldP2::new(),
+            stack: ArrayVec::new(),
+        }
+    }
+
+    pub fn part1(&mut self, input: &str) -> usize {
+        crate::dispatch(|| unsafe { self.run_p1::<Unchecked>(input.as_bytes()).assume() })
+    }
+
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<usize, ParseError> {
+        crate::dispatch(|| unsafe { self.run_p1::<Checked>(input.as_bytes()) })
+    }
+
+    pub fn part2(&mut self, input: &str) -> usize {
+        crate::dispatch(|| unsafe { self.run_p2::<Unchecked>(input.as_bytes()).assume() })
+    }
+
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<usize, ParseError> {
+        crate::dispatch(|| unsafe { self.run_p2::<Checked>(input.as_bytes()) })
+    }
+
+    /// The parsing phase of [`Solver::part1`], reading the warehouse into the solver's buffers.
+    pub fn read_field_p1(&mut self, input: &str) {
+        crate::dispatch(|| unsafe {
+            read_field_p1::<Unchecked, 50>(input.as_bytes(), &mut self.field_p1).assume()
+        });
+    }
+
+    /// The parsing phase of [`Solver::part2`], reading the widened warehouse into the solver's
+    /// buffers.
+    pub fn read_field_p2(&mut self, input: &str) {
+        crate::dispatch(|| unsafe {
+            read_field_p2::<Unchecked, 50>(input.as_bytes(), &mut self.field_p2).assume()
+        });
+    }
 
-    unsafe {
-        let initial = read_field_p1(input, &mut FIELD);
-        inner_p1(input, &mut FIELD, initial)
+    #[inline(always)]
+    unsafe fn run_p1<M: Mode>(&mut self, input: &[u8]) -> Result<usize, ParseError> {
+        let initial = read_field_p1::<M, 50>(input, &mut self.field_p1)?;
+        inner_p1::<M, 50>(input, &mut self.field_p1, initial)
+    }
+
+    #[inline(always)]
+    unsafe fn run_p2<M: Mode>(&mut self, input: &[u8]) -> Result<usize, ParseError> {
+        let initial = read_field_p2::<M, 50>(input, &mut self.field_p2)?;
+        self.stack.clear();
+        inner_p2::<M, 50>(input, &mut self.field_p2, initial, &mut self.stack)
     }
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p1<const DIM: usize>(
+#[aoc(day15, part1)]
+pub fn part1(input: &str) -> usize {
+    Solver::new().part1(input)
+}
+
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<usize, ParseError> {
+    Solver::new().try_part1(input)
+}
+
+#[inline(always)]
+unsafe fn inner_p1<M: Mode, const DIM: usize>(
     input: &[u8],
     field: &mut FieldP1<DIM>,
     mut pos: IndexI8<DIM>,
-) -> usize {
-    let input = input.get_unchecked((DIM + 1) * DIM + 1..);
+) -> Result<usize, ParseError> {
+    let start = (DIM + 1) * DIM + 1;
 
-    for c in input {
+    for (i, c) in input.get_unchecked(start..).iter().enumerate() {
         let dir = match c {
             b'>' => IndexI8::RIGHT,
             b'^' => IndexI8::UP,
@@ -185,6 +350,7 @@ unsafe fn inner_p1<const DIM: usize>(
             b'V' | b'v' => IndexI8::DOWN,
             b'\n' => IndexI8::ZERO,
             _c => {
+                ensure!(M, false, input, start + i, "one of \"<>^v\"");
                 crate::debug!("Unexpected character {_c} ({})", *_c as char);
                 Unreachable.assume();
             }
@@ -217,7 +383,7 @@ unsafe fn inner_p1<const DIM: usize>(
         crate::debug!("Map:\n{field}");
     }
 
-    field.value()
+    Ok(field.value())
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -368,19 +534,26 @@ impl<const DIM: usize> IndexMut<IndexI8<{ 2 * DIM }>> for FieldP2<DIM> {
     }
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn read_field_p2<const DIM: usize>(
+/// Reads the `DIM` square warehouse at the start of `input` into `field`, widened, returning the
+/// robot's position. In checked mode, also checks each cell with [`cell`]
+#[inline(always)]
+unsafe fn read_field_p2<M: Mode, const DIM: usize>(
     input: &[u8],
     field: &mut FieldP2<DIM>,
-) -> IndexI8<{ 2 * DIM }>
+) -> Result<IndexI8<{ 2 * DIM }>, ParseError>
 where
     [(); 2 * DIM]:,
 {
     let mut start_index = IndexI8 { x: 0, y: 0 };
+    let mut robot = false;
 
     for y in 0..DIM {
         for x in 0..DIM {
-            let c = input[y * (DIM + 1) + x];
+            let c = if M::CHECKED {
+                cell::<M, DIM>(input, x, y, &mut robot)?
+            } else {
+                input[y * (DIM + 1) + x]
+            };
             let cell = match c {
                 ROBOT => {
                     start_index = IndexI8 {
@@ -409,35 +582,35 @@ where
         }
     }
 
+    blank_line::<M, DIM>(input, robot)?;
+
     debug_assert!(start_index != IndexI8 { x: 0, y: 0 });
-    start_index
+    Ok(start_index)
 }
 
 #[aoc(day15, part2)]
 pub fn part2(input: &str) -> usize {
-    static mut FIELD: FieldP2<50> = FieldP2::new();
-    static mut STACK: ArrayVec<1_000, IndexI8<100>> = ArrayVec::new();
-    let input = input.as_bytes();
+    Solver::new().part2(input)
+}
 
-    unsafe {
-        let initial = read_field_p2(input, &mut FIELD);
-        inner_p2(input, &mut FIELD, initial, &mut STACK)
-    }
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<usize, ParseError> {
+    Solver::new().try_part2(input)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p2<const DIM: usize>(
+#[inline(always)]
+unsafe fn inner_p2<M: Mode, const DIM: usize>(
     input: &[u8],
     field: &mut FieldP2<DIM>,
     mut pos: IndexI8<{ 2 * DIM }>,
     stack: &mut ArrayVec<1_000, IndexI8<{ 2 * DIM }>>,
-) -> usize
+) -> Result<usize, ParseError>
 where
     [(); 2 * DIM]:,
 {
-    let input = input.get_unchecked((DIM + 1) * DIM + 1..);
+    let start = (DIM + 1) * DIM + 1;
 
-    'outer: for c in input {
+    'outer: for (i, c) in input.get_unchecked(start..).iter().enumerate() {
         let dir = match c {
             b'>' => IndexI8::RIGHT,
             b'^' => IndexI8::UP,
@@ -445,6 +618,7 @@ where
             b'V' | b'v' => IndexI8::DOWN,
             b'\n' => IndexI8::ZERO,
             _c => {
+                ensure!(M, false, input, start + i, "one of \"<>^v\"");
                 crate::debug!("Unexpected character {_c} ({})", *_c as char);
                 Unreachable.assume();
             }
@@ -459,7 +633,7 @@ where
             CellP2::ObjectLeft | CellP2::ObjectRight => {
                 let mut queued = [0_u128; DIM];
 
-                stack.push_unchecked(pos);
+                push::<M, _, _>(stack, pos, input, start + i, PUSHED)?;
                 for index in 0.. {
                     if index >= stack.len {
                         break;
@@ -479,7 +653,7 @@ where
                                 let queued = &mut queued[new_pos.y as usize];
                                 if (*queued & 1 << new_pos.x) == 0 {
                                     *queued |= 1 << new_pos.x;
-                                    stack.push_unchecked(new_pos);
+                                    push::<M, _, _>(stack, new_pos, input, start + i, PUSHED)?;
                                 }
                             }
                             {
@@ -487,7 +661,7 @@ where
                                 let queued = &mut queued[new_pos.y as usize];
                                 if (*queued & 1 << new_pos.x) == 0 {
                                     *queued |= 1 << new_pos.x;
-                                    stack.push_unchecked(new_pos);
+                                    push::<M, _, _>(stack, new_pos, input, start + i, PUSHED)?;
                                 }
                             }
                         }
@@ -496,7 +670,7 @@ where
                                 let queued = &mut queued[new_pos.y as usize];
                                 if (*queued & 1 << new_pos.x) == 0 {
                                     *queued |= 1 << new_pos.x;
-                                    stack.push_unchecked(new_pos);
+                                    push::<M, _, _>(stack, new_pos, input, start + i, PUSHED)?;
                                 }
                             }
                             {
@@ -504,7 +678,7 @@ where
                                 let queued = &mut queued[new_pos.y as usize];
                                 if (*queued & 1 << new_pos.x) == 0 {
                                     *queued |= 1 << new_pos.x;
-                                    stack.push_unchecked(new_pos);
+                                    push::<M, _, _>(stack, new_pos, input, start + i, PUSHED)?;
                                 }
                             }
                         }
@@ -528,7 +702,7 @@ where
         crate::debug!("Map:\n{}", field._print(pos));
     }
 
-    field.value()
+    Ok(field.value())
 }
 
 #[cfg(test)]
@@ -546,4 +720,71 @@ mod tests {
         let input = include_str!("../input/2024/day15.txt");
         assert_eq!(part2(input), 1_425_169);
     }
+
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day15.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 1_441_031);
+            assert_eq!(part2(input), 1_425_169);
+        });
+    }
+
+    #[test]
+    fn solver_reuse() {
+        let input = include_str!("../input/2024/day15.txt");
+        let mut solver = Solver::new();
+        assert_eq!(solver.part2(input), 1_425_169);
+        assert_eq!(solver.part1(input), 1_441_031);
+        assert_eq!(solver.part2(input), 1_425_169);
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day15.txt");
+        assert_eq!(try_part1(input), Ok(1_441_031));
+        assert_eq!(try_part2(input), Ok(1_425_169));
+
+        let mut input = input.to_owned();
+        input.replace_range(0..1, ".");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 1, "a wall around the warehouse", Some(b'.'))
+        );
+        input.replace_range(0..1, "#");
+
+        assert_eq!(try_part1(input.trim_end()), Ok(1_441_031));
+        assert_eq!(try_part2(input.trim_end()), Ok(1_425_169));
+
+        let err = try_part2(&input.replacen('.', "@", 1)).unwrap_err();
+        assert_eq!(err.expected, "a single robot");
+
+        let moves = input.rfind('<').unwrap();
+        input.replace_range(moves..moves + 1, "x");
+        for err in [try_part1(&input), try_part2(&input)] {
+            let err = err.unwrap_err();
+            assert_eq!((err.offset, err.found), (moves, Some(b'x')));
+            assert_eq!(err.expected, "one of \"<>^v\"");
+        }
+    }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day15.txt");
+        assert_eq!(validate(input).map(|shape| shape.records), Ok(20_000));
+
+        let err = |input: &str| match validate(input) {
+            Err(InputError::UnexpectedByte(err)) => (err.offset, err.expected),
+            other => panic!("{other:?}"),
+        };
+        assert_eq!(
+            err(&input.replacen('.', "@", 1)),
+            (input.find('@').unwrap(), "a single robot")
+        );
+        let moves = input.rfind('<').unwrap();
+        let mut bad_move = input.to_owned();
+        bad_move.replace_range(moves..moves + 1, "x");
+        assert_eq!(err(&bad_move), (moves, "one of \"<>^v\""));
+    }
 }
@@ -2,15 +2,165 @@ use std::{cmp::Reverse, collections::BinaryHeap, mem::transmute};
 
 use aoc_runner_derive::aoc;
 
-use crate::{ArrayVec, Assume as _, ConstDefault, Direction, IndexI16 as Index};
+use crate::{
+    ensure, push, ArrayVec, Assume as _, Checked, ConstDefault, Direction, IndexI16 as Index,
+    InputError, InputShape, Mode, ParseError, Unchecked,
+};
 
 const WALL: u8 = b'#';
+/// What a tile in part 2 is expected to be when the best paths through it branch more than the
+/// stack walking them holds
+const BRANCHES: &str = "best paths with at most 200 branches left to walk";
+
+/// Reads where a `DIM` square maze starts and ends, the bottom left and top right. The end of the
+/// input ends the last line, as `aoc-runner` trims the trailing new line. In checked mode, also
+/// checks the input is as long as the maze and has its start and end there
+#[inline(always)]
+fn ends<M: Mode, const DIM: usize>(input: &[u8]) -> Result<(Index<DIM>, Index<DIM>), ParseError> {
+    let start = Index::<DIM> {
+        x: 1,
+        y: DIM as i16 - 2,
+    };
+    let end = Index::<DIM> {
+        x: DIM as i16 - 2,
+        y: 1,
+    };
+
+    if M::CHECKED {
+        let len = DIM * (DIM + 1);
+        ensure!(M, input.len() <= len, input, len, "end of input");
+        ensure!(
+            M,
+            input.len() >= len - 1,
+            input,
+            input.len(),
+            "another line"
+        );
+
+        let pos = (DIM - 2) * (DIM + 1) + 1;
+        ensure!(
+            M,
+            input[pos] == b'S',
+            input,
+            pos,
+            "the start in the bottom left"
+        );
+        let pos = DIM + 1 + DIM - 2;
+        ensure!(
+            M,
+            input[pos] == b'E',
+            input,
+            pos,
+            "the end in the top right"
+        );
+    }
+
+    Ok((start, end))
+}
+
+/// Reads whether the tile at `index` is open. In checked mode, also checks it is a wall on the
+/// edges of the maze, and `#` or `.` inside it. Tiles the search never reaches aren't read, so
+/// aren't checked either
+#[inline(always)]
+unsafe fn open<M: Mode, const DIM: usize>(
+    input: &[u8],
+    index: Index<DIM>,
+) -> Result<bool, ParseError> {
+    let pos = index.to();
+    let c = *input.get_unchecked(pos);
+
+    if M::CHECKED {
+        let (x, y) = (index.x as usize, index.y as usize);
+        if x == 0 || y == 0 || x == DIM - 1 || y == DIM - 1 {
+            ensure!(M, c == WALL, input, pos, "a wall around the maze");
+        } else if (x, y) != (1, DIM - 2) && (x, y) != (DIM - 2, 1) {
+            ensure!(M, matches!(c, WALL | b'.'), input, pos, "'#' or '.'");
+        }
+    }
+
+    Ok(c != WALL)
+}
+
+/// Checks `input` is a 141 by 141 maze from the bottom left to the top right, as [`part1`] and
+/// [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let input = input.as_bytes();
+    let shape = InputShape::grid(input, 141, 141)?;
+    ends::<Checked, 141>(input)?;
+    for y in 0..141 {
+        for x in 0..141 {
+            unsafe { open::<Checked, 141>(input, Index { x, y })? };
+        }
+    }
+
+    Ok(shape)
+}
+
+/// Solves day 16, keeping the cost of reaching each tile and the backtracking state in buffers it
+/// resets and reuses for each input
+pub struct Solver {
+    cost: Box<[[[u32; 4]; 141]; 141]>,
+    stack: ArrayVec<200, StackEntry<141>>,
+    visits: Box<[[bool; 141]; 141]>,
+}
+
+impl Default for Solver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        // Left zeroed, which is valid for both, as `run_p1` and `run_p2` reset them before use
+        Self {
+            cost: unsafe { Box::new_zeroed().assume_init() },
+            stack: ArrayVec::new(),
+            visits: unsafe { Box::new_zeroed().assume_init() },
+        }
+    }
+
+    pub fn part1(&mut self, input: &str) -> u32 {
+        crate::dispatch(|| unsafe { self.run_p1::<Unchecked>(input).assume() })
+    }
+
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<u32, ParseError> {
+        crate::dispatch(|| unsafe { self.run_p1::<Checked>(input) })
+    }
+
+    pub fn part2(&mut self, input: &str) -> u32 {
+        crate::dispatch(|| unsafe { self.run_p2::<Unchecked>(input).assume() })
+    }
+
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<u32, ParseError> {
+        crate::dispatch(|| unsafe { self.run_p2::<Checked>(input) })
+    }
+
+    #[inline(always)]
+    unsafe fn run_p1<M: Mode>(&mut self, input: &str) -> Result<u32, ParseError> {
+        self.cost.as_flattened_mut().fill([u32::MAX; 4]);
+        inner_p1::<M, 141>(input, &mut self.cost)
+    }
+
+    #[inline(always)]
+    unsafe fn run_p2<M: Mode>(&mut self, input: &str) -> Result<u32, ParseError> {
+        self.cost.as_flattened_mut().fill([u32::MAX; 4]);
+        self.visits.as_flattened_mut().fill(false);
+        self.stack.clear();
+        inner_p2::<M, 141>(input, &mut self.cost, &mut self.stack, &mut self.visits)
+    }
+}
 
 #[aoc(day16, part1)]
 pub fn part1(input: &str) -> u32 {
-    static mut COST: [[[u32; 4]; 141]; 141] = [[[u32::MAX; 4]; _]; _];
+    Solver::new().part1(input)
+}
 
-    unsafe { inner_p1::<141>(input, &mut COST) }
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+    Solver::new().try_part1(input)
 }
 
 const fn estimate_cost<const DIM: usize>(Index { y, x }: Index<DIM>) -> u32 {
@@ -38,17 +188,13 @@ impl<const DIM: usize> Ord for HeapEntry<DIM> {
     }
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p1<const DIM: usize>(input: &str, curr_cost: &mut [[[u32; 4]; DIM]; DIM]) -> u32 {
+#[inline(always)]
+unsafe fn inner_p1<M: Mode, const DIM: usize>(
+    input: &str,
+    curr_cost: &mut [[[u32; 4]; DIM]; DIM],
+) -> Result<u32, ParseError> {
     let input = input.as_bytes();
-    let start = Index::<DIM> {
-        x: 1,
-        y: DIM as i16 - 2,
-    };
-    let end = Index::<DIM> {
-        x: DIM as i16 - 2,
-        y: 1,
-    };
+    let (start, end) = ends::<M, DIM>(input)?;
 
     curr_cost[start.y as usize][start.x as usize][Direction::East as usize] = 0;
 
@@ -117,7 +263,7 @@ unsafe fn inner_p1<const DIM: usize>(input: &str, curr_cost: &mut [[[u32; 4]; DI
         }
 
         let new_index = index + dir.into();
-        if *input.get_unchecked(new_index.to()) != WALL {
+        if open::<M, DIM>(input, new_index)? {
             let step_cost = base_cost + 1;
             let step = &mut curr_cost[new_index.y as usize][new_index.x as usize][dir as usize];
 
@@ -138,37 +284,31 @@ unsafe fn inner_p1<const DIM: usize>(input: &str, curr_cost: &mut [[[u32; 4]; DI
         }
     }
 
-    *curr_cost[end.y as usize][end.x as usize]
+    Ok(*curr_cost[end.y as usize][end.x as usize]
         .iter()
         .min()
-        .assume()
+        .assume())
 }
 
 #[aoc(day16, part2)]
 pub fn part2(input: &str) -> u32 {
-    static mut COST: [[[u32; 4]; 141]; 141] = [[[u32::MAX; 4]; _]; _];
-    static mut STACK: ArrayVec<200, StackEntry<141>> = ArrayVec::new();
-    static mut VISITS: [[bool; 141]; 141] = [[false; _]; _];
+    Solver::new().part2(input)
+}
 
-    unsafe { inner_p2::<141>(input, &mut COST, &mut STACK, &mut VISITS) }
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+    Solver::new().try_part2(input)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p2<const DIM: usize>(
+#[inline(always)]
+unsafe fn inner_p2<M: Mode, const DIM: usize>(
     input: &str,
     curr_cost: &mut [[[u32; 4]; DIM]; DIM],
     stack: &mut ArrayVec<200, StackEntry<DIM>>,
     visited: &mut [[bool; DIM]; DIM],
-) -> u32 {
+) -> Result<u32, ParseError> {
     let input = input.as_bytes();
-    let start = Index::<DIM> {
-        x: 1,
-        y: DIM as i16 - 2,
-    };
-    let end = Index::<DIM> {
-        x: DIM as i16 - 2,
-        y: 1,
-    };
+    let (start, end) = ends::<M, DIM>(input)?;
 
     curr_cost[start.y as usize][start.x as usize][Direction::East as usize] = 0;
 
@@ -240,7 +380,7 @@ unsafe fn inner_p2<const DIM: usize>(
         }
 
         let new_index = index + dir.into();
-        if *input.get_unchecked(new_index.to()) != WALL {
+        if open::<M, DIM>(input, new_index)? {
             let step_cost = base_cost + 1;
             let step = &mut curr_cost[new_index.y as usize][new_index.x as usize][dir as usize];
 
@@ -268,10 +408,11 @@ unsafe fn inner_p2<const DIM: usize>(
         .min()
         .assume();
 
-    stack.push_unchecked(StackEntry {
+    let entry = StackEntry {
         index: end,
         dir: transmute(index as u8),
-    });
+    };
+    push::<M, _, _>(stack, entry, input, end.to(), BRANCHES)?;
     let mut count = 0;
 
     while let Some(StackEntry { index, dir }) = stack.pop() {
@@ -288,32 +429,35 @@ unsafe fn inner_p2<const DIM: usize>(
 
         if value >= 1_000 {
             if curr_tile[dir.rotate_clockwise() as usize] == value - 1_000 {
-                stack.push_unchecked(StackEntry {
+                let entry = StackEntry {
                     index,
                     dir: dir.rotate_clockwise(),
-                });
+                };
+                push::<M, _, _>(stack, entry, input, index.to(), BRANCHES)?;
             }
 
             if curr_tile[dir.rotate_widdershins() as usize] == value - 1_000 {
-                stack.push_unchecked(StackEntry {
+                let entry = StackEntry {
                     index,
                     dir: dir.rotate_widdershins(),
-                });
+                };
+                push::<M, _, _>(stack, entry, input, index.to(), BRANCHES)?;
             }
         }
 
         if value >= 1 {
             let old_pos = index - dir.into();
             if curr_cost[old_pos.y as usize][old_pos.x as usize][dir as usize] == value - 1 {
-                stack.push_unchecked(StackEntry {
+                let entry = StackEntry {
                     index: old_pos,
                     dir,
-                });
+                };
+                push::<M, _, _>(stack, entry, input, old_pos.to(), BRANCHES)?;
             }
         }
     }
 
-    count
+    Ok(count)
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -357,7 +501,10 @@ mod tests {
 
         let mut costs = [[[u32::MAX; _]; _]; _];
 
-        assert_eq!(unsafe { inner_p1::<15>(input, &mut costs) }, 7_036);
+        assert_eq!(
+            unsafe { inner_p1::<Checked, 15>(input, &mut costs) },
+            Ok(7_036)
+        );
     }
 
     #[test]
@@ -384,7 +531,10 @@ mod tests {
 
         let mut costs = [[[u32::MAX; _]; _]; _];
 
-        assert_eq!(unsafe { inner_p1::<17>(input, &mut costs) }, 11_048);
+        assert_eq!(
+            unsafe { inner_p1::<Checked, 17>(input, &mut costs) },
+            Ok(11_048)
+        );
     }
 
     #[test]
@@ -418,8 +568,8 @@ mod tests {
         let mut visits = [[false; _]; _];
 
         assert_eq!(
-            unsafe { inner_p2::<15>(input, &mut costs, &mut stack, &mut visits) },
-            45
+            unsafe { inner_p2::<Checked, 15>(input, &mut costs, &mut stack, &mut visits) },
+            Ok(45)
         );
     }
 
@@ -450,8 +600,8 @@ mod tests {
         let mut visits = [[false; _]; _];
 
         assert_eq!(
-            unsafe { inner_p2::<17>(input, &mut costs, &mut stack, &mut visits) },
-            64
+            unsafe { inner_p2::<Checked, 17>(input, &mut costs, &mut stack, &mut visits) },
+            Ok(64)
         );
     }
 
@@ -460,4 +610,68 @@ mod tests {
         let input = include_str!("../input/2024/day16.txt");
         assert_eq!(part2(input), 456);
     }
+
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day16.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 72_428);
+            assert_eq!(part2(input), 456);
+        });
+    }
+
+    #[test]
+    fn solver_reuse() {
+        let input = include_str!("../input/2024/day16.txt");
+        let mut solver = Solver::new();
+        assert_eq!(solver.part1(input), 72_428);
+        assert_eq!(solver.part1(input), 72_428);
+        assert_eq!(solver.part2(input), 456);
+        assert_eq!(solver.part2(input), 456);
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day16.txt");
+        assert_eq!(try_part1(input), Ok(72_428));
+        assert_eq!(try_part2(input), Ok(456));
+
+        let err = try_part1(&input.replace('S', ".")).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (140, 2, "the start in the bottom left", Some(b'.'))
+        );
+
+        assert_eq!(try_part1(input.trim_end()), Ok(72_428));
+        assert_eq!(try_part2(input.trim_end()), Ok(456));
+        let err = try_part2(&input[..input.len() - 2]).unwrap_err();
+        assert_eq!(
+            (err.line, err.expected, err.found),
+            (141, "another line", None)
+        );
+
+        // Next to the start, so the search reads them
+        for (pos, c, expected) in [
+            (139 * 142, ".", "a wall around the maze"),
+            (138 * 142 + 1, "x", "'#' or '.'"),
+        ] {
+            let mut input = input.to_owned();
+            input.replace_range(pos..pos + 1, c);
+            let err = try_part1(&input).unwrap_err();
+            assert_eq!((err.offset, err.expected), (pos, expected));
+        }
+    }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day16.txt");
+        assert_eq!(validate(input).map(|shape| shape.lines), Ok(141));
+
+        let mut bad_tile = input.to_owned();
+        bad_tile.replace_range(142 + 138..142 + 139, "x");
+        assert!(matches!(
+            validate(&bad_tile),
+            Err(InputError::UnexpectedByte(err)) if (err.line, err.column, err.expected) == (2, 139, "'#' or '.'")
+        ));
+    }
 }
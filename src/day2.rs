@@ -1,18 +1,35 @@
-use std::cmp::{min, Ordering};
+use std::{
+    cmp::{min, Ordering},
+    marker::PhantomData,
+};
 
 use aoc_runner_derive::aoc;
 
-use crate::{debug, Assume as _, Unreachable};
+use crate::{
+    debug, ensure, Assume as _, Checked, InputError, InputShape, Mode, ParseError, Unchecked,
+    Unreachable, EOL,
+};
 
-#[derive(Clone, Copy)]
-struct LineNumIter<'a> {
+struct LineNumIter<'a, M> {
+    input: &'a [u8],
     inner: &'a [u8],
     last_ended_line: bool,
     line_just_ended: bool,
+    /// Set in checked mode when `inner` starts with something other than a level
+    failed: bool,
+    mode: PhantomData<M>,
 }
 
+impl<M> Clone for LineNumIter<'_, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M> Copy for LineNumIter<'_, M> {}
+
 #[cfg(any(test, feature = "debug"))]
-impl std::fmt::Debug for LineNumIter<'_> {
+impl<M> std::fmt::Debug for LineNumIter<'_, M> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("LineNumIter")
             .field(
@@ -25,19 +42,33 @@ impl std::fmt::Debug for LineNumIter<'_> {
             )
             .field("last_ended_line", &self.last_ended_line)
             .field("line_just_ended", &self.line_just_ended)
+            .field("failed", &self.failed)
             .finish()
     }
 }
 
-impl<'a> LineNumIter<'a> {
+impl<'a, M: Mode> LineNumIter<'a, M> {
     fn new(s: &'a str) -> Self {
         Self {
+            input: s.as_bytes(),
             inner: s.as_bytes(),
             last_ended_line: false,
             line_just_ended: true,
+            failed: false,
+            mode: PhantomData,
         }
     }
 
+    fn offset(&self) -> usize {
+        self.input.len() - self.inner.len()
+    }
+
+    /// Fails if parsing stopped at something other than a level
+    fn finish(&self) -> Result<(), ParseError> {
+        ensure!(M, !self.failed, self.input, self.offset(), "level");
+        Ok(())
+    }
+
     fn jump_to_next_line(&mut self) {
         if !self.line_just_ended {
             debug!("Jumping to end of line: {self:?}");
@@ -47,12 +78,40 @@ impl<'a> LineNumIter<'a> {
     }
 }
 
-impl Iterator for LineNumIter<'_> {
-    type Item = i8;
+/// Reads a level of 3 or more digits from the start of `inner`, returning it and its length.
+/// These are too rare for [`LineNumIter::next`] to look for, so this is kept out of line and away
+/// from the iterator's state. Fails with the offset of the unexpected byte in checked mode
+#[cold]
+#[inline(never)]
+fn wide_level<M: Mode>(inner: &[u8]) -> Result<(i32, usize), usize> {
+    let digits = inner.iter().take_while(|c| c.is_ascii_digit()).count();
+    let mut level = 0_i32;
+    let mut overflowed = false;
+    for &digit in &inner[..digits] {
+        let digit = (digit - b'0') as i32;
+        level = level
+            .checked_mul(10)
+            .and_then(|l| l.checked_add(digit))
+            .unwrap_or_else(|| {
+                overflowed = true;
+                level.wrapping_mul(10).wrapping_add(digit)
+            });
+    }
+
+    match inner.get(digits) {
+        _ if M::CHECKED && (digits == 0 || overflowed) => Err(0),
+        Some(b' ' | &EOL) | None => Ok((level, digits)),
+        Some(_) => Err(digits),
+    }
+}
 
+impl<M: Mode> Iterator for LineNumIter<'_, M> {
+    type Item = i32;
+
+    #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-        unsafe fn inner(iter: &mut LineNumIter<'_>) -> Option<i8> {
+        #[inline(always)]
+        unsafe fn inner<M: Mode>(iter: &mut LineNumIter<'_, M>) -> Option<i32> {
             if iter.last_ended_line {
                 debug!("Ending line");
                 iter.last_ended_line = false;
@@ -62,246 +121,610 @@ impl Iterator for LineNumIter<'_> {
 
             iter.line_just_ended = false;
 
-            let len = iter.inner.len();
+            let inner = iter.inner;
+            let len = inner.len();
+            if len == 0 {
+                return None;
+            }
 
-            match &iter.inner[..min(3, len)] {
-                [n @ b'0'..=b'9', b' ' | b'\n', ..] | [n @ b'0'..=b'9'] => {
-                    if iter.inner.get(1).is_some_and(|&c| c == b'\n') {
-                        debug!("Line end reached");
-                        iter.last_ended_line = true;
+            // Spaces and new lines are both below `'0'`, so in well formed input one compare tells
+            // the end of a level from another digit
+            let ends = |index: usize| {
+                inner.get(index).is_none_or(|&c| {
+                    if M::CHECKED {
+                        c == b' ' || c == EOL
+                    } else {
+                        c < b'0'
                     }
-
-                    iter.inner = &iter.inner[min(2, len)..];
-                    Some((n - b'0') as i8)
-                }
-                [n1 @ b'0'..=b'9', n2 @ b'0'..=b'9', b' ' | b'\n']
-                | [n1 @ b'0'..=b'9', n2 @ b'0'..=b'9'] => {
-                    if iter.inner.get(2).is_some_and(|&c| c == b'\n') {
-                        debug!("Line end reached");
-                        iter.last_ended_line = true;
+                })
+            };
+            let all_digits =
+                |count: usize| !M::CHECKED || inner[..count].iter().all(u8::is_ascii_digit);
+            let digit = |index: usize| (inner.get_unchecked(index) - b'0') as i32;
+
+            let (level, digits) = if all_digits(1) && ends(1) {
+                (digit(0), 1)
+            } else if all_digits(2) && ends(2) {
+                (digit(0) * 10 + digit(1), 2)
+            } else {
+                match wide_level::<M>(inner) {
+                    Ok(level) => level,
+                    // Leave `inner` at the unexpected byte, so every later call ends up here too
+                    Err(unexpected) if M::CHECKED => {
+                        iter.inner = &inner[unexpected..];
+                        iter.failed = true;
+                        return None;
                     }
-
-                    iter.inner = &iter.inner[min(3, len)..];
-                    Some(((n1 - b'0') * 10 + n2 - b'0') as i8)
+                    Err(_) => Unreachable.assume(),
                 }
-                [] => None,
-                _ => Unreachable.assume(),
+            };
+
+            if inner.get(digits).is_none_or(|&c| c == EOL) {
+                debug!("Line end reached");
+                iter.last_ended_line = true;
             }
+
+            iter.inner = &inner[min(digits + 1, len)..];
+            Some(level)
         }
 
         unsafe { inner(self) }
     }
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn check_diff(first: i8, second: i8) -> bool {
-    let diff = (first - second).abs();
-    diff == 1 || diff == 2 || diff == 3
+/// What makes a report safe: adjacent levels must all increase or all decrease by `min_step` to
+/// `max_step`, once at most `removals` levels are removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Tolerance {
+    pub removals: usize,
+    /// At least 1, as adjacent levels can't be equal
+    pub min_step: i32,
+    pub max_step: i32,
+}
+
+impl Tolerance {
+    /// What [`part1`] counts as safe
+    pub const PART1: Self = Self {
+        removals: 0,
+        min_step: 1,
+        max_step: 3,
+    };
+    /// What [`part2`] counts as safe, with the Problem Dampener removing a level
+    pub const PART2: Self = Self {
+        removals: 1,
+        ..Self::PART1
+    };
+}
+
+/// The steps adjacent levels may change by, with the puzzle's known at compile time so checking
+/// them costs no more than a constant range
+trait Steps: Copy {
+    fn contains(self, diff: i32) -> bool;
+}
+
+/// The puzzle's steps, 1 to 3
+#[derive(Clone, Copy, Debug)]
+struct PuzzleSteps;
+
+impl Steps for PuzzleSteps {
+    #[inline(always)]
+    fn contains(self, diff: i32) -> bool {
+        diff == 1 || diff == 2 || diff == 3
+    }
+}
+
+impl Steps for Tolerance {
+    #[inline(always)]
+    fn contains(self, diff: i32) -> bool {
+        diff >= self.min_step && diff <= self.max_step
+    }
+}
+
+#[inline]
+unsafe fn check_diff(first: i32, second: i32, steps: impl Steps) -> bool {
+    steps.contains((first - second).abs())
+}
+
+/// Checks `input` is reports of levels that fit in an `i32`, as [`part1`] and [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let shape = InputShape::of(input.as_bytes())?;
+
+    let iter = &mut LineNumIter::<Checked>::new(input);
+    while {
+        iter.jump_to_next_line();
+        iter.next().is_some()
+    } {}
+    iter.finish()?;
+
+    Ok(shape)
 }
 
 #[aoc(day2, part1)]
 pub fn part1(input: &str) -> i32 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> i32 {
-        let mut count = 1_000;
-
-        let iter = &mut LineNumIter::new(input);
-        while let Some(first) = {
-            // Ensure the iterator has reached the end of the line (may not have happened due to copying)
-            iter.jump_to_next_line();
-            iter.next()
-        } {
-            let second = iter.next().assume();
+    Solver::new().part1(input)
+}
 
-            if !check_diff(first, second) {
-                count -= 1;
-                continue;
-            }
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part1(input)
+}
 
-            let dir = first.cmp(&second);
+#[inline(always)]
+unsafe fn inner_p1<M: Mode>(input: &str) -> Result<i32, ParseError> {
+    let mut count = 0;
+
+    let iter = &mut LineNumIter::<M>::new(input);
+    while let Some(first) = {
+        // Ensure the iterator has reached the end of the line (may not have happened due to copying)
+        iter.jump_to_next_line();
+        iter.next()
+    } {
+        count += check_p1(iter, first, PuzzleSteps) as i32;
+    }
 
-            let sub = iter
-                .try_fold(second, |last, curr| {
-                    if last.cmp(&curr) == dir && check_diff(last, curr) {
-                        Some(curr)
-                    } else {
-                        None
-                    }
-                })
-                .is_none();
-            count -= sub as i32;
+    iter.finish()?;
+    Ok(count)
+}
 
-            if !sub {
-                break;
-            }
-        }
+/// Whether the report starting with `first` is safe for part 1, reading its levels from `iter`
+#[inline(always)]
+unsafe fn check_p1<M: Mode, S: Steps>(iter: &mut LineNumIter<M>, first: i32, steps: S) -> bool {
+    let Some(second) = iter.next() else {
+        // A single level is safe as it is
+        return true;
+    };
 
-        count
+    if !check_diff(first, second, steps) {
+        return false;
     }
-    unsafe { inner(input) }
+
+    let dir = first.cmp(&second);
+    iter.try_fold(second, |last, curr| {
+        (last.cmp(&curr) == dir && check_diff(last, curr, steps)).then_some(curr)
+    })
+    .is_some()
 }
 
 #[derive(Debug)]
-struct Recurse {
+struct Recurse<S> {
     dir: Ordering,
-    penultimate: i8,
-    last: i8,
+    penultimate: i32,
+    last: i32,
     failure_hit: bool,
+    steps: S,
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn recurse(iter: &mut LineNumIter, hit_failure: &mut bool, data: Recurse) -> bool {
+#[inline(always)]
+unsafe fn recurse<M: Mode, S: Steps>(iter: &mut LineNumIter<M>, data: Recurse<S>) -> bool {
     let Recurse {
         dir,
         penultimate,
         last,
         failure_hit,
+        steps,
     } = data;
 
     let Some(curr) = iter.next() else {
         return true;
     };
-    if last.cmp(&curr) == dir && check_diff(last, curr) {
+    if last.cmp(&curr) == dir && check_diff(last, curr, steps) {
         recurse(
             iter,
-            hit_failure,
             Recurse {
                 dir,
                 penultimate: last,
                 last: curr,
                 failure_hit,
+                steps,
             },
         )
     } else if failure_hit {
         false
     } else {
-        *hit_failure = true;
         let skip_current = recurse(
             &mut iter.clone(),
-            &mut false,
             Recurse {
                 dir,
                 penultimate,
                 last,
                 failure_hit: true,
+                steps,
             },
         );
 
-        let skip_last = penultimate.cmp(&curr) == dir
-            && check_diff(penultimate, curr)
-            && recurse(
-                iter,
-                &mut false,
-                Recurse {
-                    dir,
-                    penultimate,
-                    last: curr,
-                    failure_hit: true,
-                },
-            );
-
-        skip_current || skip_last
+        skip_current
+            || penultimate.cmp(&curr) == dir
+                && check_diff(penultimate, curr, steps)
+                && recurse(
+                    iter,
+                    Recurse {
+                        dir,
+                        penultimate,
+                        last: curr,
+                        failure_hit: true,
+                        steps,
+                    },
+                )
     }
 }
 
 #[aoc(day2, part2)]
 pub fn part2(input: &str) -> i32 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> i32 {
-        let mut count = 1_000;
+    Solver::new().part2(input)
+}
+
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part2(input)
+}
+
+#[inline(always)]
+unsafe fn inner_p2<M: Mode>(input: &str) -> Result<i32, ParseError> {
+    let mut count = 0;
+
+    let iter = &mut LineNumIter::<M>::new(input);
+    while let Some(first) = {
+        // Ensure the iterator has reached the end of the line (may not have happened due to copying)
+        iter.jump_to_next_line();
+        iter.next()
+    } {
+        count += check_p2(iter, first, PuzzleSteps) as i32;
+
+        debug!("Line finished, count is {count}");
+    }
+
+    iter.finish()?;
+    Ok(count)
+}
+
+/// Whether the report starting with `first` is safe for part 2, reading its levels from `iter`
+#[inline(always)]
+unsafe fn check_p2<M: Mode, S: Steps>(iter: &mut LineNumIter<M>, first: i32, steps: S) -> bool {
+    let Some(second) = iter.next() else {
+        return true;
+    };
+
+    // The direction is voted on by the first 4 levels. Shorter reports are safe if removing any
+    // one level leaves a pair that follows on, as that leaves at most 2 levels
+    let mut dir_check_iter = *iter;
+    let Some(third) = dir_check_iter.next() else {
+        return true;
+    };
+    let Some(fourth) = dir_check_iter.next() else {
+        return check_diff(first, second, steps)
+            || check_diff(second, third, steps)
+            || check_diff(first, third, steps);
+    };
 
-        let iter = &mut LineNumIter::new(input);
+    let mut inc_count = 0;
+    let mut dec_count = 0;
+
+    for pair in [(first, second), (second, third), (third, fourth)] {
+        match pair.0.cmp(&pair.1) {
+            Ordering::Less => dec_count += 1,
+            Ordering::Equal => (),
+            Ordering::Greater => inc_count += 1,
+        }
+    }
+
+    let dir = match inc_count.cmp(&dec_count) {
+        Ordering::Equal => return false,
+        order => order,
+    };
+
+    if first.cmp(&second) == dir && check_diff(first, second, steps) {
+        recurse(
+            iter,
+            Recurse {
+                dir,
+                penultimate: first,
+                last: second,
+                failure_hit: false,
+                steps,
+            },
+        )
+    } else {
+        let third = iter.next().assume();
+
+        let skip_first = || {
+            second.cmp(&third) == dir
+                && check_diff(second, third, steps)
+                && recurse(
+                    &mut iter.clone(),
+                    Recurse {
+                        dir,
+                        penultimate: second,
+                        last: third,
+                        failure_hit: true,
+                        steps,
+                    },
+                )
+        };
+
+        let skip_second = || {
+            first.cmp(&third) == dir
+                && check_diff(first, third, steps)
+                && recurse(
+                    &mut iter.clone(),
+                    Recurse {
+                        dir,
+                        penultimate: first,
+                        last: third,
+                        failure_hit: true,
+                        steps,
+                    },
+                )
+        };
+
+        skip_first() || skip_second()
+    }
+}
+
+/// Counts the reports in `input` that are safe under `tolerance`, assuming the input is well
+/// formed as [`part1`] and [`part2`] do.
+///
+/// With [`Tolerance::PART1`] or [`Tolerance::PART2`], this checks each report as those parts do.
+/// More removals are checked more slowly, as each report's levels are collected first.
+pub fn count_safe(input: &str, tolerance: Tolerance) -> i32 {
+    Solver::new().count_safe(input, tolerance)
+}
+
+/// [`count_safe`], reporting malformed input rather than assuming it is well formed.
+pub fn try_count_safe(input: &str, tolerance: Tolerance) -> Result<i32, ParseError> {
+    Solver::new().try_count_safe(input, tolerance)
+}
+
+/// Solves day 2, keeping each report's levels, and the fewest removals that leave it safe up to
+/// each of them, in buffers it reuses between inputs. Only [`Solver::count_safe`] with more than
+/// one removal uses them
+#[derive(Clone, Debug, Default)]
+pub struct Solver {
+    levels: Vec<i32>,
+    fewest: Vec<usize>,
+}
+
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn part1(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { inner_p1::<Unchecked>(input).assume() })
+    }
+
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { inner_p1::<Checked>(input) })
+    }
+
+    pub fn part2(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { inner_p2::<Unchecked>(input).assume() })
+    }
+
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { inner_p2::<Checked>(input) })
+    }
+
+    /// [`count_safe`], reusing the solver's buffers.
+    pub fn count_safe(&mut self, input: &str, tolerance: Tolerance) -> i32 {
+        crate::dispatch(|| unsafe { self.inner_count::<Unchecked>(input, tolerance).assume() })
+    }
+
+    /// [`Solver::count_safe`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_count_safe(&mut self, input: &str, tolerance: Tolerance) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { self.inner_count::<Checked>(input, tolerance) })
+    }
+
+    #[inline(always)]
+    unsafe fn inner_count<M: Mode>(
+        &mut self,
+        input: &str,
+        tolerance: Tolerance,
+    ) -> Result<i32, ParseError> {
+        assert!(
+            1 <= tolerance.min_step && tolerance.min_step <= tolerance.max_step,
+            "steps must be 1 or more, got {}..={}",
+            tolerance.min_step,
+            tolerance.max_step
+        );
+
+        let puzzle_steps = (tolerance.min_step, tolerance.max_step) == (1, 3);
+        let mut count = 0;
+
+        let iter = &mut LineNumIter::<M>::new(input);
         while let Some(first) = {
-            // Ensure the iterator has reached the end of the line (may not have happened due to copying)
             iter.jump_to_next_line();
             iter.next()
         } {
-            let second = iter.next().assume();
+            let safe = match tolerance.removals {
+                0 if puzzle_steps => check_p1(iter, first, PuzzleSteps),
+                0 => check_p1(iter, first, tolerance),
+                1 if puzzle_steps => check_p2(iter, first, PuzzleSteps),
+                1 => check_p2(iter, first, tolerance),
+                _ => {
+                    self.levels.clear();
+                    self.levels.push(first);
+                    self.levels.extend(iter.by_ref());
+                    tolerates(&self.levels, tolerance, &mut self.fewest)
+                }
+            };
+            count += safe as i32;
+        }
 
-            let mut dir_check_iter = *iter;
-            let third = dir_check_iter.next().assume();
-            let fourth = dir_check_iter.next().assume();
+        iter.finish()?;
+        Ok(count)
+    }
+}
 
-            let mut inc_count = 0;
-            let mut dec_count = 0;
+/// Whether `levels` are safe under `tolerance`, for any number of removals, counting them in
+/// `fewest`
+fn tolerates(levels: &[i32], tolerance: Tolerance, fewest: &mut Vec<usize>) -> bool {
+    let len = levels.len();
+    if len <= tolerance.removals + 1 {
+        return true;
+    }
 
-            for pair in [(first, second), (second, third), (third, fourth)] {
-                match pair.0.cmp(&pair.1) {
-                    Ordering::Less => dec_count += 1,
-                    Ordering::Equal => (),
-                    Ordering::Greater => inc_count += 1,
+    // The fewest removals that leave a safe report ending at each level. The level kept before
+    // it can't be more than `removals` levels back
+    fewest.clear();
+    fewest.resize(len, 0);
+    [Ordering::Less, Ordering::Greater].into_iter().any(|dir| {
+        for curr in 0..len {
+            fewest[curr] = curr;
+            for prev in curr.saturating_sub(tolerance.removals + 1)..curr {
+                if levels[prev].cmp(&levels[curr]) == dir
+                    && unsafe { check_diff(levels[prev], levels[curr], tolerance) }
+                {
+                    fewest[curr] = min(fewest[curr], fewest[prev] + curr - prev - 1);
                 }
             }
+        }
+        (0..len).any(|last| fewest[last] + len - 1 - last <= tolerance.removals)
+    })
+}
 
-            let dir = match inc_count.cmp(&dec_count) {
-                Ordering::Equal => {
-                    count -= 1;
-                    continue;
-                }
-                order => order,
-            };
+/// The way a report's levels change
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+}
 
-            let valid = if first.cmp(&second) == dir && check_diff(first, second) {
-                let mut hit_failure = false;
-                let valid = recurse(
-                    iter,
-                    &mut hit_failure,
-                    Recurse {
-                        dir,
-                        penultimate: first,
-                        last: second,
-                        failure_hit: false,
-                    },
-                );
-                if valid && !hit_failure {
-                    break;
-                }
+/// Why [`part1`] or [`part2`] found a report safe or unsafe.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Diagnosis {
+    /// The report's line, from 1
+    pub line: usize,
+    pub safe: bool,
+    /// The direction the levels were checked against, from the first pair for part 1 and most
+    /// of the first four levels for part 2, or the levels kept in shorter reports. `None` if they
+    /// don't settle on one
+    pub direction: Option<Direction>,
+    /// The indices of the first adjacent levels, from 0, that don't change in `direction` by 1
+    /// to 3. `None` if every pair does, or for part 2 if there's no `direction`
+    pub offending: Option<(usize, usize)>,
+    /// The index of the level the Problem Dampener removed to make the report safe, for part 2
+    pub removed: Option<usize>,
+}
 
-                valid
+/// Diagnoses every report in `input` as `part` (1 or 2) classifies it, reporting malformed
+/// input as [`try_part1`] and [`try_part2`] do.
+pub fn diagnose(input: &str, part: u8) -> Result<Vec<Diagnosis>, ParseError> {
+    assert!(matches!(part, 1 | 2), "day 2 has no part {part}");
+
+    let mut diagnoses = Vec::new();
+    let mut levels = Vec::new();
+    let iter = &mut LineNumIter::<Checked>::new(input);
+    while let Some(first) = {
+        iter.jump_to_next_line();
+        iter.next()
+    } {
+        levels.clear();
+        levels.push(first);
+        levels.extend(&mut iter.clone());
+
+        let safe = unsafe {
+            if part == 1 {
+                check_p1(iter, first, PuzzleSteps)
             } else {
-                let third = iter.next().assume();
-
-                let skip_first = || {
-                    second.cmp(&third) == dir
-                        && check_diff(second, third)
-                        && recurse(
-                            &mut iter.clone(),
-                            &mut false,
-                            Recurse {
-                                dir,
-                                penultimate: second,
-                                last: third,
-                                failure_hit: true,
-                            },
-                        )
-                };
-
-                let skip_second = || {
-                    first.cmp(&third) == dir
-                        && check_diff(first, third)
-                        && recurse(
-                            &mut iter.clone(),
-                            &mut false,
-                            Recurse {
-                                dir,
-                                penultimate: first,
-                                last: third,
-                                failure_hit: true,
-                            },
-                        )
-                };
-
-                skip_first() || skip_second()
-            };
+                check_p2(iter, first, PuzzleSteps)
+            }
+        };
+        let diagnosis = explain(diagnoses.len() + 1, &levels, part);
+        debug_assert_eq!(diagnosis.safe, safe, "line {}", diagnosis.line);
+        diagnoses.push(diagnosis);
+    }
 
-            count -= !valid as i32;
+    iter.finish()?;
+    Ok(diagnoses)
+}
 
-            debug!("Line finished, count is {count}");
+/// Works out why [`check_p1`] or [`check_p2`] classify a report with `levels` as they do, which
+/// they don't keep track of themselves so counting safe reports doesn't pay for it
+fn explain(line: usize, levels: &[i32], part: u8) -> Diagnosis {
+    let follows = |pair: &[i32], dir| {
+        pair[0].cmp(&pair[1]) == dir && unsafe { check_diff(pair[0], pair[1], PuzzleSteps) }
+    };
+    // The direction of a pair of levels that follow on, or `Equal` if they don't
+    let pair_dir = |pair: &[i32]| match *pair {
+        [a, b] if unsafe { check_diff(a, b, PuzzleSteps) } => a.cmp(&b),
+        _ => Ordering::Equal,
+    };
+    let first_offending =
+        |levels: &[i32], dir| levels.windows(2).position(|pair| !follows(pair, dir));
+    let without = |removed: usize| {
+        let mut kept = levels.to_vec();
+        kept.remove(removed);
+        kept
+    };
+
+    let (safe, dir, offending, removed) = match (part, levels.len()) {
+        (1, _) => {
+            let dir = levels
+                .get(1)
+                .map_or(Ordering::Equal, |second| levels[0].cmp(second));
+            let offending = first_offending(levels, dir);
+            (offending.is_none(), dir, offending, None)
+        }
+        // Too short to vote on a direction, so every removal is tried, in order
+        (_, ..4) => {
+            let dir = levels.get(..2).map_or(Ordering::Equal, pair_dir);
+            let offending = levels
+                .windows(2)
+                .position(|pair| dir == Ordering::Equal || pair_dir(pair) != dir);
+            let removal = offending.and_then(|_| {
+                (0..levels.len())
+                    .map(|removed| (removed, pair_dir(&without(removed))))
+                    .find(|&(_, dir)| dir != Ordering::Equal || levels.len() <= 2)
+            });
+            match removal {
+                Some((removed, dir)) => (true, dir, offending, Some(removed)),
+                None => (offending.is_none(), dir, offending, None),
+            }
         }
+        _ => {
+            let votes = levels[..4]
+                .windows(2)
+                .map(|pair| pair[0].cmp(&pair[1]) as i32);
+            let dir = votes.sum::<i32>().cmp(&0);
+            if dir == Ordering::Equal {
+                (false, dir, None, None)
+            } else {
+                // The level after the first offending pair is tried before the one in it, other
+                // than in the first pair
+                let offending = first_offending(levels, dir);
+                let removed = offending.and_then(|index| {
+                    let candidates = if index == 0 {
+                        [0, 1]
+                    } else {
+                        [index + 1, index]
+                    };
+                    candidates
+                        .into_iter()
+                        .find(|&removed| first_offending(&without(removed), dir).is_none())
+                });
+                (
+                    offending.is_none() || removed.is_some(),
+                    dir,
+                    offending,
+                    removed,
+                )
+            }
+        }
+    };
 
-        count
+    Diagnosis {
+        line,
+        safe,
+        direction: match dir {
+            Ordering::Less => Some(Direction::Increasing),
+            Ordering::Equal => None,
+            Ordering::Greater => Some(Direction::Decreasing),
+        },
+        offending: offending.map(|index| (index, index + 1)),
+        removed,
     }
-    unsafe { inner(input) }
 }
 
 #[cfg(test)]
@@ -317,11 +740,11 @@ mod tests {
 
     #[test]
     fn data() {
-        let mut data = LineNumIter::new(INPUT);
+        let mut data = LineNumIter::<Unchecked>::new(INPUT);
         let mut index = 0;
 
         for line in INPUT.lines() {
-            for num in line.split_whitespace().map(|n| n.parse::<i8>().unwrap()) {
+            for num in line.split_whitespace().map(|n| n.parse::<i32>().unwrap()) {
                 assert_eq!(data.next(), Some(num), "Invalid output at {index}");
                 index += 1;
             }
@@ -334,6 +757,46 @@ mod tests {
         assert_eq!(data.next(), None);
     }
 
+    #[test]
+    fn wide_levels() {
+        let input =
+            "700 699 697 694 693\n1 2 7 8 9\n1000 1003 1002 1004 1005\n2147483647 2147483646\n";
+        let iter = &mut LineNumIter::<Checked>::new(input);
+        let line = |iter: &mut LineNumIter<_>| iter.collect::<Vec<_>>();
+        assert_eq!(line(iter), [700, 699, 697, 694, 693]);
+        assert_eq!(line(iter), [1, 2, 7, 8, 9]);
+        assert_eq!(line(iter), [1000, 1003, 1002, 1004, 1005]);
+        assert_eq!(line(iter), [i32::MAX, i32::MAX - 1]);
+        assert_eq!(line(iter), []);
+        assert_eq!(try_part1(input), Ok(2));
+        assert_eq!(try_part2(&input[..input.len() - 22]), Ok(2));
+
+        let err = try_part1("1 2 2147483648\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.expected), (1, 5, "level"));
+        let err = try_part1("1 2 345x\n").unwrap_err();
+        assert_eq!((err.column, err.found), (8, Some(b'x')));
+    }
+
+    #[test]
+    fn reports_counted() {
+        let input = include_str!("../input/2024/day2.txt");
+        // The real input has every report safe as it is after the rest
+        let mut reversed: Vec<_> = input.lines().rev().collect();
+        assert_eq!(part1(&(reversed.join("\n") + "\n")), 287);
+        assert_eq!(part2(&(reversed.join("\n") + "\n")), 354);
+
+        reversed.truncate(100);
+        let short = reversed.join("\n");
+        assert_eq!(
+            validate(&(short.clone() + "\n")).map(|shape| shape.records),
+            Ok(100)
+        );
+        assert_eq!(try_part1(&short), Ok(part1(&(short.clone() + "\n"))));
+        assert_eq!(try_part2(&short), Ok(part2(&(short.clone() + "\n"))));
+        assert_eq!(part1(INPUT), 2);
+        assert_eq!(part2(INPUT), 4);
+    }
+
     #[test]
     fn real_p1() {
         let input = include_str!("../input/2024/day2.txt");
@@ -345,4 +808,199 @@ mod tests {
         let input = include_str!("../input/2024/day2.txt");
         assert_eq!(part2(input), 354);
     }
+
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day2.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 287);
+            assert_eq!(part2(input), 354);
+        });
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day2.txt");
+        assert_eq!(try_part1(input), Ok(part1(input)));
+        assert_eq!(try_part2(input), Ok(part2(input)));
+
+        let mut input = input.to_owned();
+        input.replace_range(2..3, "x");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 3, "level", Some(b'x'))
+        );
+    }
+
+    #[test]
+    fn diagnoses() {
+        let safe = |line, direction, offending, removed| Diagnosis {
+            line,
+            safe: true,
+            direction: Some(direction),
+            offending,
+            removed,
+        };
+        let not_safe = |line, direction, offending| Diagnosis {
+            safe: false,
+            ..safe(line, direction, Some(offending), None)
+        };
+        use Direction::*;
+
+        assert_eq!(
+            diagnose(INPUT, 1),
+            Ok(vec![
+                safe(1, Decreasing, None, None),
+                not_safe(2, Increasing, (1, 2)),
+                not_safe(3, Decreasing, (2, 3)),
+                not_safe(4, Increasing, (1, 2)),
+                not_safe(5, Decreasing, (2, 3)),
+                safe(6, Increasing, None, None),
+            ])
+        );
+        assert_eq!(
+            diagnose(INPUT, 2),
+            Ok(vec![
+                safe(1, Decreasing, None, None),
+                not_safe(2, Increasing, (1, 2)),
+                not_safe(3, Decreasing, (2, 3)),
+                safe(4, Increasing, Some((1, 2)), Some(2)),
+                safe(5, Decreasing, Some((2, 3)), Some(3)),
+                safe(6, Increasing, None, None),
+            ])
+        );
+
+        let input = include_str!("../input/2024/day2.txt");
+        for (part, answer) in [(1, 287), (2, 354)] {
+            let diagnoses = diagnose(input, part).unwrap();
+            assert_eq!(diagnoses.len(), 1_000);
+            assert_eq!(diagnoses.iter().filter(|d| d.safe).count(), answer);
+        }
+    }
+
+    #[test]
+    fn solver_reuse() {
+        let input = include_str!("../input/2024/day2.txt");
+        let two = Tolerance {
+            removals: 2,
+            ..Tolerance::PART2
+        };
+        let mut solver = Solver::new();
+        let expected = count_safe(input, two);
+        assert_eq!(solver.count_safe(input, two), expected);
+        assert_eq!(solver.count_safe(INPUT, two), count_safe(INPUT, two));
+        assert!(solver.try_count_safe("1 2 x\n", two).is_err());
+        assert_eq!(solver.count_safe(input, two), expected);
+        assert_eq!(solver.part1(input), 287);
+        assert_eq!(solver.part2(input), 354);
+    }
+
+    #[test]
+    fn tolerances() {
+        let input = include_str!("../input/2024/day2.txt");
+        assert_eq!(count_safe(input, Tolerance::PART1), 287);
+        assert_eq!(count_safe(input, Tolerance::PART2), 354);
+        assert_eq!(try_count_safe(input, Tolerance::PART2), Ok(354));
+
+        // Removing each combination of up to `removals` levels
+        fn brute_force(levels: &[i32], tolerance: Tolerance) -> bool {
+            let safe = levels.windows(2).all(|pair| {
+                (tolerance.min_step..=tolerance.max_step).contains(&(pair[1] - pair[0]))
+            }) || levels.windows(2).all(|pair| {
+                (tolerance.min_step..=tolerance.max_step).contains(&(pair[0] - pair[1]))
+            });
+            safe || tolerance.removals > 0
+                && (0..levels.len()).any(|index| {
+                    let mut levels = levels.to_vec();
+                    levels.remove(index);
+                    let removals = tolerance.removals - 1;
+                    brute_force(
+                        &levels,
+                        Tolerance {
+                            removals,
+                            ..tolerance
+                        },
+                    )
+                })
+        }
+
+        for (removals, min_step, max_step) in
+            [(0, 1, 3), (1, 1, 3), (1, 2, 5), (2, 1, 3), (3, 1, 2)]
+        {
+            let tolerance = Tolerance {
+                removals,
+                min_step,
+                max_step,
+            };
+            let expected = input
+                .lines()
+                .filter(|line| {
+                    let levels: Vec<i32> = line.split(' ').map(|n| n.parse().unwrap()).collect();
+                    brute_force(&levels, tolerance)
+                })
+                .count();
+            assert_eq!(
+                count_safe(input, tolerance),
+                expected as i32,
+                "{tolerance:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn short_reports() {
+        let input = "5\n1 2\n4 4\n1 9\n3 2 1\n1 5 2\n2 2 2\n1 5 9\n7 1 4";
+        assert_eq!(
+            validate(&format!("{input}\n")).map(|shape| shape.records),
+            Ok(9)
+        );
+        assert_eq!((part1(input), try_part1(input)), (3, Ok(3)));
+        assert_eq!((part2(input), try_part2(input)), (7, Ok(7)));
+
+        for removals in 0..3 {
+            let tolerance = Tolerance {
+                removals,
+                ..Tolerance::PART1
+            };
+            for line in input.lines() {
+                let levels: Vec<i32> = line.split(' ').map(|n| n.parse().unwrap()).collect();
+                assert_eq!(
+                    count_safe(line, tolerance),
+                    tolerates(&levels, tolerance, &mut Vec::new()) as i32,
+                    "{line} with {removals} removals"
+                );
+            }
+        }
+
+        let diagnoses = diagnose(input, 2).unwrap();
+        let removed: Vec<_> = diagnoses
+            .iter()
+            .filter_map(|d| Some((d.line, d.removed?)))
+            .collect();
+        assert_eq!(removed, [(3, 0), (4, 0), (6, 0), (9, 0)]);
+        assert_eq!(
+            (diagnoses[5].direction, diagnoses[5].offending),
+            (Some(Direction::Decreasing), Some((0, 1)))
+        );
+        assert_eq!((diagnoses[6].safe, diagnoses[6].direction), (false, None));
+    }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day2.txt");
+        assert_eq!(validate(input).map(|shape| shape.records), Ok(1_000));
+        assert_eq!(
+            validate(&format!("{INPUT}\n")).map(|shape| shape.records),
+            Ok(6)
+        );
+        assert_eq!(validate(INPUT), Err(InputError::MissingTrailingNewline));
+
+        let short = input.replacen("2 4 6 9 10 9\n", "2 4 6\n", 1);
+        assert_eq!(validate(&short).map(|shape| shape.records), Ok(1_000));
+        assert!(matches!(
+            validate("1 2\n\n3\n"),
+            Err(InputError::UnexpectedByte(ParseError { line: 2, .. }))
+        ));
+    }
 }
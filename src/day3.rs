@@ -1,32 +1,34 @@
 use aoc_runner_derive::aoc;
-use memchr::{arch::all::packedpair::HeuristicFrequencyRank, memmem::FinderBuilder, Memchr};
-
-use crate::{debug, p};
-
-struct Aoc3;
-impl HeuristicFrequencyRank for Aoc3 {
-    fn rank(&self, byte: u8) -> u8 {
-        [
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 15, 16, 0, 16, 15, 16, 17, 20, 255, 246, 15, 17, 120, 16, 0, 15, 42, 61, 65,
-            64, 65, 63, 66, 63, 64, 60, 18, 15, 15, 0, 16, 15, 15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 16, 0, 16, 15, 0, 0, 16, 0, 14, 9, 79, 17,
-            0, 101, 0, 0, 0, 112, 115, 20, 62, 0, 0, 34, 15, 36, 97, 0, 101, 0, 16, 0, 17, 0, 16,
-            15, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
-        ][byte as usize]
-    }
+use memchr::{memmem::FinderBuilder, Memchr};
+
+use crate::{
+    debug, ensure, p, Assume as _, Checked, InputError, InputShape, Mode, ParseError, Unchecked,
+};
+
+#[path = "ranks/day3.rs"]
+mod ranks;
+use ranks::Aoc3;
+
+/// Checks `input` ends in a new line and meets [`try_part2`]'s requirements on `don't()` and
+/// `do()`. [`part1`] makes no assumptions.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let shape = InputShape::of(input.as_bytes())?;
+    unsafe { enabled::<Checked>(input, |_| ())? };
+
+    Ok(shape)
 }
 
 #[aoc(day3, part1)]
 pub fn part1(input: &str) -> u32 {
-    unsafe { inner_part1(input.as_bytes()) }
+    crate::dispatch(|| unsafe { inner_part1(input.as_bytes()) })
+}
+
+/// [`part1`] accepts any input, so this never fails.
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+    Ok(part1(input))
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
+#[inline(always)]
 unsafe fn inner_part1(input: &[u8]) -> u32 {
     let mut sum = 0;
 
@@ -80,27 +82,61 @@ unsafe fn inner_part1(input: &[u8]) -> u32 {
 
 #[aoc(day3, part2)]
 pub fn part2(input: &str) -> u32 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> u32 {
-        let mut input = input.as_bytes();
-
-        let finder_dont = FinderBuilder::new().build_forward_with_ranker(Aoc3, "'t".as_bytes());
-        let finder_do = FinderBuilder::new().build_forward_with_ranker(Aoc3, "do(".as_bytes());
+    crate::dispatch(|| unsafe { inner_p2::<Unchecked>(input).assume() })
+}
 
-        let mut sum = 0;
-        while let Some(pos) = finder_dont.find(input) {
-            debug!("Don't position {pos}");
-            sum += inner_part1(&input[..pos - 3]);
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+///
+/// Every `'t` must be part of a `don't()`, and every `do(` part of a `do()`.
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+    crate::dispatch(|| unsafe { inner_p2::<Checked>(input) })
+}
 
-            let pos = pos + finder_do.find(&input[pos + 4..]).unwrap_or(input.len() - 4);
-            debug!("Do position {pos}");
-            input = &input[pos + 4..];
-        }
+#[inline(always)]
+unsafe fn inner_p2<M: Mode>(input: &str) -> Result<u32, ParseError> {
+    let mut sum = 0;
+    enabled::<M>(input, |section| sum += inner_part1(section))?;
+    Ok(sum)
+}
 
-        sum + inner_part1(input)
+/// Calls `section` with each stretch of `input` that isn't between a `don't()` and the next
+/// `do()`. In checked mode, also checks the markers are whole
+#[inline(always)]
+unsafe fn enabled<M: Mode>(input: &str, mut section: impl FnMut(&[u8])) -> Result<(), ParseError> {
+    let full = input.as_bytes();
+    let mut input = full;
+
+    let finder_dont = FinderBuilder::new().build_forward_with_ranker(Aoc3, "'t".as_bytes());
+    let finder_do = FinderBuilder::new().build_forward_with_ranker(Aoc3, "do(".as_bytes());
+
+    while let Some(pos) = finder_dont.find(input) {
+        debug!("Don't position {pos}");
+        ensure!(
+            M,
+            input.get(pos.wrapping_sub(3)..pos + 4) == Some(b"don't()"),
+            full,
+            full.len() - input.len() + pos.saturating_sub(3),
+            "\"don't()\""
+        );
+        section(&input[..pos - 3]);
+
+        let pos = pos
+            + finder_do
+                .find(&input[pos + 4..])
+                .unwrap_or(input.len() - pos - 4);
+        debug!("Do position {pos}");
+        input = &input[pos + 4..];
+        ensure!(
+            M,
+            input.is_empty() || input.starts_with(b"do()"),
+            full,
+            full.len() - input.len(),
+            "\"do()\""
+        );
     }
 
-    unsafe { inner(input) }
+    section(input);
+    Ok(())
 }
 
 #[cfg(test)]
@@ -131,4 +167,37 @@ xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
     fn p2_real() {
         assert_eq!(part2(REAL_INPUT), 80_747_545);
     }
+
+    #[test]
+    fn real_scalar() {
+        crate::with_scalar(|| {
+            assert_eq!(part1(REAL_INPUT), 182_619_815);
+            assert_eq!(part2(REAL_INPUT), 80_747_545);
+        });
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(try_part1(REAL_INPUT), Ok(182_619_815));
+        assert_eq!(try_part2(REAL_INPUT), Ok(80_747_545));
+
+        let err = try_part2("mul(1,2)don't(mul(3,4)").unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 9, "\"don't()\"", Some(b'd'))
+        );
+    }
+
+    #[test]
+    fn ranks() {
+        use memchr::arch::all::packedpair::HeuristicFrequencyRank;
+
+        let ranks = crate::rank::Ranks(std::array::from_fn(|byte| Aoc3.rank(byte as u8)));
+        assert_eq!(ranks.module("Aoc3"), include_str!("ranks/day3.rs"));
+    }
+
+    #[test]
+    fn validate_real() {
+        assert_eq!(validate(REAL_INPUT).map(|shape| shape.lines), Ok(6));
+    }
 }
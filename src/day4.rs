@@ -2,11 +2,31 @@ use std::mem::transmute;
 
 use aoc_runner_derive::aoc;
 
-use crate::assume;
+use crate::{
+    assume, ensure, Assume as _, Checked, InputError, InputShape, Mode, ParseError, Unchecked, EOL,
+};
+
+/// Checks `input` is a rectangular grid of `XMAS` letters, as [`part1`] and [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let bytes = input.as_bytes();
+    let shape = InputShape::of(bytes)?;
+    let width = memchr::memchr(EOL, bytes).unwrap_or_default();
+    let shape = shape.expect_width(bytes, shape.lines, width)?;
+    for pos in 0..bytes.len() {
+        letter::<Checked>(bytes, width + 1, pos)?;
+    }
+
+    Ok(shape)
+}
 
 #[aoc(day4, part1)]
 pub fn part1(input: &str) -> u32 {
-    unsafe { part1_inner::<141>(input.as_bytes()) }
+    crate::dispatch(|| unsafe { part1_inner::<Unchecked>(input.as_bytes()).assume() })
+}
+
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<u32, ParseError> {
+    crate::dispatch(|| unsafe { part1_inner::<Checked>(input.as_bytes()) })
 }
 
 const X: u8 = b'X';
@@ -17,48 +37,135 @@ const S: u8 = b'S';
 const XMAS: u32 = unsafe { transmute::<[u8; 4], u32>([X, M, A, S]) };
 const SAMX: u32 = unsafe { transmute::<[u8; 4], u32>([S, A, M, X]) };
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
+/// Length of each line of the grid, including the new line
+trait LineLen: Copy {
+    fn get(self) -> usize;
+}
+
+/// A line length known at compile time, for the common grid sizes
+#[derive(Clone, Copy)]
+struct Fixed<const LINE_LEN: usize>;
+
+impl<const LINE_LEN: usize> LineLen for Fixed<LINE_LEN> {
+    #[inline(always)]
+    fn get(self) -> usize {
+        LINE_LEN
+    }
+}
+
+impl LineLen for usize {
+    #[inline(always)]
+    fn get(self) -> usize {
+        self
+    }
+}
+
+/// Checks the byte at `pos` is an `XMAS` letter, or a new line at the end of a line
+#[inline(always)]
+fn letter<Md: Mode>(input: &[u8], line_len: usize, pos: usize) -> Result<(), ParseError> {
+    let c = input[pos];
+    if pos % line_len == line_len - 1 {
+        ensure!(Md, c == EOL, input, pos, "'\\n'");
+    } else {
+        ensure!(
+            Md,
+            matches!(c, X | M | A | S),
+            input,
+            pos,
+            "one of \"XMAS\""
+        );
+    }
+
+    Ok(())
+}
+
+/// Finds each `MATCH` from `start` up to `end`. In checked mode, also checks every byte on the way
+/// with [`letter`]
 #[inline]
-unsafe fn iter_offset<const MATCH: u8>(
+unsafe fn iter_offset<Md: Mode, const MATCH: u8>(
     input: &[u8],
+    line_len: usize,
     start: usize,
     end: usize,
-) -> impl Iterator<Item = usize> + '_ {
-    (start..end).filter(|&n| *input.get_unchecked(n) == MATCH)
+) -> impl Iterator<Item = Result<usize, ParseError>> + '_ {
+    (start..end).filter_map(move |pos| {
+        if let Err(err) = letter::<Md>(input, line_len, pos) {
+            return Some(Err(err));
+        }
+        (*input.get_unchecked(pos) == MATCH).then_some(Ok(pos))
+    })
+}
+
+/// The length of each line, including the new line, from the first new line. The end of the
+/// input ends the last line, as `aoc-runner` trims the trailing new line. In checked mode, also
+/// checks the last line is as long as the first
+#[inline(always)]
+fn line_len<Md: Mode>(input: &[u8]) -> Result<usize, ParseError> {
+    let line_len = memchr::memchr(EOL, input).map_or(input.len() + 1, |width| width + 1);
+    ensure!(
+        Md,
+        input.len() % line_len == 0 || input.len() % line_len == line_len - 1,
+        input,
+        input.len(),
+        "a last line as long as the first"
+    );
+
+    Ok(line_len)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn part1_inner<const LINE_LEN: usize>(input: &[u8]) -> u32
-where
-    [(); LINE_LEN + 1]:,
-    [(); LINE_LEN - 1]:,
-{
+#[inline(always)]
+unsafe fn part1_inner<Md: Mode>(input: &[u8]) -> Result<u32, ParseError> {
+    if input.is_empty() {
+        return Ok(0);
+    }
+    let line_len = line_len::<Md>(input)?;
+
+    // The edge handling in `part1_grid` needs at least 7 full lines
+    if input.len() % line_len != 0 || input.len() < 6 * line_len + 7 {
+        return part1_small::<Md>(input, line_len);
+    }
+
+    match line_len {
+        141 => part1_grid::<Md, _>(input, Fixed::<141>),
+        11 => part1_grid::<Md, _>(input, Fixed::<11>),
+        _ => part1_grid::<Md, _>(input, line_len),
+    }
+}
+
+/// Searches a grid of at least 7 lines, each `line_len` long, with a trailing new line
+#[inline(always)]
+unsafe fn part1_grid<Md: Mode, L: LineLen>(input: &[u8], line_len: L) -> Result<u32, ParseError> {
+    let line_len = line_len.get();
+
     // Assume trailing new line
-    let len = const { LINE_LEN * (LINE_LEN - 1) - 1 };
+    let len = input.len() - 1;
+    ensure!(Md, input[len] == EOL, input, len, "'\\n'");
     assume!(input[len] == b'\n', "Expected trailing new line");
 
-    let iter_offset = iter_offset::<X>;
+    let iter_offset = |start, end| iter_offset::<Md, X>(input, line_len, start, end);
 
-    let down = line::<LINE_LEN>;
-    let up = line_neg::<LINE_LEN>;
+    let down = |input, pos| line(input, pos, line_len);
+    let up = |input, pos| line_neg(input, pos, line_len);
 
-    let down_right = line::<{ LINE_LEN + 1 }>;
-    let up_left = line_neg::<{ LINE_LEN + 1 }>;
+    let down_right = |input, pos| line(input, pos, line_len + 1);
+    let up_left = |input, pos| line_neg(input, pos, line_len + 1);
 
-    let down_left = line::<{ LINE_LEN - 1 }>;
-    let up_right = line_neg::<{ LINE_LEN - 1 }>;
+    let down_left = |input, pos| line(input, pos, line_len - 1);
+    let up_right = |input, pos| line_neg(input, pos, line_len - 1);
 
     let mut count = 0;
 
     // Top few can't have up, left
-    for pos in iter_offset(input, 0, 3) {
+    for pos in iter_offset(0, 3) {
+        let pos = pos?;
         count += right(input, pos);
         count += down(input, pos);
         count += down_right(input, pos);
     }
 
     // Top few lines can't have up
-    for x_pos in iter_offset(input, 3, LINE_LEN * 3) {
+    for x_pos in iter_offset(3, line_len * 3) {
+        let x_pos = x_pos?;
         count += left(input, x_pos);
         count += right(input, x_pos);
         count += down_left(input, x_pos);
@@ -67,7 +174,8 @@ where
     }
 
     // First few on line 4 can't have left
-    for pos in iter_offset(input, LINE_LEN * 3, LINE_LEN * 3 + 3) {
+    for pos in iter_offset(line_len * 3, line_len * 3 + 3) {
+        let pos = pos?;
         count += up(input, pos);
         count += up_right(input, pos);
         count += right(input, pos);
@@ -75,8 +183,9 @@ where
         count += down_right(input, pos);
     }
 
-    let main_end = len - (LINE_LEN * 3 + 3);
-    for x_pos in iter_offset(input, LINE_LEN * 3 + 3, main_end) {
+    let main_end = len - (line_len * 3 + 3);
+    for x_pos in iter_offset(line_len * 3 + 3, main_end) {
+        let x_pos = x_pos?;
         count += up_left(input, x_pos);
         count += up(input, x_pos);
         count += up_right(input, x_pos);
@@ -88,7 +197,8 @@ where
     }
 
     // Last few on 4th last line can't have right
-    for pos in iter_offset(input, main_end, main_end + 3) {
+    for pos in iter_offset(main_end, main_end + 3) {
+        let pos = pos?;
         count += up_left(input, pos);
         count += up(input, pos);
         count += left(input, pos);
@@ -97,7 +207,8 @@ where
     }
 
     // Bottom few lines can't have down
-    for x_pos in iter_offset(input, len - LINE_LEN * 3, len - 3) {
+    for x_pos in iter_offset(len - line_len * 3, len - 3) {
+        let x_pos = x_pos?;
         count += up_left(input, x_pos);
         count += up(input, x_pos);
         count += up_right(input, x_pos);
@@ -106,70 +217,153 @@ where
     }
 
     // Last few can't have right, down
-    for pos in iter_offset(input, len - 3, len) {
+    for pos in iter_offset(len - 3, len) {
+        let pos = pos?;
         count += up_left(input, pos);
         count += up(input, pos);
         count += left(input, pos);
     }
 
-    count
+    Ok(count)
+}
+
+/// Reads the letter at `(x, y)`, or `None` if it's outside the grid
+#[inline]
+fn at(input: &[u8], line_len: usize, x: isize, y: isize) -> Option<u8> {
+    let width = line_len as isize - 1;
+    if (0..width).contains(&x) && y >= 0 {
+        input.get(y as usize * line_len + x as usize).copied()
+    } else {
+        None
+    }
+}
+
+/// Searches grids too small for [`part1_grid`], checking bounds on every step
+#[inline]
+unsafe fn part1_small<Md: Mode>(input: &[u8], line_len: usize) -> Result<u32, ParseError> {
+    let at = |x, y| at(input, line_len, x, y);
+
+    let mut count = 0;
+    for pos in iter_offset::<Md, X>(input, line_len, 0, input.len()) {
+        let pos = pos?;
+        let (x, y) = ((pos % line_len) as isize, (pos / line_len) as isize);
+        for (dx, dy) in [
+            (1, 0),
+            (-1, 0),
+            (0, 1),
+            (0, -1),
+            (1, 1),
+            (1, -1),
+            (-1, 1),
+            (-1, -1),
+        ] {
+            count += (1..4).all(|i| at(x + i * dx, y + i * dy) == Some(b"XMAS"[i as usize])) as u32;
+        }
+    }
+
+    Ok(count)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
 #[inline]
 unsafe fn right(input: &[u8], x_pos: usize) -> u32 {
     (input.as_ptr().add(x_pos).cast::<u32>().read_unaligned() == XMAS) as u32
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
 #[inline]
 unsafe fn left(input: &[u8], x_pos: usize) -> u32 {
     (input.as_ptr().add(x_pos - 3).cast::<u32>().read_unaligned() == SAMX) as u32
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
 #[inline]
-unsafe fn line<const DIFF: usize>(input: &[u8], x_pos: usize) -> u32 {
-    (*input.get_unchecked(x_pos + DIFF) == M
-        && *input.get_unchecked(x_pos + 2 * DIFF) == A
-        && *input.get_unchecked(x_pos + 3 * DIFF) == S) as u32
+unsafe fn line(input: &[u8], x_pos: usize, diff: usize) -> u32 {
+    (*input.get_unchecked(x_pos + diff) == M
+        && *input.get_unchecked(x_pos + 2 * diff) == A
+        && *input.get_unchecked(x_pos + 3 * diff) == S) as u32
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
 #[inline]
-unsafe fn line_neg<const DIFF: usize>(input: &[u8], x_pos: usize) -> u32 {
-    (*input.get_unchecked(x_pos - DIFF) == M
-        && *input.get_unchecked(x_pos - 2 * DIFF) == A
-        && *input.get_unchecked(x_pos - 3 * DIFF) == S) as u32
+unsafe fn line_neg(input: &[u8], x_pos: usize, diff: usize) -> u32 {
+    (*input.get_unchecked(x_pos - diff) == M
+        && *input.get_unchecked(x_pos - 2 * diff) == A
+        && *input.get_unchecked(x_pos - 3 * diff) == S) as u32
 }
 
 #[aoc(day4, part2)]
 pub fn part2(input: &str) -> u32 {
-    unsafe { part2_inner::<141>(input.as_bytes()) }
+    crate::dispatch(|| unsafe { part2_inner::<Unchecked>(input.as_bytes()).assume() })
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn part2_inner<const LINE_LEN: usize>(input: &[u8]) -> u32 {
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<u32, ParseError> {
+    crate::dispatch(|| unsafe { part2_inner::<Checked>(input.as_bytes()) })
+}
+
+#[inline(always)]
+unsafe fn part2_inner<Md: Mode>(input: &[u8]) -> Result<u32, ParseError> {
+    if input.is_empty() {
+        return Ok(0);
+    }
+    let line_len = line_len::<Md>(input)?;
+
+    if input.len() % line_len != 0 || input.len() < 3 * line_len {
+        return part2_small::<Md>(input, line_len);
+    }
+
+    match line_len {
+        141 => part2_grid::<Md, _>(input, Fixed::<141>),
+        11 => part2_grid::<Md, _>(input, Fixed::<11>),
+        _ => part2_grid::<Md, _>(input, line_len),
+    }
+}
+
+/// Searches grids too small for [`part2_grid`], checking bounds on every step
+#[inline]
+unsafe fn part2_small<Md: Mode>(input: &[u8], line_len: usize) -> Result<u32, ParseError> {
+    let at = |x, y| at(input, line_len, x, y).unwrap_or_default();
+
+    let mut count = 0;
+    for pos in iter_offset::<Md, A>(input, line_len, 0, input.len()) {
+        let pos = pos?;
+        let (x, y) = ((pos % line_len) as isize, (pos / line_len) as isize);
+        count += ((at(x - 1, y - 1) ^ at(x + 1, y + 1)) == 30
+            && (at(x + 1, y - 1) ^ at(x - 1, y + 1)) == 30) as u32;
+    }
+
+    Ok(count)
+}
+
+/// Searches a grid of at least 3 lines, each `line_len` long, with a trailing new line
+#[inline(always)]
+unsafe fn part2_grid<Md: Mode, L: LineLen>(input: &[u8], line_len: L) -> Result<u32, ParseError> {
+    let line_len = line_len.get();
+
     // Assume trailing new line
-    let len = const { LINE_LEN * (LINE_LEN - 1) - 1 };
+    let len = input.len() - 1;
+    if Md::CHECKED {
+        // The search below skips the letters on the edges, which can't be the middle of a cross
+        for pos in (0..line_len + 1).chain(len - line_len - 1..len + 1) {
+            letter::<Md>(input, line_len, pos)?;
+        }
+    }
     assume!(input[len] == b'\n', "Expected trailing new line");
 
     let mut count = 0;
 
-    for a_pos in iter_offset::<A>(input, LINE_LEN + 1, len - LINE_LEN - 1) {
-        let first_valid = (input.get_unchecked(a_pos - (LINE_LEN + 1))
-            ^ input.get_unchecked(a_pos + LINE_LEN + 1))
+    for a_pos in iter_offset::<Md, A>(input, line_len, line_len + 1, len - line_len - 1) {
+        let a_pos = a_pos?;
+        let first_valid = (input.get_unchecked(a_pos - (line_len + 1))
+            ^ input.get_unchecked(a_pos + line_len + 1))
             == 30;
 
         let both_valid = first_valid
-            && (input.get_unchecked(a_pos - (LINE_LEN - 1))
-                ^ input.get_unchecked(a_pos + LINE_LEN - 1))
+            && (input.get_unchecked(a_pos - (line_len - 1))
+                ^ input.get_unchecked(a_pos + line_len - 1))
                 == 30;
 
         count += both_valid as u32;
     }
 
-    count
+    Ok(count)
 }
 
 #[cfg(test)]
@@ -190,12 +384,12 @@ MXMXAXMASX
 
     #[test]
     fn p1_example() {
-        assert_eq!(unsafe { part1_inner::<11>(INPUT.as_bytes()) }, 18);
+        assert_eq!(part1(INPUT), 18);
     }
 
     #[test]
     fn p2_example() {
-        assert_eq!(unsafe { part2_inner::<11>(INPUT.as_bytes()) }, 9);
+        assert_eq!(part2(INPUT), 9);
     }
 
     #[test]
@@ -203,71 +397,35 @@ MXMXAXMASX
         // format!("........\n........\n........\n........\n........\n........\n........\n........"),
         for input in [
             // Right
-            format!(
-                "XMAS....\n........\n........\n........\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n........\n........\n........\n....XMAS\n"
-            ),
+            "XMAS....\n........\n........\n........\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n........\n........\n........\n....XMAS\n",
             // Left
-            format!(
-                "SAMX....\n........\n........\n........\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n........\n........\n........\n....SAMX\n"
-            ),
+            "SAMX....\n........\n........\n........\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n........\n........\n........\n....SAMX\n",
             // Down
-            format!(
-                "X.......\nM.......\nA.......\nS.......\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n.......X\n.......M\n.......A\n.......S\n"
-            ),
+            "X.......\nM.......\nA.......\nS.......\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n.......X\n.......M\n.......A\n.......S\n",
             // Up
-            format!(
-                "S.......\nA.......\nM.......\nX.......\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n.......S\n.......A\n.......M\n.......X\n"
-            ),
+            "S.......\nA.......\nM.......\nX.......\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n.......S\n.......A\n.......M\n.......X\n",
             // Down right
-            format!(
-                "X.......\n.M......\n..A.....\n...S....\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n....X...\n.....M..\n......A.\n.......S\n"
-            ),
+            "X.......\n.M......\n..A.....\n...S....\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n....X...\n.....M..\n......A.\n.......S\n",
             // Down left
-            format!(
-                "...X....\n..M.....\n.A......\nS.......\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n.......X\n......M.\n.....A..\n....S...\n"
-            ),
+            "...X....\n..M.....\n.A......\nS.......\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n.......X\n......M.\n.....A..\n....S...\n",
             // Up right
-            format!(
-                "...S....\n..A.....\n.M......\nX.......\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n.......S\n......A.\n.....M..\n....X...\n"
-            ),
+            "...S....\n..A.....\n.M......\nX.......\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n.......S\n......A.\n.....M..\n....X...\n",
             // Up left
-            format!(
-                "S.......\n.A......\n..M.....\n...X....\n........\n........\n........\n........\n"
-            ),
-            format!(
-                "........\n........\n........\n........\n....S...\n.....A..\n......M.\n.......X\n"
-            ),
+            "S.......\n.A......\n..M.....\n...X....\n........\n........\n........\n........\n",
+            "........\n........\n........\n........\n....S...\n.....A..\n......M.\n.......X\n",
         ] {
             assert!(
                 input.lines().map(str::len).all(|n| n == 8),
                 "Incorrect line length"
             );
-            assert_eq!(
-                unsafe { part1_inner::<9>(input.as_bytes()) },
-                1,
-                "\n{input}"
-            );
+            assert_eq!(part1(input), 1, "\n{input}");
         }
     }
 
@@ -283,13 +441,118 @@ MXMXAXMASX
         assert_eq!(part2(REAL_INPUT), 1_950);
     }
 
+    #[test]
+    fn real_scalar() {
+        crate::with_scalar(|| {
+            assert_eq!(part1(REAL_INPUT), 2_593);
+            assert_eq!(part2(REAL_INPUT), 1_950);
+        });
+    }
+
     #[test]
     fn p2_reduced_range() {
         let input = "M.S
 .A.
 M.S
-"
-        .as_bytes();
-        assert_eq!(unsafe { part2_inner::<4>(input) }, 1);
+";
+        assert_eq!(part2(input), 1);
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day4.txt");
+        assert_eq!(try_part1(input), Ok(part1(input)));
+        assert_eq!(try_part2(input), Ok(part2(input)));
+
+        let mut input = input.to_owned();
+        input.replace_range(5..6, ".");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 6, "one of \"XMAS\"", Some(b'.'))
+        );
+    }
+
+    #[test]
+    fn validate_real() {
+        assert_eq!(
+            validate(REAL_INPUT),
+            Ok(InputShape {
+                lines: 140,
+                width: Some(140),
+                records: 140
+            })
+        );
+
+        let input = REAL_INPUT.replacen('\n', "X\n", 1);
+        assert_eq!(
+            validate(&input),
+            Err(InputError::LineLength {
+                line: 2,
+                expected: 141,
+                found: 140
+            })
+        );
+
+        let input = REAL_INPUT.replacen('M', ".", 1);
+        assert!(matches!(
+            validate(&input),
+            Err(InputError::UnexpectedByte(err)) if err.expected == "one of \"XMAS\""
+        ));
+    }
+
+    /// Both parts with the paths for small grids
+    fn small(input: &str, line_len: usize) -> (Result<u32, ParseError>, Result<u32, ParseError>) {
+        let input = input.as_bytes();
+        unsafe {
+            (
+                part1_small::<Checked>(input, line_len),
+                part2_small::<Checked>(input, line_len),
+            )
+        }
+    }
+
+    #[test]
+    fn non_square() {
+        let tall = INPUT.lines().map(|line| &line[..7]).collect::<Vec<_>>();
+        let tall = tall.join("\n") + "\n";
+        let wide = &INPUT[..7 * 11];
+
+        for (input, line_len) in [(tall.as_str(), 8), (wide, 11)] {
+            assert_eq!(small(input, line_len), (Ok(part1(input)), Ok(part2(input))));
+            assert_eq!(try_part1(input), Ok(part1(input)));
+        }
+    }
+
+    #[test]
+    fn fast_paths_match_small() {
+        for (input, line_len) in [(INPUT, 11), (REAL_INPUT, 141)] {
+            assert_eq!(small(input, line_len), (Ok(part1(input)), Ok(part2(input))));
+        }
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(part1(""), 0);
+        assert_eq!(part2(""), 0);
+        assert_eq!(try_part1(""), Ok(0));
+        assert_eq!(try_part2(""), Ok(0));
+        assert_eq!(validate("").map(|shape| shape.lines), Ok(0));
+    }
+
+    #[test]
+    fn missing_trailing_newline() {
+        let input = INPUT.trim_end();
+        assert_eq!(part1(input), 18);
+        assert_eq!(part2(input), 9);
+        assert_eq!(try_part1(input), Ok(18));
+        assert_eq!(try_part2(input), Ok(9));
+        assert_eq!(try_part1("XMAS"), Ok(1));
+
+        let err = try_part1(&input[..input.len() - 1]).unwrap_err();
+        assert_eq!(
+            (err.line, err.expected, err.found),
+            (10, "a last line as long as the first", None)
+        );
     }
 }
@@ -1,170 +1,669 @@
 use aoc_runner_derive::aoc;
 
-use crate::{debug, BitIterU128 as BitIter};
+use crate::{
+    byte, debug, ensure, Assume as _, BitIterU128 as BitIter, Checked, InputError, InputShape,
+    Mode, ParseError, Unchecked, EOL,
+};
 
 const ZERO: u8 = b'0';
-const ZERO_ZERO: u16 = ZERO as u16 * 0x0101;
+/// Rules that only mention pages below this, as the puzzle's do, index a fixed table by page
+const SMALL_PAGES: u32 = 128;
+/// Marks an empty slot in [`RuleSet::ids`], or a page no rule mentions
+const NO_ID: u32 = u32::MAX;
+
+/// The page ordering rules, stored as a bit matrix over the pages they mention
+#[derive(Clone, Debug, Default)]
+pub struct RuleSet {
+    /// When every page mentioned is below [`SMALL_PAGES`], a bit set for each page number of the
+    /// pages that must come after it, with `ids` and `after` unused. Empty otherwise
+    small: Vec<u128>,
+    /// An open-addressed table of each page mentioned and its dense id, which is its row in
+    /// `after`. Empty slots have the id [`NO_ID`]
+    ids: Vec<(u32, u32)>,
+    /// The number of distinct pages mentioned by the rules
+    pages: usize,
+    /// The number of `u64`s in each row of `after`
+    words: usize,
+    /// For each page, a bit set of the pages that must come after it
+    after: Vec<u64>,
+    /// The rules as read, kept to reuse the allocation
+    rules: Vec<(u32, u32)>,
+}
 
-static mut RULES: [u128; 100] = [0; 100];
+impl RuleSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-#[inline]
-unsafe fn p(n: u16) -> u8 {
-    let [n1, n2] = (n - ZERO_ZERO).to_ne_bytes();
+    /// Reads the rules section of `input`, up to and including the blank line.
+    pub fn parse(input: &str) -> Result<Self, ParseError> {
+        let mut rules = Self::new();
+        unsafe { rules.read::<Checked>(input.as_bytes())? };
+        Ok(rules)
+    }
 
-    n1 * 10 + n2
-}
+    /// The number of distinct pages mentioned by the rules
+    pub fn pages(&self) -> usize {
+        self.pages
+    }
 
-const RULE_LINES: usize = 1_176;
+    /// Whether a rule says `before` must be printed before `after`
+    pub fn must_precede(&self, before: u32, after: u32) -> bool {
+        if !self.small.is_empty() {
+            return before < SMALL_PAGES
+                && after < SMALL_PAGES
+                && self.small[before as usize] & (1 << after) != 0;
+        } else if self.ids.is_empty() {
+            return false;
+        }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn parse_rules(input: &[u8]) {
-    for line in 0..RULE_LINES {
-        let n1 = input.as_ptr().add(line * 6).cast::<u16>().read_unaligned();
-        let n1 = p(n1);
+        match (self.id(before), self.id(after)) {
+            (NO_ID, _) | (_, NO_ID) => false,
+            (before, after) => {
+                let (before, after) = (before as usize, after as usize);
+                self.after[before * self.words + after / 64] & (1 << (after % 64)) != 0
+            }
+        }
+    }
 
-        let n2 = input
-            .as_ptr()
-            .add(line * 6 + 3)
-            .cast::<u16>()
-            .read_unaligned();
-        let n2 = p(n2);
+    /// The slot in `ids` to start looking for `page` from
+    #[inline(always)]
+    fn slot(&self, page: u32) -> usize {
+        // Fibonacci hashing, taking the top bits of the product as they depend on every bit of
+        // the page
+        (page.wrapping_mul(0x9E37_79B9) as usize * self.ids.len()) >> 32
+    }
 
-        *RULES.get_unchecked_mut(n1 as usize) |= 1 << n2;
+    /// The dense id of `page`, or [`NO_ID`] if no rule mentions it
+    #[inline(always)]
+    fn id(&self, page: u32) -> u32 {
+        let mask = self.ids.len() - 1;
+        let mut slot = self.slot(page);
+        loop {
+            let (key, id) = unsafe { *self.ids.get_unchecked(slot) };
+            if key == page || id == NO_ID {
+                return id;
+            }
+            slot = (slot + 1) & mask;
+        }
     }
-}
 
-#[aoc(day5, part1)]
-pub fn part1(input: &str) -> i32 {
-    unsafe { inner_p1(input) }
-}
+    #[inline(always)]
+    unsafe fn row(&self, id: u32) -> &[u64] {
+        let id = id as usize;
+        self.after
+            .get_unchecked(id * self.words..(id + 1) * self.words)
+    }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p1(input: &str) -> i32 {
-    let input = input.as_bytes();
-    parse_rules(input);
-    let mut offset = RULE_LINES * 6 + 1;
+    /// Replaces the rules with those at the start of `input`, returning the offset just after the
+    /// blank line that ends them
+    #[inline(always)]
+    unsafe fn read<M: Mode>(&mut self, input: &[u8]) -> Result<usize, ParseError> {
+        // The puzzle's rules only mention two digit pages, which go straight into `small` without
+        // looking for where each page ends
+        self.small.clear();
+        self.small.resize(SMALL_PAGES as usize, 0);
+        let small = self.small.as_mut_ptr();
+        let mut offset = 0;
+        while offset + 8 <= input.len() {
+            let line = input.as_ptr().add(offset).cast::<u64>().read_unaligned();
+            let [a, b, bar, c, d, eol, ..] = line.to_le_bytes();
+            if !(bar == b'|' && eol == EOL && are_digits::<M>(a, b) && are_digits::<M>(c, d)) {
+                break;
+            }
+            *small.add(two_digits(a, b) as usize) |= 1 << two_digits(c, d);
+            offset += 6;
+        }
 
-    let mut result = 0;
+        if input.get(offset) == Some(&EOL) {
+            self.count_small();
+            Ok(offset + 1)
+        } else {
+            self.read_any::<M>(input)
+        }
+    }
 
-    while input.len() > offset + 3 {
-        let mut seen = 0u128;
-        let line_start = offset;
-
-        let valid = loop {
-            debug!(
-                "Curr: {}",
-                std::str::from_utf8(&input[offset..offset + 3]).unwrap()
-            );
-            let (n, term) = input
-                .as_ptr()
-                .add(offset)
-                .cast::<(u16, u8)>()
-                .read_unaligned();
-            let num = p(n);
-            seen |= 1 << num;
-            offset += 3;
-
-            let valid = RULES.get_unchecked(num as usize) & seen == 0;
-            if term == b'\n' {
-                break valid;
+    /// [`RuleSet::read`] for rules with pages of any width
+    #[cold]
+    unsafe fn read_any<M: Mode>(&mut self, input: &[u8]) -> Result<usize, ParseError> {
+        self.rules.clear();
+        let mut offset = 0;
+        let mut largest = 0;
+        while byte::<M>(input, offset, "blank line")? != EOL {
+            let (before, _, next) = page::<M>(input, offset, b"|", "'|'")?;
+            let (after, _, next) = page::<M>(input, next, b"\n", "'\\n'")?;
+            self.rules.push((before, after));
+            largest = largest.max(before).max(after);
+            offset = next;
+        }
+
+        if largest < SMALL_PAGES {
+            self.index_small();
+        } else {
+            self.index_large();
+        }
+
+        Ok(offset + 1)
+    }
+
+    /// Builds `small` from the rules, whose pages are all below [`SMALL_PAGES`]
+    unsafe fn index_small(&mut self) {
+        self.small.clear();
+        self.small.resize(SMALL_PAGES as usize, 0);
+        for &(before, after) in &self.rules {
+            *self.small.get_unchecked_mut(before as usize) |= 1 << after;
+        }
+        self.count_small();
+    }
+
+    /// Sets `pages` from `small`, as the pages that must come before or after another
+    #[inline(always)]
+    fn count_small(&mut self) {
+        let mut mentioned = 0;
+        for (page, &after) in self.small.iter().enumerate() {
+            mentioned |= after | (((after != 0) as u128) << page);
+        }
+        self.pages = mentioned.count_ones() as usize;
+    }
+
+    /// Builds `ids` and `after` from the rules, giving each page a dense id in the order they are
+    /// first mentioned, so the table's size doesn't depend on how large the page numbers are
+    #[cold]
+    unsafe fn index_large(&mut self) {
+        self.small.clear();
+
+        // At most half full, so probes stay short
+        let slots = (self.rules.len() * 4).next_power_of_two();
+        self.ids.clear();
+        self.ids.resize(slots, (0, NO_ID));
+        self.pages = 0;
+        for i in 0..self.rules.len() {
+            let (before, after) = *self.rules.get_unchecked(i);
+            self.insert(before);
+            self.insert(after);
+        }
+
+        self.words = self.pages.div_ceil(64);
+        self.after.clear();
+        self.after.resize(self.pages * self.words, 0);
+        for &(before, after) in &self.rules {
+            let before = self.id(before) as usize;
+            let after = self.id(after) as usize;
+            *self
+                .after
+                .get_unchecked_mut(before * self.words + after / 64) |= 1 << (after % 64);
+        }
+    }
+
+    /// Gives `page` the next dense id, unless it already has one
+    #[inline(always)]
+    unsafe fn insert(&mut self, page: u32) {
+        let mask = self.ids.len() - 1;
+        let mut slot = self.slot(page);
+        loop {
+            let entry = self.ids.get_unchecked_mut(slot);
+            if entry.1 == NO_ID {
+                *entry = (page, self.pages as u32);
+                self.pages += 1;
+                return;
             }
+            if entry.0 == page {
+                return;
+            }
+            slot = (slot + 1) & mask;
+        }
+    }
 
-            if !valid {
-                debug!("Rule breakage fast-forward");
-                while input.as_ptr().add(offset + 2).read() != b'\n' {
-                    debug!(
-                        "Looking for new line: {}",
-                        std::str::from_utf8(&input[offset..offset + 3]).unwrap()
-                    );
+    /// Reads the update at `offset` if its pages are all two digits, as in the puzzle's input,
+    /// checking them against the rules in `small` as it goes. Returns whether they are in order,
+    /// the set of pages, and the offset of the next update. With `stop_unsorted`, unchecked reads
+    /// skip the rest of the update once it is out of order, leaving the set incomplete
+    #[inline(always)]
+    unsafe fn read_small<M: Mode>(
+        &self,
+        input: &[u8],
+        mut offset: usize,
+        stop_unsorted: bool,
+    ) -> Option<(bool, u128, usize)> {
+        let small = self.small.as_ptr();
+        let mut sorted = true;
+        let mut seen = 0u128;
+        loop {
+            match input.get(offset..offset + 3) {
+                Some(&[a, b, term @ (b',' | EOL)]) if are_digits::<M>(a, b) => {
+                    let page = two_digits(a, b);
+                    sorted &= *small.add(page as usize) & seen == 0;
+                    seen |= 1 << page;
                     offset += 3;
+                    if term == EOL {
+                        return Some((sorted, seen, offset));
+                    }
+                    if !M::CHECKED && stop_unsorted && !sorted {
+                        let rest = input.get_unchecked(offset..);
+                        let next =
+                            memchr::memchr(EOL, rest).map_or(input.len(), |eol| offset + eol + 1);
+                        return Some((false, seen, next));
+                    }
                 }
-                offset += 3;
+                _ => return None,
+            }
+        }
+    }
 
-                break false;
+    /// Whether `update` is in order, given the rules are in `small`, and the set of its pages
+    #[inline(always)]
+    unsafe fn mark_small(&self, update: &[u32]) -> (bool, u128) {
+        let mut sorted = true;
+        let mut seen = 0u128;
+        for &page in update {
+            // Larger pages aren't mentioned by any rule, so don't affect the order
+            if page < SMALL_PAGES {
+                sorted &= self.small.get_unchecked(page as usize) & seen == 0;
+                seen |= 1 << page;
             }
-        };
+        }
+
+        (sorted, seen)
+    }
+
+    /// The page in `seen` with `midpoint` of the others after it, given the rules are in `small`
+    #[inline(always)]
+    unsafe fn middle_small(&self, seen: u128, midpoint: u32) -> Option<u32> {
+        BitIter(seen)
+            .find(|&page| (self.small.get_unchecked(page) & seen).count_ones() == midpoint)
+            .map(|page| page as u32)
+    }
 
-        if valid {
-            let line_end = offset;
-            let n = input
-                .as_ptr()
-                .add((line_start + line_end) / 2 - 1)
-                .cast::<u16>()
-                .read_unaligned();
-            let midpoint = p(n) as i32;
-            debug!("Midpoint: {midpoint}");
-            result += midpoint;
+    /// Looks up the dense id of each page of `update` into `ids`, and marks them in `seen`,
+    /// returning whether they are in order
+    #[inline(always)]
+    unsafe fn mark(&self, update: &[u32], ids: &mut Vec<u32>, seen: &mut Vec<u64>) -> bool {
+        ids.clear();
+        seen.clear();
+        seen.resize(self.words, 0);
+
+        let mut sorted = true;
+        for &page in update {
+            let id = self.id(page);
+            ids.push(id);
+            if id != NO_ID {
+                sorted &= self
+                    .row(id)
+                    .iter()
+                    .zip(&*seen)
+                    .all(|(after, seen)| after & seen == 0);
+                *seen.get_unchecked_mut(id as usize / 64) |= 1 << (id % 64);
+            }
         }
+
+        sorted
     }
+}
 
-    result
+/// The page number written as the digits `a` and `b`
+#[inline(always)]
+fn two_digits(a: u8, b: u8) -> u32 {
+    (a.wrapping_sub(ZERO) as u32) * 10 + b.wrapping_sub(ZERO) as u32
 }
 
-#[aoc(day5, part2)]
-pub fn part2(input: &str) -> i32 {
-    unsafe { inner_p2(input) }
+/// Whether `a` and `b` can be read by [`two_digits`], which in unchecked mode they are assumed to be
+#[inline(always)]
+fn are_digits<M: Mode>(a: u8, b: u8) -> bool {
+    !M::CHECKED || (a.is_ascii_digit() && b.is_ascii_digit())
+}
+
+/// Reads the page number at `offset`, which must be followed by a byte in `terms` or the end of the
+/// input, returning it, that byte, and the offset after it
+#[inline(always)]
+unsafe fn page<M: Mode>(
+    input: &[u8],
+    mut offset: usize,
+    terms: &[u8],
+    expected: &'static str,
+) -> Result<(u32, u8, usize), ParseError> {
+    let start = offset;
+    let mut num = 0u32;
+    let term = loop {
+        // The end of the input ends the last line, as `aoc-runner` trims the trailing new line
+        let c = input.get(offset).copied().unwrap_or(EOL);
+        if !c.is_ascii_digit() {
+            break c;
+        }
+
+        num = if M::CHECKED {
+            match num
+                .checked_mul(10)
+                .and_then(|num| num.checked_add((c - ZERO) as u32))
+            {
+                Some(num) => num,
+                None => {
+                    return Err(ParseError::new(
+                        input,
+                        start,
+                        "a page number that fits in a u32",
+                    ))
+                }
+            }
+        } else {
+            num.unchecked_mul(10)
+                .unchecked_add(c.unchecked_sub(ZERO) as u32)
+        };
+        offset += 1;
+    };
+
+    ensure!(M, offset > start, input, offset, "digit");
+    ensure!(M, terms.contains(&term), input, offset, expected);
+    Ok((num, term, offset + 1))
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p2(input: &str) -> i32 {
+/// Reads the update at `offset` into `pages`, returning the offset of the next update
+#[inline(always)]
+unsafe fn update<M: Mode>(
+    input: &[u8],
+    mut offset: usize,
+    pages: &mut Vec<u32>,
+) -> Result<usize, ParseError> {
+    pages.clear();
+    loop {
+        let (num, term, next) = page::<M>(input, offset, b",\n", "',' or '\\n'")?;
+        pages.push(num);
+        offset = next;
+        if term == EOL {
+            debug!("Update: {pages:?}");
+            return Ok(offset);
+        }
+    }
+}
+
+/// Checks `input` is rules of the form `X|Y`, a blank line, then updates of comma separated page
+/// numbers, as [`part1`] and [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
     let input = input.as_bytes();
-    parse_rules(input);
+    let mut shape = InputShape::of(input)?;
+
+    let mut records = 0;
+    unsafe {
+        let mut offset = RuleSet::new().read::<Checked>(input)?;
+        let mut pages = Vec::new();
+        while offset < input.len() {
+            offset = update::<Checked>(input, offset, &mut pages)?;
+            records += 1;
+        }
+    }
+    shape.records = records;
 
-    let mut offset = RULE_LINES * 6 + 1;
-    let mut result = 0;
+    Ok(shape)
+}
 
-    while input.len() > offset + 3 {
-        let mut seen = 0u128;
-        let line_start = offset;
+/// Solves day 5, keeping the rules and scratch buffers between inputs
+#[derive(Clone, Debug, Default)]
+pub struct Solver {
+    rules: RuleSet,
+    pages: Vec<u32>,
+    ids: Vec<u32>,
+    seen: Vec<u64>,
+}
 
-        let mut unsorted = false;
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-        loop {
-            debug!(
-                "Curr: {}",
-                std::str::from_utf8(&input[offset..offset + 3]).unwrap()
-            );
-            let (n, term) = input
-                .as_ptr()
-                .add(offset)
-                .cast::<(u16, u8)>()
-                .read_unaligned();
-            let num = p(n);
-            seen |= 1 << num;
-            offset += 3;
-
-            if RULES.get_unchecked(num as usize) & seen != 0 {
-                unsorted = true;
+    pub fn part1(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { self.inner_p1::<Unchecked>(input).assume() })
+    }
+
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { self.inner_p1::<Checked>(input) })
+    }
+
+    pub fn part2(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { self.inner_p2::<Unchecked>(input).assume() })
+    }
+
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { self.inner_p2::<Checked>(input) })
+    }
+
+    /// The parsing phase of [`Solver::part1`] and [`Solver::part2`], reading the rules into the
+    /// solver's buffers.
+    pub fn parse_rules(&mut self, input: &str) {
+        crate::dispatch(|| unsafe { self.rules.read::<Unchecked>(input.as_bytes()).assume() });
+    }
+
+    #[inline(always)]
+    unsafe fn inner_p1<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
+        let Self {
+            rules,
+            pages,
+            ids,
+            seen,
+        } = self;
+        let input = input.as_bytes();
+        let mut offset = rules.read::<M>(input)?;
+        let small = !rules.small.is_empty();
+
+        let mut result = 0;
+        while offset < input.len() {
+            if small {
+                if let Some((sorted, _, next)) = rules.read_small::<M>(input, offset, true) {
+                    if sorted {
+                        // Each page takes three bytes, with its separator
+                        let middle = offset + (next - offset) / 6 * 3;
+                        let midpoint = two_digits(
+                            *input.get_unchecked(middle),
+                            *input.get_unchecked(middle + 1),
+                        );
+                        debug!("Midpoint: {midpoint}");
+                        result += midpoint as i32;
+                    }
+                    offset = next;
+                    continue;
+                }
             }
 
-            if term == b'\n' {
-                break;
+            offset = update::<M>(input, offset, pages)?;
+            let sorted = if small {
+                rules.mark_small(pages).0
+            } else {
+                rules.mark(pages, ids, seen)
+            };
+            if sorted {
+                let midpoint = *pages.get_unchecked(pages.len() / 2) as i32;
+                debug!("Midpoint: {midpoint}");
+                result += midpoint;
             }
         }
 
-        if unsorted {
-            let line_end = offset;
-            let num_count = (line_end - line_start) / 3;
-            let midpoint = num_count / 2;
+        Ok(result)
+    }
 
-            for n in BitIter(seen) {
-                if (RULES.get_unchecked(n) & seen).count_ones() == midpoint as u32 {
-                    debug!("Midpoint: {midpoint}");
-                    result += n as i32;
-                    break;
+    #[inline(always)]
+    unsafe fn inner_p2<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
+        let Self {
+            rules,
+            pages,
+            ids,
+            seen,
+        } = self;
+        let input = input.as_bytes();
+        let mut offset = rules.read::<M>(input)?;
+        let small = !rules.small.is_empty();
+
+        // Once sorted, the middle page has exactly half of the other pages after it
+        let mut result = 0;
+        while offset < input.len() {
+            let page = if small {
+                let (sorted, seen, midpoint) = match rules.read_small::<M>(input, offset, false) {
+                    Some((sorted, seen, next)) => {
+                        let midpoint = (next - offset) / 6;
+                        offset = next;
+                        (sorted, seen, midpoint)
+                    }
+                    None => {
+                        offset = update::<M>(input, offset, pages)?;
+                        let (sorted, seen) = rules.mark_small(pages);
+                        (sorted, seen, pages.len() / 2)
+                    }
+                };
+                if sorted {
+                    continue;
                 }
+                rules.middle_small(seen, midpoint as u32)
+            } else {
+                offset = update::<M>(input, offset, pages)?;
+                if rules.mark(pages, ids, seen) {
+                    continue;
+                }
+                let midpoint = pages.len() as u32 / 2;
+                pages.iter().zip(&*ids).find_map(|(&page, &id)| {
+                    let after = (id != NO_ID).then(|| {
+                        rules
+                            .row(id)
+                            .iter()
+                            .zip(&*seen)
+                            .map(|(after, seen)| (after & seen).count_ones())
+                            .sum::<u32>()
+                    });
+                    (after == Some(midpoint)).then_some(page)
+                })
+            };
+
+            if let Some(page) = page {
+                debug!("Midpoint: {page}");
+                result += page as i32;
             }
         }
+
+        Ok(result)
     }
+}
 
-    result
+#[aoc(day5, part1)]
+pub fn part1(input: &str) -> i32 {
+    Solver::new().part1(input)
+}
+
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part1(input)
+}
+
+#[aoc(day5, part2)]
+pub fn part2(input: &str) -> i32 {
+    Solver::new().part2(input)
+}
+
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part2(input)
 }
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
+
     use super::*;
 
+    const INPUT: &str = indoc! {"
+        47|53
+        97|13
+        97|61
+        97|47
+        75|29
+        61|13
+        75|53
+        29|13
+        97|29
+        53|29
+        61|53
+        97|53
+        61|29
+        47|13
+        75|47
+        97|75
+        47|61
+        75|61
+        47|29
+        75|13
+        53|13
+
+        75,47,61,53,29
+        97,61,53,29,13
+        75,29,13
+        75,97,47,61,53
+        61,13,29
+        97,13,75,29,47
+    "};
+
+    #[test]
+    fn p1_example() {
+        assert_eq!(part1(INPUT), 143);
+    }
+
+    #[test]
+    fn p2_example() {
+        assert_eq!(part2(INPUT), 123);
+    }
+
+    #[test]
+    fn any_numbering() {
+        let renumbered = INPUT
+            .replace("13", "1300")
+            .replace("29", "7")
+            .replace("47", "100047")
+            .replace("97", "197");
+        assert_eq!(part1(&renumbered), 61 + 53 + 7);
+        assert_eq!(part2(&renumbered), 100047 + 7 + 100047);
+
+        // Rules from one input don't leak into the next
+        let more_rules = "61|75\n".to_owned() + INPUT;
+        let mut solver = Solver::new();
+        assert_eq!(solver.part1(&more_rules), 143 - 61);
+        assert_eq!(solver.part1(INPUT), 143);
+        assert_eq!(solver.try_part2(&renumbered), Ok(100047 + 7 + 100047));
+        assert_eq!(solver.part2(INPUT), 123);
+
+        assert_eq!(part1("\n1,2,3\n"), 2);
+        assert_eq!(part1(INPUT.trim_end()), 143);
+        assert_eq!(part2(INPUT.trim_end()), 123);
+        assert_eq!(part2("\n1,2,3\n"), 0);
+
+        // The rules' table is sized by how many pages they mention, not the largest page
+        let huge = "4000000000|1\n\n4000000000,7,1\n1,7,4000000000\n";
+        assert_eq!(part1(huge), 7);
+        assert_eq!(try_part1(huge), Ok(7));
+
+        // Pages that aren't two digits can share an input with ones that are
+        let mixed = "9|47\n47|53\n9|53\n\n9,47,53\n53,9,47\n9,100,47,53,8\n";
+        assert_eq!(part1(mixed), 47 + 47);
+        assert_eq!(try_part2(mixed), Ok(47));
+        let mixed = "47|53\n\n53,100,47\n";
+        assert_eq!(try_part1(mixed), Ok(0));
+        assert_eq!(part2(mixed), 47);
+    }
+
+    #[test]
+    fn rule_set() {
+        let rules = RuleSet::parse(INPUT).unwrap();
+        assert_eq!(rules.pages(), 7);
+        assert!(rules.must_precede(47, 53));
+        assert!(!rules.must_precede(53, 47));
+        assert!(!rules.must_precede(47, 1_000));
+
+        assert!(!RuleSet::new().must_precede(1, 2));
+        let rules = RuleSet::parse("1000|5\n5|7\n\n").unwrap();
+        assert_eq!(rules.pages(), 3);
+        assert!(rules.must_precede(1_000, 5));
+        assert!(!rules.must_precede(5, 1_000));
+        assert!(!rules.must_precede(1_000, 7));
+
+        let err = RuleSet::parse("1|2\n").unwrap_err();
+        assert_eq!((err.line, err.expected, err.found), (2, "blank line", None));
+    }
+
     #[test]
     fn real_p1() {
         let input = include_str!("../input/2024/day5.txt");
@@ -176,4 +675,37 @@ mod tests {
         let input = include_str!("../input/2024/day5.txt");
         assert_eq!(part2(input), 6_142);
     }
+
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day5.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 5_391);
+            assert_eq!(part2(input), 6_142);
+        });
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day5.txt");
+        assert_eq!(try_part1(input), Ok(5_391));
+        assert_eq!(try_part2(input), Ok(6_142));
+
+        let err = try_part2("1|2\n\n1,,2\n").unwrap_err();
+        assert_eq!((err.line, err.column, err.expected), (3, 3, "digit"));
+
+        let mut input = input.to_owned();
+        input.replace_range(2..3, "-");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 3, "'|'", Some(b'-'))
+        );
+    }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day5.txt");
+        assert_eq!(validate(input).map(|shape| shape.records), Ok(205));
+    }
 }
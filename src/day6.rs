@@ -1,323 +1,533 @@
 use aoc_runner_derive::aoc;
-use memchr::arch::x86_64::avx2::memchr;
 
-use crate::{assume, debug, Assume};
+use crate::{
+    assume, debug, ensure, Assume, Checked, InputError, InputShape, Mode, ParseError, Unchecked,
+};
 
 const GUARD: u8 = b'^';
 const BLOCK: u8 = b'#';
 const EOL: u8 = b'\n';
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn find_guard(input: &[u8]) -> usize {
-    memchr::One::new_unchecked(GUARD).find(input).assume()
+const LEFT_VISIT: u8 = 1 << 0;
+const RIGHT_VISIT: u8 = 1 << 1;
+const UP_VISIT: u8 = 1 << 2;
+const DOWN_VISIT: u8 = 1 << 3;
+
+/// What a guard walking through the same cell in the same direction twice, and so never leaving
+/// the map, is reported as
+const LOOPING: &str = "a patrol that leaves the map";
+/// The most walks [`visit`] tells apart, as it keeps the directions in the low 4 bits
+const MAX_EPOCH: u32 = u32::MAX >> 4;
+
+/// Reads the length of each line, including the new line, from the first new line, and finds
+/// the guard. The end of the input ends the last line, as `aoc-runner` trims the trailing new
+/// line. In checked mode, finds the guard while checking the input is a rectangular map of `.#^`
+/// with a single guard
+#[inline(always)]
+unsafe fn read_map<M: Mode>(input: &[u8]) -> Result<(usize, usize), ParseError> {
+    let line_len = memchr::memchr(EOL, input).map_or(input.len() + 1, |width| width + 1);
+
+    if !M::CHECKED {
+        return Ok((line_len, memchr::memchr(GUARD, input).assume()));
+    }
+
+    let mut guard = None;
+    for (pos, &c) in input.iter().enumerate() {
+        if pos % line_len == line_len - 1 {
+            ensure!(M, c == EOL, input, pos, "'\\n'");
+        } else if c == GUARD {
+            ensure!(M, guard.is_none(), input, pos, "a single guard");
+            guard = Some(pos);
+        } else {
+            ensure!(M, matches!(c, b'.' | BLOCK), input, pos, "one of \".#^\"");
+        }
+    }
+
+    ensure!(
+        M,
+        input.len() % line_len == 0 || input.len() % line_len == line_len - 1,
+        input,
+        input.len(),
+        "a last line as long as the first"
+    );
+    match guard {
+        Some(guard) => Ok((line_len, guard)),
+        None => Err(ParseError::new(input, input.len(), "a guard")),
+    }
 }
 
-#[aoc(day6, part1)]
-pub fn part1(input: &str) -> i32 {
-    unsafe { inner_p1::<130>(input) }
+/// Checks `input` is a rectangular map of `.#^` with a single guard, as [`part1`] and [`part2`]
+/// assume. Whether the guard's patrol leaves the map is left to [`try_part1`].
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let bytes = input.as_bytes();
+    let shape = InputShape::of(bytes)?;
+    let width = memchr::memchr(EOL, bytes).unwrap_or_default();
+    let shape = shape.expect_width(bytes, shape.lines, width)?;
+    unsafe { read_map::<Checked>(bytes)? };
+
+    Ok(shape)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p1<const DIM: usize>(input: &str) -> i32
-where
-    [(); DIM * (DIM + 1)]:,
-{
-    let len = DIM * (DIM + 1);
-    let mut visited = [false; DIM * (DIM + 1)];
-    let input = input.as_bytes();
-    let mut pos = find_guard(input);
-    let mut total = 0;
-
-    macro_rules! debug_map {
-        ($input:ident, $visited:ident) => {
-            debug!("Current map:\n{}", {
-                let mut s = String::with_capacity($input.len());
-                for (i, c) in $input.iter().enumerate() {
-                    s.push(match c {
-                        b'.' if $visited[i] => b'O',
-                        c => *c,
-                    } as char);
-                }
-                s
-            });
-        };
-    }
+/// Draws the map with the cells the guard walked through: `-` when moving left or right, `|`
+/// when moving up or down, and `+` for both. `obstruction` is drawn as `O`
+fn render(input: &[u8], visits: &[u8], obstruction: Option<usize>) -> String {
+    input
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| match c {
+            _ if Some(i) == obstruction => 'O',
+            b'.' => match visits[i] {
+                0 => '.',
+                LEFT_VISIT | RIGHT_VISIT | const { LEFT_VISIT | RIGHT_VISIT } => '-',
+                UP_VISIT | DOWN_VISIT | const { UP_VISIT | DOWN_VISIT } => '|',
+                _ => '+',
+            },
+            c => c as char,
+        })
+        .collect()
+}
+
+/// Walks the guard's patrol, checking bounds on every step, and records the directions it moves
+/// through each cell in `visits`. Returns whether the guard ends up in a loop
+fn patrol(
+    input: &[u8],
+    line_len: usize,
+    guard: usize,
+    obstruction: Option<usize>,
+    visits: &mut [u8],
+) -> bool {
+    let width = line_len as isize - 1;
+    let height = input.len().div_ceil(line_len) as isize;
+    let (mut x, mut y) = ((guard % line_len) as isize, (guard / line_len) as isize);
+    let mut dir = 0;
 
     loop {
-        // Up
-        loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if !*visit {
-                total += 1;
-                *visit = true;
-            }
+        let (dx, dy, bit) = [
+            (0, -1, UP_VISIT),
+            (1, 0, RIGHT_VISIT),
+            (0, 1, DOWN_VISIT),
+            (-1, 0, LEFT_VISIT),
+        ][dir];
+        let visit = &mut visits[y as usize * line_len + x as usize];
+        if *visit & bit != 0 {
+            return true;
+        }
+        *visit |= bit;
 
-            if pos < DIM + 1 {
-                debug_map!(input, visited);
-                return total;
-            }
+        let (next_x, next_y) = (x + dx, y + dy);
+        if !(0..width).contains(&next_x) || !(0..height).contains(&next_y) {
+            return false;
+        }
 
-            let new_pos = pos - DIM - 1;
-            if *input.get_unchecked(new_pos) == BLOCK {
-                break;
-            }
+        let next = next_y as usize * line_len + next_x as usize;
+        if input[next] == BLOCK || Some(next) == obstruction {
+            dir = (dir + 1) % 4;
+        } else {
+            (x, y) = (next_x, next_y);
+        }
+    }
+}
 
-            pos = new_pos;
+/// Draws the guard's patrol over the map, as in the puzzle's examples, with an optional extra
+/// obstruction at `(row, col)`, which must be inside the map and not on the guard's start.
+pub fn render_patrol(
+    input: &str,
+    obstruction: Option<(usize, usize)>,
+) -> Result<String, InputError> {
+    let input = input.as_bytes();
+    let (line_len, guard) = unsafe { read_map::<Checked>(input)? };
+    let height = input.len().div_ceil(line_len);
+    let obstruction = match obstruction {
+        Some((row, _)) if row >= height => {
+            return Err(ParseError::new(input, input.len(), "an obstruction inside the map").into())
         }
+        Some((row, col)) if col >= line_len - 1 => {
+            let eol = row * line_len + line_len - 1;
+            return Err(ParseError::new(input, eol, "an obstruction inside the map").into());
+        }
+        Some((row, col)) if row * line_len + col == guard => {
+            return Err(
+                ParseError::new(input, guard, "an obstruction off the guard's start").into(),
+            )
+        }
+        obstruction => obstruction.map(|(row, col)| row * line_len + col),
+    };
 
-        // Right
-        loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if !*visit {
-                total += 1;
-                *visit = true;
-            }
+    let mut visits = vec![0; input.len()];
+    patrol(input, line_len, guard, obstruction, &mut visits);
+    Ok(render(input, &visits, obstruction))
+}
 
-            let new_pos = pos + 1;
-            if new_pos > len {
-                debug_map!(input, visited);
-                return total;
-            }
+#[aoc(day6, part1)]
+pub fn part1(input: &str) -> i32 {
+    Solver::new().part1(input)
+}
 
-            match *input.get_unchecked(new_pos) {
-                BLOCK => break,
-                EOL => {
-                    debug_map!(input, visited);
-                    return total;
-                }
-                _ => (),
-            }
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part1(input)
+}
 
-            pos = new_pos
-        }
+#[aoc(day6, part2)]
+pub fn part2(input: &str) -> i32 {
+    Solver::new().part2(input)
+}
 
-        // Down
-        loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if !*visit {
-                total += 1;
-                *visit = true;
-            }
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part2(input)
+}
 
-            let new_pos = pos + DIM + 1;
-            if new_pos > len {
-                debug_map!(input, visited);
-                return total;
-            }
+/// The `(row, col)` of every position where an obstruction would trap the guard in a loop, in
+/// order. [`part2`] is the number of them
+pub fn loop_obstructions(input: &str) -> Vec<(usize, usize)> {
+    let mut positions = Vec::new();
+    let mut solver = Solver::new();
+    crate::dispatch(|| unsafe {
+        solver
+            .inner_p2::<Unchecked>(input, |pos| positions.push(pos))
+            .assume()
+    });
+    to_coords(input, positions)
+}
 
-            if *input.get_unchecked(new_pos) == BLOCK {
-                break;
-            }
+/// [`loop_obstructions`], reporting malformed input rather than assuming it is well formed.
+pub fn try_loop_obstructions(input: &str) -> Result<Vec<(usize, usize)>, ParseError> {
+    let mut positions = Vec::new();
+    let mut solver = Solver::new();
+    crate::dispatch(|| unsafe { solver.inner_p2::<Checked>(input, |pos| positions.push(pos)) })?;
+    Ok(to_coords(input, positions))
+}
 
-            pos = new_pos;
-        }
+fn to_coords(input: &str, mut positions: Vec<usize>) -> Vec<(usize, usize)> {
+    let line_len = memchr::memchr(EOL, input.as_bytes()).map_or(input.len() + 1, |width| width + 1);
+    positions.sort_unstable();
+    positions
+        .into_iter()
+        .map(|pos| (pos / line_len, pos % line_len))
+        .collect()
+}
 
-        // Left
-        loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if !*visit {
-                total += 1;
-                *visit = true;
-            }
+/// Solves day 6, keeping the guard's visits, and those of the patrols part 2 tries, in buffers it
+/// reuses between inputs
+#[derive(Clone, Debug, Default)]
+pub struct Solver {
+    visited: Vec<u8>,
+    /// Reused by every call to [`loops`], which tells its visits apart by `epoch`
+    seen: Vec<u32>,
+    epoch: u32,
+}
 
-            if pos == 0 {
-                debug_map!(input, visited);
-                return total;
-            }
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-            let new_pos = pos - 1;
-            match *input.get_unchecked(new_pos) {
-                BLOCK => break,
-                EOL => {
-                    debug_map!(input, visited);
-                    return total;
-                }
-                _ => (),
-            }
+    pub fn part1(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { self.inner_p1::<Unchecked>(input).assume() })
+    }
 
-            pos = new_pos
-        }
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { self.inner_p1::<Checked>(input) })
     }
-}
 
-#[aoc(day6, part2)]
-pub fn part2(input: &str) -> i32 {
-    unsafe { inner_p2::<130>(input) }
-}
+    pub fn part2(&mut self, input: &str) -> i32 {
+        let mut count = 0;
+        crate::dispatch(|| unsafe { self.inner_p2::<Unchecked>(input, |_| count += 1).assume() });
+        count
+    }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p2<const DIM: usize>(input: &str) -> i32
-where
-    [(); DIM * (DIM + 1)]:,
-{
-    let input = input.as_bytes();
-    let len = DIM * (DIM + 1);
-    let mut visited = [0u8; DIM * (DIM + 1)];
-    let mut pos = find_guard(input);
-    let mut count = 0;
-
-    macro_rules! debug_map {
-        ($input:ident, $visited:ident, $obstruction:ident) => {
-            debug!("Current map:\n{}", {
-                let mut s = String::with_capacity($input.len());
-                for (i, c) in $input.iter().enumerate() {
-                    s.push(match c {
-                        _ if i == $obstruction => b'O',
-                        b'.' => match $visited[i] {
-                            0 => b'.',
-                            LEFT_VISIT | RIGHT_VISIT | const { LEFT_VISIT | RIGHT_VISIT } => b'-',
-                            UP_VISIT | DOWN_VISIT | const { UP_VISIT | DOWN_VISIT } => b'|',
-                            _ => b'+',
-                        },
-                        c => *c,
-                    } as char);
-                }
-                s
-            });
-        };
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<i32, ParseError> {
+        let mut count = 0;
+        crate::dispatch(|| unsafe { self.inner_p2::<Checked>(input, |_| count += 1) })?;
+        Ok(count)
     }
 
-    loop {
-        // Up
+    #[inline(always)]
+    unsafe fn inner_p1<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
+        let input = input.as_bytes();
+        let (line_len, mut pos) = read_map::<M>(input)?;
+        let len = input.len();
+        let visited = &mut self.visited;
+        visited.clear();
+        visited.resize(len, 0);
+        let mut total = 0;
+
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            *visit |= UP_VISIT;
+            // Up
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & UP_VISIT == 0, input, pos, LOOPING);
+                total += (*visit == 0) as i32;
+                *visit |= UP_VISIT;
+
+                if pos < line_len {
+                    debug!("Final map:\n{}", render(input, visited, None));
+                    return Ok(total);
+                }
 
-            if pos < DIM + 1 {
-                return count;
-            }
+                let new_pos = pos - line_len;
+                if *input.get_unchecked(new_pos) == BLOCK {
+                    break;
+                }
 
-            let new_pos = pos - DIM - 1;
-            if *input.get_unchecked(new_pos) == BLOCK {
-                break;
+                pos = new_pos;
             }
 
-            if *visited.get_unchecked(new_pos) == 0 {
-                let loops = loops::<DIM>(input, pos, UP_VISIT, new_pos);
-                if loops {
-                    debug!("Looping at {}, {}", new_pos / (DIM + 1), new_pos % DIM);
-                    debug_map!(input, visited, new_pos);
+            // Right
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & RIGHT_VISIT == 0, input, pos, LOOPING);
+                total += (*visit == 0) as i32;
+                *visit |= RIGHT_VISIT;
+
+                let new_pos = pos + 1;
+                if new_pos >= len {
+                    debug!("Final map:\n{}", render(input, visited, None));
+                    return Ok(total);
                 }
-                count += loops as i32;
-            }
-            pos = new_pos;
-        }
 
-        // Right
-        loop {
-            let visit = visited.get_unchecked_mut(pos);
-            *visit |= RIGHT_VISIT;
+                match *input.get_unchecked(new_pos) {
+                    BLOCK => break,
+                    EOL => {
+                        debug!("Final map:\n{}", render(input, visited, None));
+                        return Ok(total);
+                    }
+                    _ => (),
+                }
 
-            let new_pos = pos + 1;
-            if new_pos > len {
-                return count;
+                pos = new_pos
             }
 
-            match *input.get_unchecked(new_pos) {
-                BLOCK => break,
-                EOL => {
-                    return count;
+            // Down
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & DOWN_VISIT == 0, input, pos, LOOPING);
+                total += (*visit == 0) as i32;
+                *visit |= DOWN_VISIT;
+
+                let new_pos = pos + line_len;
+                if new_pos >= len {
+                    debug!("Final map:\n{}", render(input, visited, None));
+                    return Ok(total);
                 }
-                _ => (),
+
+                if *input.get_unchecked(new_pos) == BLOCK {
+                    break;
+                }
+
+                pos = new_pos;
             }
 
-            if *visited.get_unchecked(new_pos) == 0 {
-                let loops = loops::<DIM>(input, pos, RIGHT_VISIT, new_pos);
-                if loops {
-                    debug!("Looping at {}, {}", new_pos / (DIM + 1), new_pos % DIM);
-                    debug_map!(input, visited, new_pos);
+            // Left
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & LEFT_VISIT == 0, input, pos, LOOPING);
+                total += (*visit == 0) as i32;
+                *visit |= LEFT_VISIT;
+
+                if pos == 0 {
+                    debug!("Final map:\n{}", render(input, visited, None));
+                    return Ok(total);
                 }
-                count += loops as i32;
+
+                let new_pos = pos - 1;
+                match *input.get_unchecked(new_pos) {
+                    BLOCK => break,
+                    EOL => {
+                        debug!("Final map:\n{}", render(input, visited, None));
+                        return Ok(total);
+                    }
+                    _ => (),
+                }
+
+                pos = new_pos
             }
-            pos = new_pos;
         }
+    }
+
+    /// Walks the guard's patrol, calling `on_loop` with each position where an obstruction would
+    /// cause a loop. In checked mode, reports a patrol that never leaves the map rather than walking
+    /// it forever
+    #[inline(always)]
+    unsafe fn inner_p2<M: Mode>(
+        &mut self,
+        input: &str,
+        mut on_loop: impl FnMut(usize),
+    ) -> Result<(), ParseError> {
+        let input = input.as_bytes();
+        let (line_len, mut pos) = read_map::<M>(input)?;
+        let len = input.len();
+        let Self {
+            visited,
+            seen,
+            epoch,
+        } = self;
+        visited.clear();
+        visited.resize(len, 0);
+        // `seen` keeps earlier patrols' visits, which have older epochs, until the epochs run out
+        if ((MAX_EPOCH - *epoch) as usize) < len {
+            seen.clear();
+            *epoch = 0;
+        }
+        seen.resize(len.max(seen.len()), 0);
 
-        // Down
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            *visit |= DOWN_VISIT;
+            // Up
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & UP_VISIT == 0, input, pos, LOOPING);
+                *visit |= UP_VISIT;
+
+                if pos < line_len {
+                    return Ok(());
+                }
 
-            let new_pos = pos + DIM + 1;
-            if new_pos > len {
-                return count;
-            }
+                let new_pos = pos - line_len;
+                if *input.get_unchecked(new_pos) == BLOCK {
+                    break;
+                }
 
-            if *input.get_unchecked(new_pos) == BLOCK {
-                break;
+                if *visited.get_unchecked(new_pos) == 0 {
+                    *epoch += 1;
+                    if loops(input, line_len, seen, *epoch, pos, UP_VISIT, new_pos) {
+                        debug!("Looping at {}, {}", new_pos / line_len, new_pos % line_len);
+                        debug!("Map:\n{}", render(input, visited, Some(new_pos)));
+                        on_loop(new_pos);
+                    }
+                }
+                pos = new_pos;
             }
 
-            if *visited.get_unchecked(new_pos) == 0 {
-                let loops = loops::<DIM>(input, pos, DOWN_VISIT, new_pos);
-                if loops {
-                    debug!("Looping at {}, {}", new_pos / (DIM + 1), new_pos % DIM);
-                    debug_map!(input, visited, new_pos);
+            // Right
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & RIGHT_VISIT == 0, input, pos, LOOPING);
+                *visit |= RIGHT_VISIT;
+
+                let new_pos = pos + 1;
+                if new_pos >= len {
+                    return Ok(());
                 }
-                count += loops as i32;
-            }
-            pos = new_pos;
-        }
 
-        // Left
-        loop {
-            let visit = visited.get_unchecked_mut(pos);
-            *visit |= LEFT_VISIT;
+                match *input.get_unchecked(new_pos) {
+                    BLOCK => break,
+                    EOL => {
+                        return Ok(());
+                    }
+                    _ => (),
+                }
 
-            if pos == 0 {
-                return count;
+                if *visited.get_unchecked(new_pos) == 0 {
+                    *epoch += 1;
+                    if loops(input, line_len, seen, *epoch, pos, RIGHT_VISIT, new_pos) {
+                        debug!("Looping at {}, {}", new_pos / line_len, new_pos % line_len);
+                        debug!("Map:\n{}", render(input, visited, Some(new_pos)));
+                        on_loop(new_pos);
+                    }
+                }
+                pos = new_pos;
             }
 
-            let new_pos = pos - 1;
-            match *input.get_unchecked(new_pos) {
-                BLOCK => break,
-                EOL => {
-                    return count;
+            // Down
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & DOWN_VISIT == 0, input, pos, LOOPING);
+                *visit |= DOWN_VISIT;
+
+                let new_pos = pos + line_len;
+                if new_pos >= len {
+                    return Ok(());
                 }
-                _ => (),
+
+                if *input.get_unchecked(new_pos) == BLOCK {
+                    break;
+                }
+
+                if *visited.get_unchecked(new_pos) == 0 {
+                    *epoch += 1;
+                    if loops(input, line_len, seen, *epoch, pos, DOWN_VISIT, new_pos) {
+                        debug!("Looping at {}, {}", new_pos / line_len, new_pos % line_len);
+                        debug!("Map:\n{}", render(input, visited, Some(new_pos)));
+                        on_loop(new_pos);
+                    }
+                }
+                pos = new_pos;
             }
 
-            if *visited.get_unchecked(new_pos) == 0 {
-                let loops = loops::<DIM>(input, pos, LEFT_VISIT, new_pos);
-                if loops {
-                    debug!("Looping at {}, {}", new_pos / (DIM + 1), new_pos % DIM);
-                    debug_map!(input, visited, new_pos);
+            // Left
+            loop {
+                let visit = visited.get_unchecked_mut(pos);
+                ensure!(M, *visit & LEFT_VISIT == 0, input, pos, LOOPING);
+                *visit |= LEFT_VISIT;
+
+                if pos == 0 {
+                    return Ok(());
                 }
-                count += loops as i32;
+
+                let new_pos = pos - 1;
+                match *input.get_unchecked(new_pos) {
+                    BLOCK => break,
+                    EOL => {
+                        return Ok(());
+                    }
+                    _ => (),
+                }
+
+                if *visited.get_unchecked(new_pos) == 0 {
+                    *epoch += 1;
+                    if loops(input, line_len, seen, *epoch, pos, LEFT_VISIT, new_pos) {
+                        debug!("Looping at {}, {}", new_pos / line_len, new_pos % line_len);
+                        debug!("Map:\n{}", render(input, visited, Some(new_pos)));
+                        on_loop(new_pos);
+                    }
+                }
+                pos = new_pos;
             }
-            pos = new_pos;
         }
     }
 }
 
-const LEFT_VISIT: u8 = 1 << 0;
-const RIGHT_VISIT: u8 = 1 << 1;
-const UP_VISIT: u8 = 1 << 2;
-const DOWN_VISIT: u8 = 1 << 3;
+/// Marks `pos` as visited moving in `dir` during the walk numbered `epoch`, returning whether it
+/// already was
+#[inline(always)]
+unsafe fn visit(seen: &mut [u32], epoch: u32, pos: usize, dir: u8) -> bool {
+    let cell = seen.get_unchecked_mut(pos);
+    if *cell >> 4 != epoch {
+        *cell = epoch << 4 | dir as u32;
+        false
+    } else if *cell & dir as u32 != 0 {
+        true
+    } else {
+        *cell |= dir as u32;
+        false
+    }
+}
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn loops<const DIM: usize>(
+#[inline]
+unsafe fn loops(
     input: &[u8],
+    line_len: usize,
+    seen: &mut [u32],
+    epoch: u32,
     mut pos: usize,
     start_dir: u8,
     obstruction: usize,
-) -> bool
-where
-    [(); DIM * (DIM + 1)]:,
-{
-    let len = DIM * (DIM + 1);
-    let mut visited = [0u8; DIM * (DIM + 1)];
+) -> bool {
+    let len = input.len();
     assume!(pos != obstruction);
 
     if start_dir == RIGHT_VISIT {
         // Right
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if *visit & RIGHT_VISIT != 0 {
+            if visit(seen, epoch, pos, RIGHT_VISIT) {
                 return true;
             }
-            *visit |= RIGHT_VISIT;
 
             let new_pos = pos + 1;
-            if new_pos > len {
+            if new_pos >= len {
                 return false;
             }
 
@@ -339,14 +549,12 @@ where
     if start_dir & (RIGHT_VISIT | DOWN_VISIT) != 0 {
         // Down
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if *visit & DOWN_VISIT != 0 {
+            if visit(seen, epoch, pos, DOWN_VISIT) {
                 return true;
             }
-            *visit |= DOWN_VISIT;
 
-            let new_pos = pos + DIM + 1;
-            if new_pos > len {
+            let new_pos = pos + line_len;
+            if new_pos >= len {
                 return false;
             }
 
@@ -361,11 +569,9 @@ where
     if start_dir != UP_VISIT {
         // Left
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if *visit & LEFT_VISIT != 0 {
+            if visit(seen, epoch, pos, LEFT_VISIT) {
                 return true;
             }
-            *visit |= LEFT_VISIT;
 
             if pos == 0 {
                 return false;
@@ -390,17 +596,15 @@ where
     loop {
         // Up
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if *visit & UP_VISIT != 0 {
+            if visit(seen, epoch, pos, UP_VISIT) {
                 return true;
             }
-            *visit |= UP_VISIT;
 
-            if pos < DIM + 1 {
+            if pos < line_len {
                 return false;
             }
 
-            let new_pos = pos - DIM - 1;
+            let new_pos = pos - line_len;
             if new_pos == obstruction || *input.get_unchecked(new_pos) == BLOCK {
                 break;
             }
@@ -410,14 +614,12 @@ where
 
         // Right
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if *visit & RIGHT_VISIT != 0 {
+            if visit(seen, epoch, pos, RIGHT_VISIT) {
                 return true;
             }
-            *visit |= RIGHT_VISIT;
 
             let new_pos = pos + 1;
-            if new_pos > len {
+            if new_pos >= len {
                 return false;
             }
 
@@ -437,14 +639,12 @@ where
 
         // Down
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if *visit & DOWN_VISIT != 0 {
+            if visit(seen, epoch, pos, DOWN_VISIT) {
                 return true;
             }
-            *visit |= DOWN_VISIT;
 
-            let new_pos = pos + DIM + 1;
-            if new_pos > len {
+            let new_pos = pos + line_len;
+            if new_pos >= len {
                 return false;
             }
 
@@ -457,11 +657,9 @@ where
 
         // Left
         loop {
-            let visit = visited.get_unchecked_mut(pos);
-            if *visit & LEFT_VISIT != 0 {
+            if visit(seen, epoch, pos, LEFT_VISIT) {
                 return true;
             }
-            *visit |= LEFT_VISIT;
 
             if pos == 0 {
                 return false;
@@ -505,12 +703,12 @@ mod tests {
 
     #[test]
     fn p1_example() {
-        assert_eq!(unsafe { inner_p1::<10>(INPUT) }, 41);
+        assert_eq!(part1(INPUT), 41);
     }
 
     #[test]
     fn p2_example() {
-        assert_eq!(unsafe { inner_p2::<10>(INPUT) }, 6);
+        assert_eq!(part2(INPUT), 6);
     }
 
     const REAL: &str = include_str!("../input/2024/day6.txt");
@@ -526,27 +724,42 @@ mod tests {
     }
 
     #[test]
-    fn loops_p2_real() {
-        assert!(!unsafe {
-            loops::<130>(
-                REAL.as_bytes(),
-                find_guard(REAL.as_bytes()),
-                UP_VISIT,
-                usize::MAX,
-            )
+    fn real_scalar() {
+        crate::with_scalar(|| {
+            assert_eq!(part1(REAL), 4665);
+            assert_eq!(part2(REAL), 1_688);
         });
     }
 
+    #[test]
+    fn solver_reuse() {
+        let mut solver = Solver::new();
+        assert_eq!(solver.part2(REAL), 1_688);
+        assert_eq!(solver.part2(INPUT), 6);
+        assert!(solver.try_part1(".#..\n...#\n#^..\n..#.\n").is_err());
+        assert_eq!(solver.part1(INPUT), 41);
+        assert_eq!(solver.part2(REAL), 1_688);
+
+        solver.epoch = MAX_EPOCH - 10;
+        assert_eq!(solver.part2(INPUT), 6);
+        assert_eq!(solver.part2(REAL), 1_688);
+    }
+
+    fn loops_from_guard(input: &str) -> bool {
+        let input = input.as_bytes();
+        let (line_len, guard) = unsafe { read_map::<Checked>(input) }.unwrap();
+        let mut seen = vec![0; input.len()];
+        unsafe { loops(input, line_len, &mut seen, 1, guard, UP_VISIT, usize::MAX) }
+    }
+
+    #[test]
+    fn loops_p2_real() {
+        assert!(!loops_from_guard(REAL));
+    }
+
     #[test]
     fn loops_p2_example() {
-        assert!(!unsafe {
-            loops::<10>(
-                INPUT.as_bytes(),
-                find_guard(INPUT.as_bytes()),
-                UP_VISIT,
-                usize::MAX,
-            )
-        });
+        assert!(!loops_from_guard(INPUT));
     }
 
     #[test]
@@ -556,13 +769,181 @@ mod tests {
             #^#
             ###
         "};
-        assert!(unsafe {
-            loops::<3>(
-                input.as_bytes(),
-                find_guard(input.as_bytes()),
-                UP_VISIT,
-                usize::MAX,
-            )
-        });
+        assert!(loops_from_guard(input));
+    }
+
+    #[test]
+    fn non_square() {
+        let input = indoc! {"
+            .#......
+            ......#.
+            ........
+            .^......
+            .....#..
+        "};
+        assert_eq!(part1(input), 13);
+        assert_eq!(part2(input), 1);
+        assert_eq!(loop_obstructions(input), [(3, 0)]);
+
+        let input = indoc! {"
+            .#.
+            ...
+            ...
+            ...
+            #..
+            .^#
+        "};
+        assert_eq!(try_part1(input), Ok(6));
+        assert_eq!(try_part2(input), Ok(0));
+    }
+
+    #[test]
+    fn obstructions() {
+        let expected = [(6, 3), (7, 6), (7, 7), (8, 1), (8, 3), (9, 7)];
+        assert_eq!(loop_obstructions(INPUT), expected);
+        assert_eq!(try_loop_obstructions(INPUT), Ok(expected.to_vec()));
+
+        let obstructions = loop_obstructions(REAL);
+        assert_eq!(obstructions.len(), 1_688);
+        let line_len = REAL.find('\n').unwrap() + 1;
+        let guard = REAL.find('^').unwrap();
+        for &(row, col) in obstructions.iter().step_by(50) {
+            let mut visits = vec![0; REAL.len()];
+            assert!(patrol(
+                REAL.as_bytes(),
+                line_len,
+                guard,
+                Some(row * line_len + col),
+                &mut visits
+            ));
+        }
+    }
+
+    #[test]
+    fn render() {
+        assert_eq!(
+            render_patrol(INPUT, Some((6, 3))).unwrap(),
+            indoc! {"
+                ....#.....
+                ....+---+#
+                ....|...|.
+                ..#.|...|.
+                ....|..#|.
+                ....|...|.
+                .#.O^---+.
+                ........#.
+                #.........
+                ......#...
+            "}
+        );
+        assert_eq!(
+            render_patrol(INPUT, Some((9, 7))).unwrap(),
+            indoc! {"
+                ....#.....
+                ....+---+#
+                ....|...|.
+                ..#.|...|.
+                ..+-+-+#|.
+                ..|.|.|.|.
+                .#+-^-+-+.
+                .+----++#.
+                #+----++..
+                ......#O..
+            "}
+        );
+        assert_eq!(render_patrol("^\n", None), Ok("^\n".to_owned()));
+        assert!(render_patrol("..\n", None).is_err());
+
+        for (obstruction, line, column, expected, found) in [
+            ((0, 1), 1, 2, "an obstruction inside the map", Some(b'\n')),
+            ((1, 0), 2, 1, "an obstruction inside the map", None),
+            ((5, 20), 2, 1, "an obstruction inside the map", None),
+            (
+                (0, 0),
+                1,
+                1,
+                "an obstruction off the guard's start",
+                Some(b'^'),
+            ),
+        ] {
+            let Err(InputError::UnexpectedByte(err)) = render_patrol("^\n", Some(obstruction))
+            else {
+                panic!("{obstruction:?} should be rejected");
+            };
+            assert_eq!(
+                (err.line, err.column, err.expected, err.found),
+                (line, column, expected, found)
+            );
+        }
+        assert!(render_patrol(INPUT, Some((6, 10))).is_err());
+    }
+
+    #[test]
+    fn checked() {
+        assert_eq!(try_part1(REAL), Ok(4665));
+        assert_eq!(try_part2(REAL), Ok(1_688));
+        assert_eq!(try_part1(INPUT.trim_end()), Ok(41));
+        assert_eq!(try_part2(INPUT.trim_end()), Ok(6));
+
+        let err = try_part1(&INPUT[..INPUT.len() - 2]).unwrap_err();
+        assert_eq!(
+            (err.line, err.expected, err.found),
+            (10, "a last line as long as the first", None)
+        );
+
+        let err = try_part1(&INPUT.replace('^', ".")).unwrap_err();
+        assert_eq!((err.line, err.expected, err.found), (11, "a guard", None));
+
+        let err = try_part2(&INPUT.replacen('.', "^", 1)).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (7, 5, "a single guard", Some(b'^'))
+        );
+    }
+
+    #[test]
+    fn looping_patrol() {
+        let input = indoc! {"
+            .#...
+            ....#
+            .^...
+            #....
+            ...#.
+        "};
+        for err in [try_part1(input), try_part2(input)].map(Result::unwrap_err) {
+            assert_eq!(
+                (err.line, err.column, err.expected, err.found),
+                (3, 2, LOOPING, Some(b'^'))
+            );
+        }
+
+        let mut real = REAL.to_owned().into_bytes();
+        real[16449] = BLOCK;
+        let real = String::from_utf8(real).unwrap();
+        assert_eq!(try_part1(&real).unwrap_err().expected, LOOPING);
+        assert_eq!(try_part2(&real).unwrap_err().expected, LOOPING);
+        // The patrol is the solvers' to walk, not part of the map's shape
+        assert_eq!(validate(&real).map(|shape| shape.width), Ok(Some(130)));
+    }
+
+    #[test]
+    fn validate_real() {
+        assert_eq!(validate(REAL).map(|shape| shape.width), Ok(Some(130)));
+        assert!(matches!(
+            validate("...\n.^\n"),
+            Err(InputError::LineLength {
+                line: 2,
+                expected: 3,
+                found: 2
+            })
+        ));
+        assert!(matches!(
+            validate("^..\n.^.\n"),
+            Err(InputError::UnexpectedByte(err)) if err.expected == "a single guard"
+        ));
+        assert!(matches!(
+            validate("...\n...\n"),
+            Err(InputError::UnexpectedByte(err)) if err.expected == "a guard"
+        ));
     }
 }
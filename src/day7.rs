@@ -3,7 +3,10 @@ use std::intrinsics::{unchecked_add, unchecked_div, unchecked_mul, unchecked_rem
 use aoc_runner_derive::aoc;
 use atoi_simd::parse_any_pos;
 
-use crate::{assume, Assume as _};
+use crate::{
+    assume, ensure, Assume as _, Checked, InputError, InputShape, Mode, ParseError, Unchecked,
+    Unreachable,
+};
 
 const EOL: u8 = b'\n';
 const ZERO: u8 = b'0';
@@ -12,24 +15,43 @@ const SPACE: u8 = b' ';
 const ZERO_11: u16 = ZERO as u16 * 11;
 const ZERO_111: u16 = ZERO as u16 * 111;
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn parse_3_or_shorter(input: &[u8]) -> (u16, u8, usize) {
-    let n1 = *input.get_unchecked(0);
+/// Reads the number of at most 3 digits at `pos`, returning it, the space or new line after it,
+/// and how many digits it has. The end of the input ends the last line, as `aoc-runner` trims the
+/// trailing new line. In checked mode, also checks the number isn't zero
+#[inline]
+unsafe fn parse_3_or_shorter<M: Mode>(
+    input: &[u8],
+    pos: usize,
+) -> Result<(u16, u8, usize), ParseError> {
+    let at = |i: usize| {
+        if M::CHECKED {
+            input.get(pos + i).copied().unwrap_or(EOL)
+        } else {
+            *input.get_unchecked(pos + i)
+        }
+    };
+
+    let n1 = at(0);
+    ensure!(M, n1.is_ascii_digit(), input, pos, "digit");
+    ensure!(M, n1 != ZERO, input, pos, "a non-zero number");
 
-    let n2 = *input.get_unchecked(1);
+    let n2 = at(1);
     if n2 == EOL || n2 == SPACE {
         let num = unchecked_sub(n1, ZERO) as u16;
-        return (num, n2, 1);
+        return Ok((num, n2, 1));
     }
+    ensure!(M, n2.is_ascii_digit(), input, pos + 1, "' ' or '\\n'");
 
-    let n3 = *input.get_unchecked(2);
+    let n3 = at(2);
     if n3 == EOL || n3 == SPACE {
         let num = unchecked_sub(
             unchecked_add(unchecked_mul(n1 as u16, 10), n2 as u16),
             ZERO_11,
         );
-        return (num, n3, 2);
+        return Ok((num, n3, 2));
     }
+    ensure!(M, n3.is_ascii_digit(), input, pos + 2, "' ' or '\\n'");
+
     let num = unchecked_sub(
         unchecked_add(
             unchecked_mul(n1 as u16, 100),
@@ -37,44 +59,94 @@ unsafe fn parse_3_or_shorter(input: &[u8]) -> (u16, u8, usize) {
         ),
         ZERO_111,
     );
+    let n4 = at(3);
+    ensure!(M, n4 == EOL || n4 == SPACE, input, pos + 3, "' ' or '\\n'");
+
+    Ok((num, n4, 3))
+}
+
+/// Reads the equation at `pos` into `nums`: a target, `": "`, then up to 12 space separated
+/// non-zero numbers of at most 3 digits, ending in a new line. Returns the target, the index of
+/// the last number and the offset of the next line
+#[inline(always)]
+unsafe fn read_equation<M: Mode>(
+    input: &[u8],
+    pos: usize,
+    nums: &mut [u16; 12],
+) -> Result<(u64, usize, usize), ParseError> {
+    let (target, bytes) = match parse_any_pos(input.get_unchecked(pos..)) {
+        Ok(parsed) => parsed,
+        Err(_) if M::CHECKED => {
+            let expected = if input.get(pos).is_some_and(u8::is_ascii_digit) {
+                "a target that fits in a u64"
+            } else {
+                "digit"
+            };
+            return Err(ParseError::new(input, pos, expected));
+        }
+        Err(_) => Unreachable.assume(),
+    };
+
+    let mut pos = pos + bytes;
+    ensure!(M, input.get(pos) == Some(&b':'), input, pos, "':'");
+    pos += 1;
+
+    let mut index = 0;
+    loop {
+        ensure!(M, input.get(pos) == Some(&SPACE), input, pos, "' '");
+        ensure!(M, index < 12, input, pos + 1, "at most 12 numbers");
+        let (next, term, bytes) = parse_3_or_shorter::<M>(input, pos + 1)?;
+        *nums.get_unchecked_mut(index) = next;
+        pos += bytes + 1;
+        if term == EOL {
+            return Ok((target, index, pos + 1));
+        }
+        index += 1;
+    }
+}
+
+/// Checks every line of `input` is an equation of at most 12 numbers under 1000, as [`part1`]
+/// and [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let input = input.as_bytes();
+    let shape = InputShape::of(input)?;
+    let mut nums = [0; 12];
+    let mut pos = 0;
+    while pos < input.len() {
+        (_, _, pos) = unsafe { read_equation::<Checked>(input, pos, &mut nums)? };
+    }
 
-    (num, *input.get_unchecked(3), 3)
+    Ok(shape)
 }
 
 #[aoc(day7, part1)]
 pub fn part1(input: &str) -> u64 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> u64 {
-        let input = input.as_bytes();
-        let mut count = 0;
-        let mut vec = [0; 12];
-        let mut pos = 0;
-
-        while input.len() > pos + 3 {
-            let (target, bytes) = parse_any_pos(input.get_unchecked(pos..)).assume();
-            pos += bytes + 2;
-
-            let mut index = 0;
-            loop {
-                let (next, term, bytes) = parse_3_or_shorter(input.get_unchecked(pos..));
-                *vec.get_unchecked_mut(index) = next;
-                index += 1;
-                pos += bytes + 1;
-                if term == EOL {
-                    break;
-                }
-            }
-
-            let math_checks_out = recurse_p1(target, &vec, index - 1);
-            count += target * math_checks_out as u64;
-        }
+    crate::dispatch(|| unsafe { inner_p1::<Unchecked>(input).assume() })
+}
 
-        count
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<u64, ParseError> {
+    crate::dispatch(|| unsafe { inner_p1::<Checked>(input) })
+}
+
+#[inline(always)]
+unsafe fn inner_p1<M: Mode>(input: &str) -> Result<u64, ParseError> {
+    let input = input.as_bytes();
+    let mut count = 0;
+    let mut vec = [0; 12];
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let (target, last, next) = read_equation::<M>(input, pos, &mut vec)?;
+        pos = next;
+
+        let math_checks_out = recurse_p1(target, &vec, last);
+        count += target * math_checks_out as u64;
     }
-    unsafe { inner(input) }
+
+    Ok(count)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
 unsafe fn recurse_p1(target: u64, nums: &[u16; 12], index: usize) -> bool {
     assume!(index < 12);
     let num = *nums.get_unchecked(index) as u64;
@@ -88,38 +160,32 @@ unsafe fn recurse_p1(target: u64, nums: &[u16; 12], index: usize) -> bool {
 
 #[aoc(day7, part2)]
 pub fn part2(input: &str) -> u64 {
-    #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-    unsafe fn inner(input: &str) -> u64 {
-        let input = input.as_bytes();
-        let mut count = 0;
-        let mut vec = [0; 12];
-        let mut pos = 0;
-
-        while input.len() > pos + 3 {
-            let (target, bytes) = parse_any_pos(input.get_unchecked(pos..)).assume();
-            pos += bytes + 2;
-
-            let mut index = 0;
-            loop {
-                let (next, term, bytes) = parse_3_or_shorter(input.get_unchecked(pos..));
-                *vec.get_unchecked_mut(index) = next;
-                index += 1;
-                pos += bytes + 1;
-                if term == EOL {
-                    break;
-                }
-            }
-
-            let math_checks_out = recurse_p2(target, &vec, index - 1);
-            count += target * math_checks_out as u64;
-        }
+    crate::dispatch(|| unsafe { inner_p2::<Unchecked>(input).assume() })
+}
+
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<u64, ParseError> {
+    crate::dispatch(|| unsafe { inner_p2::<Checked>(input) })
+}
 
-        count
+#[inline(always)]
+unsafe fn inner_p2<M: Mode>(input: &str) -> Result<u64, ParseError> {
+    let input = input.as_bytes();
+    let mut count = 0;
+    let mut vec = [0; 12];
+    let mut pos = 0;
+
+    while pos < input.len() {
+        let (target, last, next) = read_equation::<M>(input, pos, &mut vec)?;
+        pos = next;
+
+        let math_checks_out = recurse_p2(target, &vec, last);
+        count += target * math_checks_out as u64;
     }
-    unsafe { inner(input) }
+
+    Ok(count)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
 unsafe fn recurse_p2(target: u64, nums: &[u16; 12], index: usize) -> bool {
     assume!(index < 12);
     let num = *nums.get_unchecked(index) as u64;
@@ -182,13 +248,79 @@ mod tests {
         assert_eq!(part2(input), 500_335_179_214_836);
     }
 
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day7.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 10_741_443_549_536);
+            assert_eq!(part2(input), 500_335_179_214_836);
+        });
+    }
+
     #[test]
     fn parsing() {
         unsafe {
-            assert_eq!(parse_3_or_shorter(b"1\n00"), (1, EOL, 1));
-            assert_eq!(parse_3_or_shorter(b"1 0\n"), (1, SPACE, 1));
-            assert_eq!(parse_3_or_shorter(b"12 3"), (12, SPACE, 2));
-            assert_eq!(parse_3_or_shorter(b"123\n"), (123, EOL, 3));
+            assert_eq!(
+                parse_3_or_shorter::<Unchecked>(b"1\n00", 0),
+                Ok((1, EOL, 1))
+            );
+            assert_eq!(
+                parse_3_or_shorter::<Unchecked>(b"1 0\n", 0),
+                Ok((1, SPACE, 1))
+            );
+            assert_eq!(
+                parse_3_or_shorter::<Unchecked>(b"12 3", 0),
+                Ok((12, SPACE, 2))
+            );
+            assert_eq!(
+                parse_3_or_shorter::<Unchecked>(b"123\n", 0),
+                Ok((123, EOL, 3))
+            );
+            assert_eq!(parse_3_or_shorter::<Checked>(b"12", 0), Ok((12, EOL, 2)));
+            assert_eq!(parse_3_or_shorter::<Checked>(b"5 1", 2), Ok((1, EOL, 1)));
+        }
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day7.txt");
+        assert_eq!(try_part1(input), Ok(part1(input)));
+        assert_eq!(try_part2(input), Ok(part2(input)));
+
+        let mut input = input.to_owned();
+        input.replace_range(4..5, ";");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 5, "':'", Some(b';'))
+        );
+        input.replace_range(4..5, ":");
+
+        assert_eq!(try_part1(input.trim_end()), Ok(part1(&input)));
+        assert_eq!(try_part2(input.trim_end()), Ok(part2(&input)));
+        for (line, column, expected) in [
+            ("1: 1234\n", 7, "' ' or '\\n'"),
+            (
+                "99999999999999999999: 1\n",
+                1,
+                "a target that fits in a u64",
+            ),
+            (": 1\n", 1, "digit"),
+            ("1:1\n", 3, "' '"),
+            ("1: 1 1 1 1 1 1 1 1 1 1 1 1 1\n", 28, "at most 12 numbers"),
+        ] {
+            let err = try_part2(line).unwrap_err();
+            assert_eq!((err.column, err.expected), (column, expected), "{line:?}");
         }
     }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day7.txt");
+        assert_eq!(validate(input).map(|shape| shape.records), Ok(850));
+        assert!(matches!(
+            validate("1: 2 0\n"),
+            Err(InputError::UnexpectedByte(ParseError { column: 6, .. }))
+        ));
+    }
 }
@@ -1,9 +1,14 @@
-use core::str;
-use std::simd::{cmp::SimdPartialOrd as _, Simd};
+use std::simd::{
+    cmp::{SimdPartialEq as _, SimdPartialOrd as _},
+    Simd,
+};
 
 use aoc_runner_derive::aoc;
 
-use crate::{debug, ptr_add, ArrayVec, BigBitSet, BitIterU64 as BitIter, IndexI8 as Index, ZERO};
+use crate::{
+    debug, ensure, Assume as _, BitIterU64 as BitIter, Checked, InputError, InputShape, Mode,
+    ParseError, Unchecked, EOL, ZERO,
+};
 
 const ANTENNA_OPTS: usize = (b'z' - b'0' + 1) as usize;
 const _: () = {
@@ -11,182 +16,333 @@ const _: () = {
     assert!(b'Z' < b'z');
 };
 
+const CHUNK: usize = 32;
+
+/// Checks `input` is a rectangular map of `.` and alphanumeric antennae, as [`part1`] and
+/// [`part2`] assume.
+pub fn validate(input: &str) -> Result<InputShape, InputError> {
+    let bytes = input.as_bytes();
+    let shape = InputShape::of(bytes)?;
+    let width = memchr::memchr(EOL, bytes).unwrap_or_default();
+    let shape = shape.expect_width(bytes, shape.lines, width)?;
+    let (width, height) = dimensions::<Checked>(bytes)?;
+    unsafe { scan::<Checked>(bytes, width, height, |_, _, _| ())? };
+
+    Ok(shape)
+}
+
 #[aoc(day8, part1)]
 pub fn part1(input: &str) -> i32 {
-    unsafe { inner_p1(input) }
+    Solver::new().part1(input)
 }
 
-const DIM: usize = 50;
-const SECOND_HALF_START: usize = 50 - 32;
+/// [`part1`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part1(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part1(input)
+}
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p1(input: &str) -> i32 {
-    let mut input = input.as_bytes().as_ptr();
+/// Finds the width and height of the map from the first new line. The end of the input ends the
+/// last line, as `aoc-runner` trims the trailing new line. In checked mode, also checks the last
+/// line is as long as the first
+#[inline(always)]
+fn dimensions<M: Mode>(input: &[u8]) -> Result<(usize, usize), ParseError> {
+    let width = memchr::memchr(EOL, input).unwrap_or(input.len());
+    ensure!(
+        M,
+        input.len() % (width + 1) == 0 || input.len() % (width + 1) == width,
+        input,
+        input.len(),
+        "a last line as long as the first"
+    );
 
-    let mut antennae = [ArrayVec::<4, Index<DIM>>::new_unchecked(); ANTENNA_OPTS];
-    let mut antinodes = BigBitSet::<{ DIM * (DIM + 1) / 8 + 1 }>::new();
-    let mut count = 0;
+    Ok((width, input.len().div_ceil(width + 1)))
+}
 
+/// Calls `antenna` with the frequency, `x` and `y` of every antenna, in reading order. Each line is
+/// scanned `CHUNK` bytes at a time, copying the end of the input into a buffer of `.` so that no
+/// load reads past it. In checked mode, also checks each chunk is only `.` and alphanumeric
+/// antennae, and each line ends in a new line
+#[inline(always)]
+unsafe fn scan<M: Mode>(
+    input: &[u8],
+    width: usize,
+    height: usize,
+    mut antenna: impl FnMut(usize, i32, i32),
+) -> Result<(), ParseError> {
+    let dots = Simd::splat(b'.');
     let zeroes = Simd::splat(ZERO);
 
-    for y in 0..DIM {
-        let first_half = input.cast::<Simd<u8, 32>>().read_unaligned();
-        let second_half = ptr_add(input, SECOND_HALF_START as _)
-            .cast::<Simd<u8, 32>>()
-            .read_unaligned();
-        let mask = first_half.simd_ge(zeroes).to_bitmask()
-            | second_half
-                .simd_ge(zeroes)
-                .to_bitmask()
-                .unchecked_shl(SECOND_HALF_START as _);
-        debug!("Mask:\n{mask:050b}\n{}", {
-            let mut line = input.cast::<[u8; DIM]>().read_unaligned();
-            line.reverse();
-            str::from_utf8(&line).unwrap().to_owned()
-        });
-
-        for x in BitIter(mask) {
-            let pos = Index {
-                x: x as _,
-                y: y as _,
+    for y in 0..height {
+        let line = y * (width + 1);
+        for start in (0..width).step_by(CHUNK) {
+            let chunk = match input.get(line + start..line + start + CHUNK) {
+                Some(chunk) => Simd::<u8, CHUNK>::from_slice(chunk),
+                None => Simd::load_or(input.get_unchecked(line + start..), dots),
             };
-            let c: u8 = *ptr_add(input, x);
-            debug!("Found char {c:x} ({}) at {pos:?}", c as char);
-            let antennae = antennae.get_unchecked_mut(c.unchecked_sub(ZERO) as usize);
-
-            for &antenna in antennae.into_iter() {
-                let diff = pos - antenna;
-                debug!("{antenna:?}, {pos:?}, {diff:?}");
-                if let Some(pos) = (antenna - diff).checked_to() {
-                    let (antinode_index, antinode_mask) = antinodes.calc_byte_mask(pos);
-                    let antinode = antinodes.get_byte_unchecked_mut(antinode_index);
-                    count += ((*antinode & antinode_mask) == 0) as i32;
-                    *antinode |= antinode_mask;
-                }
-                if let Some(pos) = (pos + diff).checked_to() {
-                    let (antinode_index, antinode_mask) = antinodes.calc_byte_mask(pos);
-                    let antinode = antinodes.get_byte_unchecked_mut(antinode_index);
-                    count += ((*antinode & antinode_mask) == 0) as i32;
-                    *antinode |= antinode_mask;
-                }
+
+            let mut mask = chunk.simd_ge(zeroes).to_bitmask();
+            let remaining = width - start;
+            let in_line = (1 << remaining.min(CHUNK)) - 1;
+            mask &= in_line;
+
+            if M::CHECKED {
+                let between = |lo: u8, hi: u8| {
+                    chunk.simd_ge(Simd::splat(lo)) & chunk.simd_le(Simd::splat(hi))
+                };
+                let valid = (chunk.simd_eq(dots)
+                    | between(b'0', b'9')
+                    | between(b'A', b'Z')
+                    | between(b'a', b'z'))
+                .to_bitmask();
+                let invalid = !valid & in_line;
+                ensure!(
+                    M,
+                    invalid == 0,
+                    input,
+                    line + start + invalid.trailing_zeros() as usize,
+                    "'.' or an alphanumeric antenna"
+                );
             }
 
-            antennae.push_unchecked(pos);
+            for x in BitIter(mask) {
+                let x = start + x;
+                let c = *input.get_unchecked(line + x);
+                debug!("Found char {c:x} ({}) at {x}, {y}", c as char);
+                antenna(c.unchecked_sub(ZERO) as usize, x as i32, y as i32);
+            }
         }
 
-        input = ptr_add(input, const { DIM + 1 } as _);
-    }
-
-    // debug!("Final map:\n{}", {
-    //     let mut s = String::new();
-    //     for (i, &c) in input.iter().enumerate() {
-    //         s.push(if antinodes.get_unchecked(i) {
-    //             if c == b'.' {
-    //                 '+'
-    //             } else {
-    //                 '#'
-    //             }
-    //         } else {
-    //             c as char
-    //         })
-    //     }
-    //     s
-    // });
-
-    count
+        ensure!(
+            M,
+            matches!(input.get(line + width), Some(&EOL) | None),
+            input,
+            line + width,
+            "'\\n'"
+        );
+    }
+
+    Ok(())
 }
 
 #[aoc(day8, part2)]
 pub fn part2(input: &str) -> i32 {
-    unsafe { inner_p2(input) }
+    Solver::new().part2(input)
 }
 
-#[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
-unsafe fn inner_p2(input: &str) -> i32 {
-    let mut input = input.as_bytes().as_ptr();
+/// [`part2`], reporting malformed input rather than assuming it is well formed.
+pub fn try_part2(input: &str) -> Result<i32, ParseError> {
+    Solver::new().try_part2(input)
+}
 
-    let mut antennae = [ArrayVec::<4, Index<DIM>>::new_unchecked(); ANTENNA_OPTS];
-    let mut antinodes = [false; DIM * (DIM + 1)];
-    let mut count = 0;
+/// Solves day 8, keeping the antennae of each frequency and the antinodes found in buffers it
+/// reuses between inputs
+#[derive(Clone, Debug, Default)]
+pub struct Solver {
+    antennae: Vec<Vec<(i32, i32)>>,
+    antinodes: Vec<bool>,
+}
 
-    let zeroes = Simd::splat(ZERO);
+impl Solver {
+    pub fn new() -> Self {
+        Self::default()
+    }
 
-    for y in 0..DIM {
-        let first_half = input.cast::<Simd<u8, 32>>().read_unaligned();
-        let second_half = ptr_add(input, SECOND_HALF_START as _)
-            .cast::<Simd<u8, 32>>()
-            .read_unaligned();
-        let mask = first_half.simd_ge(zeroes).to_bitmask()
-            | second_half
-                .simd_ge(zeroes)
-                .to_bitmask()
-                .unchecked_shl(SECOND_HALF_START as _);
-        debug!("Mask:\n{mask:050b}\n{}", {
-            let mut line = input.cast::<[u8; DIM]>().read_unaligned();
-            line.reverse();
-            str::from_utf8(&line).unwrap().to_owned()
-        });
+    pub fn part1(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { self.inner_p1::<Unchecked>(input).assume() })
+    }
 
-        for x in BitIter(mask) {
-            let pos = Index {
-                x: x as _,
-                y: y as _,
-            };
-            let c: u8 = *ptr_add(input, x);
-            debug!("Found char {c:x} ({}) at {pos:?}", c as char);
-            let antennae = antennae.get_unchecked_mut(c.unchecked_sub(ZERO) as usize);
-
-            for &antenna in antennae.into_iter() {
-                let diff = pos - antenna;
-                {
-                    let mut index = antenna;
-                    while let Some(pos) = index.checked_to() {
-                        let antinode = antinodes.get_unchecked_mut(pos);
-                        count += !*antinode as i32;
-                        *antinode = true;
-                        index -= diff;
-                    }
-                }
-                {
-                    let mut index = pos;
-                    while let Some(pos) = index.checked_to() {
-                        let antinode = antinodes.get_unchecked_mut(pos);
-                        count += !*antinode as i32;
-                        *antinode = true;
-                        index += diff;
-                    }
-                }
+    /// [`Solver::part1`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part1(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { self.inner_p1::<Checked>(input) })
+    }
+
+    pub fn part2(&mut self, input: &str) -> i32 {
+        crate::dispatch(|| unsafe { self.inner_p2::<Unchecked>(input).assume() })
+    }
+
+    /// [`Solver::part2`], reporting malformed input rather than assuming it is well formed.
+    pub fn try_part2(&mut self, input: &str) -> Result<i32, ParseError> {
+        crate::dispatch(|| unsafe { self.inner_p2::<Checked>(input) })
+    }
+
+    /// Empties the antennae of every frequency, and marks none of a map of `cells` cells as
+    /// antinodes
+    fn reset(&mut self, cells: usize) {
+        self.antennae.resize_with(ANTENNA_OPTS, Vec::new);
+        self.antennae.iter_mut().for_each(Vec::clear);
+        self.antinodes.clear();
+        self.antinodes.resize(cells, false);
+    }
+
+    #[inline(always)]
+    unsafe fn inner_p1<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
+        let input = input.as_bytes();
+        let (width, height) = dimensions::<M>(input)?;
+        let (w, h) = (width as i32, height as i32);
+
+        self.reset(width * height);
+        let Self {
+            antennae,
+            antinodes,
+        } = self;
+        let mut count = 0;
+
+        let mut mark = |(x, y): (i32, i32)| {
+            if (0..w).contains(&x) && (0..h).contains(&y) {
+                let antinode = antinodes.get_unchecked_mut((y * w + x) as usize);
+                count += !*antinode as i32;
+                *antinode = true;
             }
+        };
 
-            antennae.push_unchecked(pos);
-        }
+        scan::<M>(input, width, height, |c, x, y| {
+            let antennae = antennae.get_unchecked_mut(c);
+            for &(ax, ay) in antennae.iter() {
+                let (dx, dy) = (x - ax, y - ay);
+                debug!("{ax}, {ay}, {x}, {y}, {dx}, {dy}");
+                mark((ax - dx, ay - dy));
+                mark((x + dx, y + dy));
+            }
+
+            antennae.push((x, y));
+        })?;
+
+        Ok(count)
+    }
+
+    #[inline(always)]
+    unsafe fn inner_p2<M: Mode>(&mut self, input: &str) -> Result<i32, ParseError> {
+        let input = input.as_bytes();
+        let (width, height) = dimensions::<M>(input)?;
+        let (w, h) = (width as i32, height as i32);
+
+        self.reset(width * height);
+        let Self {
+            antennae,
+            antinodes,
+        } = self;
+        let mut count = 0;
+
+        let mut mark_line = |(mut x, mut y): (i32, i32), (dx, dy): (i32, i32)| {
+            while (0..w).contains(&x) && (0..h).contains(&y) {
+                let antinode = antinodes.get_unchecked_mut((y * w + x) as usize);
+                count += !*antinode as i32;
+                *antinode = true;
+                (x, y) = (x + dx, y + dy);
+            }
+        };
+
+        scan::<M>(input, width, height, |c, x, y| {
+            let antennae = antennae.get_unchecked_mut(c);
+            for &(ax, ay) in antennae.iter() {
+                let (dx, dy) = (x - ax, y - ay);
+                mark_line((ax, ay), (-dx, -dy));
+                mark_line((x, y), (dx, dy));
+            }
+
+            antennae.push((x, y));
+        })?;
 
-        input = ptr_add(input, const { DIM + 1 } as _);
-    }
-
-    // debug!("Final map:\n{}", {
-    //     let mut s = String::new();
-    //     for (i, &c) in input.iter().enumerate() {
-    //         s.push(if antinodes[i] {
-    //             if c == b'.' {
-    //                 '+'
-    //             } else {
-    //                 '#'
-    //             }
-    //         } else {
-    //             c as char
-    //         })
-    //     }
-    //     s
-    // });
-
-    count
+        Ok(count)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
+
     use super::*;
 
+    const INPUT: &str = indoc! {"
+        ............
+        ........0...
+        .....0......
+        .......0....
+        ....0.......
+        ......A.....
+        ............
+        ............
+        ........A...
+        .........A..
+        ............
+        ............
+    "};
+
+    #[test]
+    fn p1_example() {
+        assert_eq!(part1(INPUT), 14);
+    }
+
+    #[test]
+    fn p2_example() {
+        assert_eq!(part2(INPUT), 34);
+    }
+
+    /// Counts antinodes by checking every cell against every pair of antennae, stepping by the
+    /// whole distance between them like the solvers do
+    fn brute_force(input: &str, harmonics: bool) -> i32 {
+        let lines: Vec<&[u8]> = input.lines().map(str::as_bytes).collect();
+        let antennae: Vec<(i32, i32, u8)> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(y, line)| {
+                line.iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| c != b'.')
+                    .map(move |(x, &c)| (x as i32, y as i32, c))
+            })
+            .collect();
+
+        let mut count = 0;
+        for y in 0..lines.len() as i32 {
+            for x in 0..lines[0].len() as i32 {
+                count += antennae.iter().any(|&(ax, ay, a)| {
+                    antennae.iter().any(|&(bx, by, b)| {
+                        let (dx, dy) = (bx - ax, by - ay);
+                        let cross = (x - ax) * dy - (y - ay) * dx;
+                        a == b
+                            && (ax, ay) != (bx, by)
+                            && if harmonics {
+                                cross == 0
+                                    && (dx == 0 || (x - ax) % dx == 0)
+                                    && (dy == 0 || (y - ay) % dy == 0)
+                            } else {
+                                (x - bx, y - by) == (dx, dy)
+                            }
+                    })
+                }) as i32;
+            }
+        }
+        count
+    }
+
+    #[test]
+    fn any_size() {
+        let mut input = String::new();
+        for y in 0..23_usize {
+            input.extend((0..77_usize).map(|x| match (x * 7 + y * 13) % 31 {
+                0 => 'a',
+                5 => 'B',
+                9 if y % 2 == 0 => '7',
+                _ => '.',
+            }));
+            input.push('\n');
+        }
+        assert!(input.matches('a').count() > 4);
+
+        assert_eq!(part1(&input), brute_force(&input, false));
+        assert_eq!(part2(&input), brute_force(&input, true));
+        assert_eq!(try_part1(&input), Ok(part1(&input)));
+        crate::with_scalar(|| assert_eq!(part2(&input), brute_force(&input, true)));
+
+        let input = "a..a.a\n";
+        assert_eq!(part1(input), 1);
+        assert_eq!(part2(input), 4);
+        assert_eq!(part1(""), 0);
+        assert_eq!(part1(INPUT.trim_end()), 14);
+        assert_eq!(part2(INPUT.trim_end()), 34);
+    }
+
     #[test]
     fn real_p1() {
         let input = include_str!("../input/2024/day8.txt");
@@ -198,4 +354,74 @@ mod tests {
         let input = include_str!("../input/2024/day8.txt");
         assert_eq!(part2(input), 1_221);
     }
+
+    #[test]
+    fn real_scalar() {
+        let input = include_str!("../input/2024/day8.txt");
+        crate::with_scalar(|| {
+            assert_eq!(part1(input), 348);
+            assert_eq!(part2(input), 1_221);
+        });
+    }
+
+    #[test]
+    fn solver_reuse() {
+        let input = include_str!("../input/2024/day8.txt");
+        let mut solver = Solver::new();
+        assert_eq!(solver.part2(input), 1_221);
+        assert_eq!(solver.part1(INPUT), 14);
+        assert!(solver.try_part1("a.?\n").is_err());
+        assert_eq!(solver.part1(input), 348);
+        assert_eq!(solver.part2(INPUT), 34);
+    }
+
+    #[test]
+    fn checked() {
+        let input = include_str!("../input/2024/day8.txt");
+        assert_eq!(try_part1(input), Ok(part1(input)));
+        assert_eq!(try_part2(input), Ok(part2(input)));
+        assert_eq!(try_part1(input.trim_end()), Ok(part1(input)));
+        assert_eq!(try_part2(input.trim_end()), Ok(part2(input)));
+        assert_eq!(try_part1(""), Ok(0));
+
+        let err = try_part1(&input[..input.len() - 2]).unwrap_err();
+        assert_eq!(
+            (err.line, err.expected, err.found),
+            (50, "a last line as long as the first", None)
+        );
+
+        let mut input = input.to_owned();
+        input.replace_range(101..102, "a");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (2, 51, "'\\n'", Some(b'a'))
+        );
+        input.replace_range(101..102, "\n");
+
+        input.replace_range(0..1, "!");
+        let err = try_part1(&input).unwrap_err();
+        assert_eq!(
+            (err.line, err.column, err.expected, err.found),
+            (1, 1, "'.' or an alphanumeric antenna", Some(b'!'))
+        );
+    }
+
+    #[test]
+    fn validate_real() {
+        let input = include_str!("../input/2024/day8.txt");
+        assert_eq!(validate(input).map(|shape| shape.width), Ok(Some(50)));
+        assert!(matches!(
+            validate("a..\n.a\n"),
+            Err(InputError::LineLength {
+                line: 2,
+                expected: 3,
+                found: 2
+            })
+        ));
+        assert!(matches!(
+            validate("a..\n.#a\n"),
+            Err(InputError::UnexpectedByte(err)) if (err.line, err.column) == (2, 2)
+        ));
+    }
 }
@@ -0,0 +1,321 @@
+//! Reading the examples out of a saved puzzle description, to keep as fixtures in
+//! `input/2024/examples`.
+//!
+//! Each example is saved as `dayN-K.txt`, the Kth example of day N, and its answers go in the
+//! directory's `answers.toml` under the name `dayN-K`, laid out as [`Answers`] expects.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+use crate::{
+    answers::{Answers, AnswersError},
+    batch::Input,
+};
+
+/// One example input from a puzzle description.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Example {
+    pub text: String,
+    /// The answer the description gives for each part, if any
+    pub answers: [Option<String>; 2],
+}
+
+/// Finds the examples in the HTML of a puzzle description, with the answers it gives for them.
+///
+/// Each part is an `<article>`. An example is a `<pre><code>` block, and the answer given for it
+/// is the last `<code><em>` after it but before the next example. Blocks after an example but
+/// before its answer show the working, so aren't examples themselves. A part that gives an answer
+/// without an example of its own gives it for the first example. Descriptions that emphasise
+/// answers along the way can fool this, so check what it finds.
+pub fn extract(page: &str) -> Vec<Example> {
+    let mut examples: Vec<Example> = Vec::new();
+
+    for (part, article) in page
+        .split("<article")
+        .skip(1)
+        .filter_map(|article| article.split_once("</article>").map(|(article, _)| article))
+        .take(2)
+        .enumerate()
+    {
+        // The example the next answer is for, and whether it's been given one yet
+        let mut current: Option<(usize, bool)> = None;
+        let mut rest = article;
+        loop {
+            let block = rest.find("<pre><code>");
+            let answer = [rest.find("<code><em>"), rest.find("<em><code>")]
+                .into_iter()
+                .flatten()
+                .min();
+
+            match (block, answer) {
+                (Some(block), answer) if answer.is_none_or(|answer| block < answer) => {
+                    let text = &rest[block + "<pre><code>".len()..];
+                    let Some((text, after)) = text.split_once("</code></pre>") else {
+                        break;
+                    };
+                    if current.is_none_or(|(_, answered)| answered) {
+                        examples.push(Example {
+                            text: unescape(text),
+                            ..Example::default()
+                        });
+                        current = Some((examples.len() - 1, false));
+                    }
+                    rest = after;
+                }
+                (_, Some(answer)) => {
+                    let text = &rest[answer + "<code><em>".len()..];
+                    let Some((text, after)) = text
+                        .split_once("</em></code>")
+                        .or_else(|| text.split_once("</code></em>"))
+                    else {
+                        break;
+                    };
+                    let example = match current {
+                        Some((example, _)) => example,
+                        None if !examples.is_empty() => 0,
+                        None => {
+                            rest = after;
+                            continue;
+                        }
+                    };
+                    examples[example].answers[part] = Some(unescape(text));
+                    current = Some((example, true));
+                    rest = after;
+                }
+                _ => break,
+            }
+        }
+    }
+
+    examples
+}
+
+/// Strips the tags from `html` and decodes the entities AoC uses
+fn unescape(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(tag) = rest.find('<') {
+        text.push_str(&rest[..tag]);
+        rest = rest[tag..].split_once('>').map_or("", |(_, after)| after);
+    }
+    text.push_str(rest);
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// The examples couldn't be saved, or the answers already there couldn't be read.
+#[derive(Debug)]
+pub enum ExamplesError {
+    Io(io::Error),
+    Answers(AnswersError),
+}
+
+impl From<io::Error> for ExamplesError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<AnswersError> for ExamplesError {
+    fn from(err: AnswersError) -> Self {
+        Self::Answers(err)
+    }
+}
+
+impl Display for ExamplesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Answers(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl Error for ExamplesError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Answers(err) => Some(err),
+        }
+    }
+}
+
+/// Saves `examples` of `day` in `dir` as `dayN-K.txt`, adding their answers to `answers.toml`.
+pub fn save(dir: &Path, day: u8, examples: &[Example]) -> Result<(), ExamplesError> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join("answers.toml");
+    let mut answers = match fs::read_to_string(&path) {
+        Ok(text) => Answers::parse(&text)?,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Answers::default(),
+        Err(err) => return Err(err.into()),
+    };
+
+    for (index, example) in examples.iter().enumerate() {
+        let name = format!("day{day}-{}", index + 1);
+        fs::write(dir.join(format!("{name}.txt")), &example.text)?;
+        for (part, answer) in (1..).zip(&example.answers) {
+            if let Some(answer) = answer {
+                answers.insert(day, part, &name, answer);
+            }
+        }
+    }
+
+    fs::write(path, answers.to_string())?;
+    Ok(())
+}
+
+/// Reads every `dayN-K.txt` in `dir`, named `dayN-K`, sorted by day then number.
+pub fn discover(dir: &Path) -> io::Result<Vec<Input>> {
+    let mut inputs = Vec::new();
+    for file in fs::read_dir(dir)? {
+        let path = file?.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some((day, number)) = name
+            .strip_prefix("day")
+            .and_then(|name| name.strip_suffix(".txt"))
+            .and_then(|name| name.split_once('-'))
+        else {
+            continue;
+        };
+        let (Ok(day), Ok(number)) = (day.parse(), number.parse::<u32>()) else {
+            continue;
+        };
+
+        inputs.push((
+            number,
+            Input {
+                user: name.trim_end_matches(".txt").to_owned(),
+                day,
+                text: fs::read_to_string(&path)?,
+            },
+        ));
+    }
+
+    inputs.sort_by_key(|(number, input)| (input.day, *number));
+    Ok(inputs.into_iter().map(|(_, input)| input).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+
+    const PAGE: &str = indoc! {r#"
+        <main>
+        <article class="day-desc"><h2>--- Day 7: Bridge Repair ---</h2>
+        <p>For example:</p>
+        <pre><code>190: 10 19
+        3267: 81 40 27
+        </code></pre>
+        <p>Only <code>190</code> can be made true, so the total is:</p>
+        <pre><code>190 = 10 * 19
+        </code></pre>
+        <p>The total calibration result is <code><em>190</em></code>.</p>
+        <p>A second example, with working:</p>
+        <pre><code>1 &lt; 2 &amp;&amp; <em>x</em>
+        </code></pre>
+        <pre><code>working
+        </code></pre>
+        <p>Here the total is <em><code>3</code></em>.</p>
+        </article>
+        <p>Your puzzle answer was <code>10741443549536</code>.</p>
+        <article class="day-desc"><h2 id="part2">--- Part Two ---</h2>
+        <p>In the example above, <code><em>3267</em></code> can now also be made true, so the
+        total is now <code><em>3457</em></code>.</p>
+        </article>
+        </main>
+    "#};
+
+    #[test]
+    fn extract() {
+        assert_eq!(
+            super::extract(PAGE),
+            [
+                Example {
+                    text: "190: 10 19\n3267: 81 40 27\n".to_owned(),
+                    answers: [Some("190".to_owned()), Some("3457".to_owned())],
+                },
+                Example {
+                    text: "1 < 2 && x\n".to_owned(),
+                    answers: [Some("3".to_owned()), None],
+                },
+            ]
+        );
+        assert_eq!(super::extract("<p>No puzzle here</p>"), []);
+    }
+
+    #[test]
+    fn save_and_discover() {
+        let dir = std::env::temp_dir().join(format!("aoc-examples-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("answers.toml"), "[day1.part1]\nday1-1 = 11\n").unwrap();
+
+        save(&dir, 7, &super::extract(PAGE)).unwrap();
+        let answers = Answers::parse(&fs::read_to_string(dir.join("answers.toml")).unwrap());
+        let answers = answers.unwrap();
+        assert_eq!(answers.len(), 4);
+        assert_eq!(answers.get(1, 1, "day1-1"), Some("11"));
+        assert_eq!(answers.get(7, 2, "day7-1"), Some("3457"));
+        assert_eq!(answers.get(7, 1, "day7-2"), Some("3"));
+
+        fs::write(dir.join("day10-1.txt"), "").unwrap();
+        fs::write(dir.join("notes.txt"), "").unwrap();
+        let inputs = discover(&dir).unwrap();
+        let names: Vec<_> = inputs.iter().map(|input| input.user.as_str()).collect();
+        assert_eq!(names, ["day7-1", "day7-2", "day10-1"]);
+        assert_eq!(inputs[1].text, "1 < 2 && x\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Days whose solvers are specialised to the size of the real inputs, so reject the examples
+    const REAL_SIZE_ONLY: [u8; 2] = [14, 16];
+
+    /// Runs every saved example through the solvers for each part it has an answer for, except
+    /// on the [`REAL_SIZE_ONLY`] days, whose validation must reject them.
+    #[test]
+    fn examples() {
+        let dir = Path::new(concat!(env!("CARGO_MANIFEST_DIR"), "/input/2024/examples"));
+        let answers = Answers::parse(&fs::read_to_string(dir.join("answers.toml")).unwrap());
+        let answers = answers.unwrap();
+
+        let mut checked = 0;
+        for input in discover(dir).unwrap() {
+            for part in 1..=2 {
+                let Some(expected) = answers.get(input.day, part, &input.user) else {
+                    continue;
+                };
+                let solution = crate::solution(input.day, part).unwrap();
+                let valid = solution.check(&input.text);
+                if REAL_SIZE_ONLY.contains(&input.day) {
+                    assert!(valid.is_err(), "{} should be rejected", input.user);
+                    continue;
+                }
+                if let Err(err) = valid {
+                    panic!("{} part {part} doesn't validate: {err}", input.user);
+                }
+
+                assert_eq!(
+                    (solution.solve)(&input.text),
+                    expected,
+                    "{} part {part}",
+                    input.user
+                );
+                checked += 1;
+            }
+        }
+        assert!(checked > 0);
+    }
+}
@@ -0,0 +1,950 @@
+//! Seeded random inputs for every solved day, in the formats its solvers assume, for fuzzing them
+//! and timing them on more than the one real input per day.
+//!
+//! Each generator works out the answers to what it generates, either by construction or with a
+//! simple reference solution that only has to be right rather than fast. The same seed always
+//! generates the same input.
+
+use std::{cmp::Reverse, collections::BinaryHeap, fmt::Write as _, ops::RangeInclusive};
+
+/// A generated input, with the answer to each part where it's cheap to work out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Generated {
+    pub text: String,
+    pub answers: [Option<String>; 2],
+}
+
+impl Generated {
+    fn new(text: String, part1: impl ToString, part2: impl ToString) -> Self {
+        Self {
+            text,
+            answers: [Some(part1.to_string()), Some(part2.to_string())],
+        }
+    }
+}
+
+/// A SplitMix64 pseudo-random number generator: small, fast, and the same on every platform.
+#[derive(Clone, Debug)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub const fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// A number in `range`, which must not be empty
+    pub fn range(&mut self, range: RangeInclusive<i64>) -> i64 {
+        let (start, end) = range.into_inner();
+        let len = end.abs_diff(start) as u128 + 1;
+        start.wrapping_add(((self.next_u64() as u128 * len) >> 64) as i64)
+    }
+
+    /// An index into `len` items, of which there must be some
+    pub fn index(&mut self, len: usize) -> usize {
+        self.range(0..=len as i64 - 1) as usize
+    }
+
+    /// Whether something with a `percent`% chance happened
+    pub fn chance(&mut self, percent: u32) -> bool {
+        self.range(1..=100) <= percent as i64
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.index(items.len())]
+    }
+
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.index(i + 1));
+        }
+    }
+}
+
+/// A number with 1 to `max_digits` digits, with each length equally likely
+fn number(rng: &mut Rng, max_digits: u32) -> i64 {
+    let digits = rng.range(1..=max_digits as i64) as u32;
+    rng.range(10_i64.pow(digits - 1)..=10_i64.pow(digits) - 1)
+}
+
+/// Generates an input for a day from a source of randomness.
+pub type Generator = fn(&mut Rng) -> Generated;
+
+/// The generator for each day, in order of day.
+pub static GENERATORS: &[(u8, Generator)] = &[
+    (1, day1),
+    (2, day2),
+    (3, day3),
+    (4, day4),
+    (5, day5),
+    (6, day6),
+    (7, day7),
+    (8, day8),
+    (13, day13),
+    (14, day14),
+    (15, day15),
+    (16, day16),
+];
+
+/// Generates an input for `day` from `seed`, if the day has a generator.
+pub fn generate(day: u8, seed: u64) -> Option<Generated> {
+    GENERATORS
+        .iter()
+        .find(|&&(d, _)| d == day)
+        .map(|(_, generate)| generate(&mut Rng::new(seed)))
+}
+
+/// 1000 pairs of 5 digit numbers, with about a third of the right list copied from the left so
+/// the similarity score isn't 0
+pub fn day1(rng: &mut Rng) -> Generated {
+    let left: Vec<i64> = (0..1000).map(|_| rng.range(10_000..=99_999)).collect();
+    let right: Vec<i64> = (0..1000)
+        .map(|_| match rng.chance(30) {
+            true => *rng.pick(&left),
+            false => rng.range(10_000..=99_999),
+        })
+        .collect();
+
+    let mut text = String::new();
+    for (l, r) in left.iter().zip(&right) {
+        writeln!(text, "{l}   {r}").unwrap();
+    }
+
+    let (mut sorted_left, mut sorted_right) = (left.clone(), right.clone());
+    sorted_left.sort_unstable();
+    sorted_right.sort_unstable();
+    let distance: i64 = sorted_left
+        .iter()
+        .zip(&sorted_right)
+        .map(|(l, r)| (l - r).abs())
+        .sum();
+    let similarity: i64 = left
+        .iter()
+        .map(|l| l * right.iter().filter(|&r| r == l).count() as i64)
+        .sum();
+
+    Generated::new(text, distance, similarity)
+}
+
+/// 1000 reports of 5 to 8 levels under 100. A third are safe, half are safe but for one level,
+/// which the Problem Dampener may or may not fix, and the rest are random
+pub fn day2(rng: &mut Rng) -> Generated {
+    let safe = |levels: &[i64]| {
+        let dir = (levels[1] - levels[0]).signum();
+        levels
+            .windows(2)
+            .all(|pair| (1..=3).contains(&((pair[1] - pair[0]) * dir)))
+    };
+
+    let mut text = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    for _ in 0..1000 {
+        let len = rng.range(5..=8) as usize;
+        let steps: Vec<i64> = (1..len).map(|_| rng.range(1..=3)).collect();
+        let mut level = rng.range(1..=99 - steps.iter().sum::<i64>());
+        let mut levels = vec![level];
+        for step in steps {
+            level += step;
+            levels.push(level);
+        }
+        if rng.chance(50) {
+            levels.reverse();
+        }
+
+        match rng.range(0..=5) {
+            0 | 1 => (),
+            2..=4 => {
+                let index = rng.index(len);
+                levels[index] = rng.range(1..=99);
+            }
+            _ => levels
+                .iter_mut()
+                .for_each(|level| *level = rng.range(1..=99)),
+        }
+
+        part1 += safe(&levels) as i32;
+        part2 += (0..len).any(|skip| {
+            let mut levels = levels.clone();
+            levels.remove(skip);
+            safe(&levels)
+        }) as i32;
+
+        let levels: Vec<_> = levels.iter().map(i64::to_string).collect();
+        writeln!(text, "{}", levels.join(" ")).unwrap();
+    }
+
+    Generated::new(text, part1, part2)
+}
+
+/// 6 lines of about 3000 bytes of corrupted memory: `mul(X,Y)`, `do()` and `don't()` among
+/// noise, some of which looks like them. The noise has no `'` or `do(`, as [`crate::day3`] checks
+pub fn day3(rng: &mut Rng) -> Generated {
+    const NOISE: &[&str] = &[
+        "what()",
+        "who()",
+        "select(",
+        "from()",
+        "when()",
+        "how(",
+        "where()",
+        "why()",
+        "mul[3,7]",
+        "mul(32,64]",
+        "mul ( 2 , 4 )",
+        "mul(4*",
+        "mul(6,9!",
+        "mul(1234,5)",
+        "?(12,34)",
+        "[",
+        "]",
+        "{",
+        "}",
+        "<",
+        ">",
+        "!",
+        "@",
+        "#",
+        "$",
+        "%",
+        "^",
+        "&",
+        "*",
+        "+",
+        "-",
+        "~",
+        ",",
+        ":",
+        ";",
+        " ",
+        "/",
+    ];
+
+    let mut text = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    let mut enabled = true;
+    for _ in 0..6 {
+        let start = text.len();
+        while text.len() - start < 3000 {
+            match rng.range(1..=100) {
+                1..=30 => {
+                    let (x, y) = (number(rng, 3), number(rng, 3));
+                    write!(text, "mul({x},{y})").unwrap();
+                    part1 += x * y;
+                    part2 += x * y * enabled as i64;
+                }
+                31..=33 => {
+                    text.push_str("do()");
+                    enabled = true;
+                }
+                34..=36 => {
+                    text.push_str("don't()");
+                    enabled = false;
+                }
+                _ => text.push_str(NOISE[rng.index(NOISE.len())]),
+            }
+        }
+        text.push('\n');
+    }
+
+    Generated::new(text, part1, part2)
+}
+
+/// A 140 by 140 word search of random `XMAS` letters
+pub fn day4(rng: &mut Rng) -> Generated {
+    const DIM: i64 = 140;
+    let grid: Vec<Vec<u8>> = (0..DIM)
+        .map(|_| (0..DIM).map(|_| *rng.pick(b"XMAS")).collect())
+        .collect();
+    let at = |x: i64, y: i64| {
+        grid.get(usize::try_from(y).ok()?)?
+            .get(usize::try_from(x).ok()?)
+            .copied()
+    };
+
+    let (mut xmas, mut x_mas) = (0, 0);
+    for y in 0..DIM {
+        for x in 0..DIM {
+            for (dx, dy) in [
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ] {
+                xmas +=
+                    (0..4).all(|i| at(x + dx * i, y + dy * i) == Some(b"XMAS"[i as usize])) as u32;
+            }
+
+            let mas = |a, b| matches!((a, b), (Some(b'M'), Some(b'S')) | (Some(b'S'), Some(b'M')));
+            x_mas += (at(x, y) == Some(b'A')
+                && mas(at(x - 1, y - 1), at(x + 1, y + 1))
+                && mas(at(x + 1, y - 1), at(x - 1, y + 1))) as u32;
+        }
+    }
+
+    let mut text = String::new();
+    for row in &grid {
+        text.push_str(std::str::from_utf8(row).unwrap());
+        text.push('\n');
+    }
+
+    Generated::new(text, xmas, x_mas)
+}
+
+/// A rule for every pair of 49 two digit pages, then 200 updates of 5 to 23 of them, about half
+/// of which are in order
+pub fn day5(rng: &mut Rng) -> Generated {
+    let mut pages: Vec<i64> = (10..=99).collect();
+    rng.shuffle(&mut pages);
+    pages.truncate(49);
+
+    // With a rule for every pair, every update has exactly one correct order
+    let mut rules = Vec::new();
+    for (i, before) in pages.iter().enumerate() {
+        for after in &pages[i + 1..] {
+            rules.push((before, after));
+        }
+    }
+    rng.shuffle(&mut rules);
+
+    let mut text = String::new();
+    for (before, after) in rules {
+        writeln!(text, "{before}|{after}").unwrap();
+    }
+    text.push('\n');
+
+    let rank = |page| pages.iter().position(|&p| p == page);
+    let (mut part1, mut part2) = (0, 0);
+    for _ in 0..200 {
+        let len = rng.range(2..=11) as usize * 2 + 1;
+        let mut update = pages.clone();
+        rng.shuffle(&mut update);
+        update.truncate(len);
+        let mut sorted = update.clone();
+        sorted.sort_by_key(|&page| rank(page));
+        if rng.chance(50) {
+            update.clone_from(&sorted);
+        }
+
+        match update == sorted {
+            true => part1 += sorted[len / 2],
+            false => part2 += sorted[len / 2],
+        }
+        let update: Vec<_> = update.iter().map(i64::to_string).collect();
+        writeln!(text, "{}", update.join(",")).unwrap();
+    }
+
+    Generated::new(text, part1, part2)
+}
+
+/// Up, right, down and left, in the order the guard turns through them
+const GUARD_DIRS: [(i64, i64); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+/// Walks the guard through `grid` from `x`, `y` facing `GUARD_DIRS[dir]`, calling `step` with
+/// where they are and which way they face before each step forward. Returns whether they leave the
+/// map rather than walking in a loop
+fn patrol(
+    grid: &[u8],
+    dim: i64,
+    (mut x, mut y, mut dir): (i64, i64, usize),
+    mut step: impl FnMut(i64, i64, usize),
+) -> bool {
+    let mut seen = vec![0_u8; grid.len()];
+    loop {
+        let cell = &mut seen[(y * dim + x) as usize];
+        if *cell & 1 << dir != 0 {
+            return false;
+        }
+        *cell |= 1 << dir;
+
+        let (next_x, next_y) = (x + GUARD_DIRS[dir].0, y + GUARD_DIRS[dir].1);
+        if !(0..dim).contains(&next_x) || !(0..dim).contains(&next_y) {
+            return true;
+        }
+        if grid[(next_y * dim + next_x) as usize] == b'#' {
+            dir = (dir + 1) % 4;
+        } else {
+            step(x, y, dir);
+            (x, y) = (next_x, next_y);
+        }
+    }
+}
+
+/// A 130 by 130 map the guard patrols for thousands of steps before walking off it. On a random
+/// map they walk off after a few hundred, so the map is built around an outward spiral: they're
+/// turned by an obstruction each time they get 2 to 5 positions past where they've already
+/// walked, until they reach the edge. Then about 1 in 25 of the positions they never walk through
+/// are obstructed too, which doesn't change their patrol
+pub fn day6(rng: &mut Rng) -> Generated {
+    const DIM: i64 = 130;
+    loop {
+        let mut grid = vec![b'.'; (DIM * DIM) as usize];
+        let mut walked = vec![false; grid.len()];
+        let start = (rng.range(50..=79), rng.range(50..=79), 0);
+        let (mut x, mut y, mut dir) = start;
+        // The bounds of where they've walked before the current side of the spiral
+        let (mut min_x, mut max_x, mut min_y, mut max_y) = (x, x, y, y);
+        let mut gap = rng.range(2..=5);
+        loop {
+            walked[(y * DIM + x) as usize] = true;
+            let (next_x, next_y) = (x + GUARD_DIRS[dir].0, y + GUARD_DIRS[dir].1);
+            if !(0..DIM).contains(&next_x) || !(0..DIM).contains(&next_y) {
+                break;
+            }
+
+            let past = [
+                min_y - next_y,
+                next_x - max_x,
+                next_y - max_y,
+                min_x - next_x,
+            ][dir];
+            if past > gap {
+                grid[(next_y * DIM + next_x) as usize] = b'#';
+                dir = (dir + 1) % 4;
+                (min_x, max_x, min_y, max_y) =
+                    (min_x.min(x), max_x.max(x), min_y.min(y), max_y.max(y));
+                gap = rng.range(2..=5);
+            } else {
+                (x, y) = (next_x, next_y);
+            }
+        }
+        for (cell, &walked) in grid.iter_mut().zip(&walked) {
+            if !walked && *cell == b'.' && rng.chance(4) {
+                *cell = b'#';
+            }
+        }
+        let guard = (start.1 * DIM + start.0) as usize;
+        grid[guard] = b'^';
+
+        // Where the guard was just before first stepping into each position
+        let mut entered = vec![None; grid.len()];
+        let leaves = patrol(&grid, DIM, start, |x, y, dir| {
+            let next = (y + GUARD_DIRS[dir].1) * DIM + x + GUARD_DIRS[dir].0;
+            entered[next as usize].get_or_insert((x, y, dir));
+        });
+        if !leaves {
+            continue;
+        }
+
+        entered[guard] = None;
+        let visited = entered.iter().flatten().count() + 1;
+        // An obstruction only changes the guard's path from where they would first have walked into
+        // it, so start there
+        let mut loops = 0;
+        for (pos, from) in entered.iter().enumerate() {
+            if let Some(&from) = from.as_ref() {
+                grid[pos] = b'#';
+                loops += !patrol(&grid, DIM, from, |_, _, _| ()) as u32;
+                grid[pos] = b'.';
+            }
+        }
+
+        let mut text = String::new();
+        for row in grid.chunks(DIM as usize) {
+            text.push_str(std::str::from_utf8(row).unwrap());
+            text.push('\n');
+        }
+        return Generated::new(text, visited, loops);
+    }
+}
+
+/// Whether some choice of operators makes `nums` evaluate to `target` from left to right, starting
+/// from `value`. The operators never make the value smaller, so it stops once it's too big
+fn calibrates(target: u64, value: u64, nums: &[u64], concat: bool) -> bool {
+    match nums.split_first() {
+        _ if value > target => false,
+        None => value == target,
+        Some((&num, rest)) => [
+            value.checked_add(num),
+            value.checked_mul(num),
+            concat.then(|| join(value, num)).flatten(),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|value| calibrates(target, value, rest, concat)),
+    }
+}
+
+/// `value` with the digits of `num` on the end
+fn join(value: u64, num: u64) -> Option<u64> {
+    value
+        .checked_mul(10_u64.pow(num.ilog10() + 1))?
+        .checked_add(num)
+}
+
+/// 850 equations of 3 to 12 numbers, mostly of 1 or 2 digits, with targets under 10^15. Most
+/// targets are made with random operators, half of those including `||`, and the rest are one to
+/// nine more than that
+pub fn day7(rng: &mut Rng) -> Generated {
+    let mut text = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    for _ in 0..850 {
+        let (target, nums) = loop {
+            let len = rng.range(3..=12);
+            let nums: Vec<u64> = (0..len)
+                .map(|_| {
+                    let digits = if rng.chance(10) { 3 } else { 2 };
+                    number(rng, digits) as u64
+                })
+                .collect();
+            let ops = if rng.chance(50) { 2 } else { 1 };
+            let value =
+                nums[1..]
+                    .iter()
+                    .try_fold(nums[0], |value, &num| match rng.range(0..=ops) {
+                        0 => value.checked_add(num),
+                        1 => value.checked_mul(num),
+                        _ => join(value, num),
+                    });
+
+            match value {
+                Some(value) if value < 10_u64.pow(15) => {
+                    let off = if rng.chance(25) { rng.range(1..=9) } else { 0 };
+                    break (value + off as u64, nums);
+                }
+                _ => continue,
+            }
+        };
+
+        if calibrates(target, nums[0], &nums[1..], false) {
+            part1 += target;
+            part2 += target;
+        } else if calibrates(target, nums[0], &nums[1..], true) {
+            part2 += target;
+        }
+
+        let nums: Vec<_> = nums.iter().map(u64::to_string).collect();
+        writeln!(text, "{target}: {}", nums.join(" ")).unwrap();
+    }
+
+    Generated::new(text, part1, part2)
+}
+
+/// A 50 by 50 map with 3 or 4 antennae of each of 30 to 45 frequencies
+pub fn day8(rng: &mut Rng) -> Generated {
+    const DIM: i64 = 50;
+    let mut grid = vec![b'.'; (DIM * DIM) as usize];
+    let mut freqs: Vec<u8> = (b'0'..=b'9')
+        .chain(b'A'..=b'Z')
+        .chain(b'a'..=b'z')
+        .collect();
+    rng.shuffle(&mut freqs);
+    freqs.truncate(rng.range(30..=45) as usize);
+
+    let mut antinodes = [vec![false; grid.len()], vec![false; grid.len()]];
+    for freq in freqs {
+        let mut antennae: Vec<(i64, i64)> = Vec::new();
+        for _ in 0..rng.range(3..=4) {
+            let (x, y) = loop {
+                let (x, y) = (rng.range(0..=DIM - 1), rng.range(0..=DIM - 1));
+                if grid[(y * DIM + x) as usize] == b'.' {
+                    break (x, y);
+                }
+            };
+            grid[(y * DIM + x) as usize] = freq;
+            antennae.push((x, y));
+        }
+
+        for (i, &(ax, ay)) in antennae.iter().enumerate() {
+            for &(bx, by) in &antennae[i + 1..] {
+                let (dx, dy) = (bx - ax, by - ay);
+                // Every multiple of the distance between them away from the first, and for part
+                // 1 only those twice as far from one as the other
+                for (mut x, mut y, dx, dy) in [(ax, ay, -dx, -dy), (bx, by, dx, dy)] {
+                    for step in 0.. {
+                        if !(0..DIM).contains(&x) || !(0..DIM).contains(&y) {
+                            break;
+                        }
+                        antinodes[0][(y * DIM + x) as usize] |= step == 1;
+                        antinodes[1][(y * DIM + x) as usize] = true;
+                        (x, y) = (x + dx, y + dy);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut text = String::new();
+    for row in grid.chunks(DIM as usize) {
+        text.push_str(std::str::from_utf8(row).unwrap());
+        text.push('\n');
+    }
+    let [part1, part2] = antinodes.map(|antinodes| antinodes.iter().filter(|&&a| a).count());
+
+    Generated::new(text, part1, part2)
+}
+
+/// How many times to press A and B to move the claw to the prize, if that's a whole number of
+/// times, however many or negative it is
+fn presses([ax, ay, bx, by, px, py]: [i64; 6]) -> Option<(i64, i64)> {
+    let det = ax * by - ay * bx;
+    let (a, b) = (px * by - py * bx, ax * py - ay * px);
+    (a % det == 0 && b % det == 0).then_some((a / det, b / det))
+}
+
+/// 320 claw machines with buttons that move the claw 10 to 99 along each axis. About 40% have a
+/// prize that can be won with up to 100 presses of each button, and the rest a random prize
+/// that usually can't. Machines that could be won with a negative number of presses, or more than
+/// 100 in part 1, aren't generated
+pub fn day13(rng: &mut Rng) -> Generated {
+    const OFFSET: i64 = 10_000_000_000_000;
+    let mut text = String::new();
+    let (mut part1, mut part2) = (0, 0);
+    for machine in 0..320 {
+        let [ax, ay, bx, by, px, py] = loop {
+            let [ax, ay, bx, by] = [(); 4].map(|_| rng.range(10..=99));
+            let (px, py) = if rng.chance(40) {
+                let (a, b) = (rng.range(0..=100), rng.range(0..=100));
+                (a * ax + b * bx, a * ay + b * by)
+            } else {
+                (rng.range(100..=19_999), rng.range(100..=19_999))
+            };
+
+            let prizes = 100..=99_999;
+            if ax * by == ay * bx || !prizes.contains(&px) || !prizes.contains(&py) {
+                continue;
+            }
+            let near = presses([ax, ay, bx, by, px, py]);
+            let far = presses([ax, ay, bx, by, px + OFFSET, py + OFFSET]);
+            if near.is_some_and(|(a, b)| !(0..=100).contains(&a) || !(0..=100).contains(&b))
+                || far.is_some_and(|(a, b)| a < 0 || b < 0)
+            {
+                continue;
+            }
+            break [ax, ay, bx, by, px, py];
+        };
+
+        let cost = |presses: Option<(i64, i64)>| presses.map_or(0, |(a, b)| a * 3 + b);
+        part1 += cost(presses([ax, ay, bx, by, px, py]));
+        part2 += cost(presses([ax, ay, bx, by, px + OFFSET, py + OFFSET]));
+
+        if machine > 0 {
+            text.push('\n');
+        }
+        writeln!(text, "Button A: X+{ax}, Y+{ay}").unwrap();
+        writeln!(text, "Button B: X+{bx}, Y+{by}").unwrap();
+        writeln!(text, "Prize: X={px}, Y={py}").unwrap();
+    }
+
+    Generated::new(text, part1, part2)
+}
+
+/// 500 robots in a 101 by 103 room, moving up to 99 tiles a second. At a random second they draw
+/// a picture in a 31 by 33 frame, as [`crate::day14`] looks for, so that second is part 2's answer
+pub fn day14(rng: &mut Rng) -> Generated {
+    const WIDTH: i64 = 101;
+    const HEIGHT: i64 = 103;
+    let seconds = rng.range(0..=WIDTH * HEIGHT - 1);
+
+    let (left, top) = (rng.range(0..=WIDTH - 31), rng.range(0..=HEIGHT - 33));
+    let mut picture: Vec<(i64, i64)> = (0..33)
+        .flat_map(|y| [(left, top + y), (left + 30, top + y)])
+        .chain((1..30).flat_map(|x| [(left + x, top), (left + x, top + 32)]))
+        .collect();
+    for _ in 0..40 {
+        picture.push((left + rng.range(1..=29), top + rng.range(1..=31)));
+    }
+    while picture.len() < 500 {
+        picture.push((rng.range(0..=WIDTH - 1), rng.range(0..=HEIGHT - 1)));
+    }
+    rng.shuffle(&mut picture);
+
+    let mut text = String::new();
+    let mut quadrants = [0; 4];
+    for (x, y) in picture {
+        let (dx, dy) = (rng.range(-99..=99), rng.range(-99..=99));
+        let (x, y) = (
+            (x - dx * seconds).rem_euclid(WIDTH),
+            (y - dy * seconds).rem_euclid(HEIGHT),
+        );
+        writeln!(text, "p={x},{y} v={dx},{dy}").unwrap();
+
+        let (x, y) = (
+            (x + dx * 100).rem_euclid(WIDTH),
+            (y + dy * 100).rem_euclid(HEIGHT),
+        );
+        if x != WIDTH / 2 && y != HEIGHT / 2 {
+            quadrants[(x > WIDTH / 2) as usize + 2 * (y > HEIGHT / 2) as usize] += 1;
+        }
+    }
+
+    Generated::new(text, quadrants.iter().product::<i32>(), seconds)
+}
+
+/// Moves the robot around `grid` as `moves` tell it to, returning the sum of the boxes' GPS
+/// coordinates after. Boxes are `O`, or `[]` when they're two tiles wide
+fn warehouse(mut grid: Vec<Vec<u8>>, moves: &str) -> usize {
+    let mut robot = grid
+        .iter()
+        .enumerate()
+        .find_map(|(y, row)| Some((row.iter().position(|&c| c == b'@')?, y)))
+        .unwrap();
+
+    for c in moves.bytes() {
+        let (dx, dy): (isize, isize) = match c {
+            b'<' => (-1, 0),
+            b'>' => (1, 0),
+            b'^' => (0, -1),
+            b'v' => (0, 1),
+            _ => continue,
+        };
+
+        // The robot and every tile of every box it would push
+        let mut pushed = vec![robot];
+        let mut next = 0;
+        let blocked = loop {
+            let Some(&(x, y)) = pushed.get(next) else {
+                break false;
+            };
+            next += 1;
+
+            let (x, y) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+            let tiles = match grid[y][x] {
+                b'#' => break true,
+                b'O' => [Some(x), None],
+                b'[' => [Some(x), Some(x + 1)],
+                b']' => [Some(x), Some(x - 1)],
+                _ => [None, None],
+            };
+            for x in tiles.into_iter().flatten() {
+                if !pushed.contains(&(x, y)) {
+                    pushed.push((x, y));
+                }
+            }
+        };
+        if blocked {
+            continue;
+        }
+
+        // Move the furthest first, so nothing is overwritten
+        pushed.sort_by_key(|&(x, y)| Reverse(x as isize * dx + y as isize * dy));
+        for (x, y) in pushed {
+            let (to_x, to_y) = (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy));
+            grid[to_y][to_x] = grid[y][x];
+            grid[y][x] = b'.';
+        }
+        robot = (
+            robot.0.wrapping_add_signed(dx),
+            robot.1.wrapping_add_signed(dy),
+        );
+    }
+
+    grid.iter()
+        .enumerate()
+        .flat_map(|(y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|&(_, &c)| matches!(c, b'O' | b'['))
+                .map(move |(x, _)| 100 * y + x)
+        })
+        .sum()
+}
+
+/// A 50 by 50 warehouse with the robot in the middle, with about a third of the floor walls or
+/// boxes, then 20 lines of 1000 moves
+pub fn day15(rng: &mut Rng) -> Generated {
+    const DIM: usize = 50;
+    let mut grid: Vec<Vec<u8>> = (0..DIM)
+        .map(|y| {
+            (0..DIM)
+                .map(|x| match rng.range(1..=100) {
+                    _ if x == 0 || y == 0 || x == DIM - 1 || y == DIM - 1 => b'#',
+                    1..=8 => b'#',
+                    9..=34 => b'O',
+                    _ => b'.',
+                })
+                .collect()
+        })
+        .collect();
+    grid[DIM / 2][DIM / 2] = b'@';
+
+    let mut moves = String::new();
+    for _ in 0..20 {
+        for _ in 0..1000 {
+            moves.push(*rng.pick(&['<', '>', '^', 'v']));
+        }
+        moves.push('\n');
+    }
+
+    let mut text = String::new();
+    for row in &grid {
+        text.push_str(std::str::from_utf8(row).unwrap());
+        text.push('\n');
+    }
+    text.push('\n');
+    text.push_str(&moves);
+
+    let wide = grid
+        .iter()
+        .map(|row| {
+            row.iter()
+                .flat_map(|&c| match c {
+                    b'O' => *b"[]",
+                    b'@' => *b"@.",
+                    c => [c, c],
+                })
+                .collect()
+        })
+        .collect();
+    Generated::new(text, warehouse(grid, &moves), warehouse(wide, &moves))
+}
+
+/// The lowest score from the start of `maze` to its end, and the number of tiles on a path with
+/// that score
+fn reindeer(maze: &[Vec<u8>]) -> (u32, usize) {
+    // East first, as the reindeer starts facing east, then clockwise
+    const DIRS: [(isize, isize); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let dim = maze.len();
+    let index = |x: usize, y: usize, dir: usize| (y * dim + x) * 4 + dir;
+
+    // The lowest score to each position and direction from any of `starts`, stepping forwards
+    // through the maze, or backwards with a `sign` of -1
+    let scores = |starts: &[(usize, usize, usize)], sign: isize| {
+        let mut scores = vec![u32::MAX; dim * dim * 4];
+        let mut heap = BinaryHeap::new();
+        for &(x, y, dir) in starts {
+            scores[index(x, y, dir)] = 0;
+            heap.push(Reverse((0, x, y, dir)));
+        }
+
+        while let Some(Reverse((score, x, y, dir))) = heap.pop() {
+            if score > scores[index(x, y, dir)] {
+                continue;
+            }
+
+            let (dx, dy) = DIRS[dir];
+            let (next_x, next_y) = (
+                x.wrapping_add_signed(dx * sign),
+                y.wrapping_add_signed(dy * sign),
+            );
+            let mut next = vec![
+                (score + 1000, x, y, (dir + 1) % 4),
+                (score + 1000, x, y, (dir + 3) % 4),
+            ];
+            if maze[next_y][next_x] != b'#' {
+                next.push((score + 1, next_x, next_y, dir));
+            }
+            for (score, x, y, dir) in next {
+                if score < scores[index(x, y, dir)] {
+                    scores[index(x, y, dir)] = score;
+                    heap.push(Reverse((score, x, y, dir)));
+                }
+            }
+        }
+        scores
+    };
+
+    let (end_x, end_y) = (dim - 2, 1);
+    let from_start = scores(&[(1, dim - 2, 0)], 1);
+    let to_end = scores(&[0, 1, 2, 3].map(|dir| (end_x, end_y, dir)), -1);
+
+    let best = (0..4)
+        .map(|dir| from_start[index(end_x, end_y, dir)])
+        .min()
+        .unwrap();
+    let tiles = (0..dim * dim)
+        .filter(|&tile| {
+            (tile * 4..tile * 4 + 4)
+                .any(|state| from_start[state].checked_add(to_end[state]) == Some(best))
+        })
+        .count();
+    (best, tiles)
+}
+
+/// A 141 by 141 maze of corridors carved by a depth first search, with about 280 walls knocked
+/// through so there's more than one way from the start to the end
+pub fn day16(rng: &mut Rng) -> Generated {
+    const DIM: usize = 141;
+    let mut maze = vec![vec![b'#'; DIM]; DIM];
+
+    // Corridors join the tiles with odd coordinates
+    maze[DIM - 2][1] = b'.';
+    let mut stack: Vec<(usize, usize)> = vec![(1, DIM - 2)];
+    while let Some(&(x, y)) = stack.last() {
+        let options: Vec<_> = [(0, -2), (2, 0), (0, 2), (-2, 0)]
+            .into_iter()
+            .map(|(dx, dy)| (x.wrapping_add_signed(dx), y.wrapping_add_signed(dy)))
+            .filter(|&(x, y)| x < DIM - 1 && y < DIM - 1 && maze[y][x] == b'#')
+            .collect();
+        if options.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let (next_x, next_y) = *rng.pick(&options);
+        maze[(y + next_y) / 2][(x + next_x) / 2] = b'.';
+        maze[next_y][next_x] = b'.';
+        stack.push((next_x, next_y));
+    }
+
+    // A wall with one odd and one even coordinate is between two corridors
+    for _ in 0..DIM * 2 {
+        let (x, y) = (rng.index(DIM - 2) + 1, rng.index(DIM - 2) + 1);
+        if (x + y) % 2 == 1 {
+            maze[y][x] = b'.';
+        }
+    }
+    maze[DIM - 2][1] = b'S';
+    maze[1][DIM - 2] = b'E';
+
+    let mut text = String::new();
+    for row in &maze {
+        text.push_str(std::str::from_utf8(row).unwrap());
+        text.push('\n');
+    }
+    let (score, tiles) = reindeer(&maze);
+
+    Generated::new(text, score, tiles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seeded() {
+        assert_eq!(generate(7, 1), generate(7, 1));
+        assert_ne!(generate(7, 1), generate(7, 2));
+        assert_eq!(generate(9, 1), None);
+
+        let mut rng = Rng::new(0);
+        for _ in 0..1000 {
+            assert!((-3..=3).contains(&rng.range(-3..=3)));
+        }
+        assert!((0..100).map(|_| rng.range(0..=1)).any(|n| n == 1));
+    }
+
+    /// Runs each generated input through the solvers, checking they accept it and agree with the
+    /// answers generated with it
+    #[test]
+    fn answers() {
+        for &(day, generate) in GENERATORS {
+            for seed in 0..2 {
+                let generated = generate(&mut Rng::new(seed));
+                for (part, answer) in (1..).zip(&generated.answers) {
+                    let solution = crate::solution(day, part).unwrap();
+                    if let Err(err) = solution.check(&generated.text) {
+                        panic!("day {day} seed {seed}: {err}");
+                    }
+                    if let Some(answer) = answer {
+                        assert_eq!(
+                            (solution.solve)(&generated.text),
+                            *answer,
+                            "day {day} part {part} seed {seed}"
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,426 @@
+//! A local record of every answer submitted, kept in `input/<year>/journal.toml`, so answers
+//! already ruled out aren't submitted again.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs::{self, OpenOptions},
+    io::{self, Write as _},
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::client::{Client, ClientError, Feedback, Verdict};
+
+/// One submitted answer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Attempt {
+    pub day: u8,
+    pub part: u8,
+    pub answer: String,
+    pub verdict: Verdict,
+    /// When it was submitted, in seconds since the Unix epoch
+    pub at: u64,
+    /// When the server will take another answer, in seconds since the Unix epoch, if it said
+    pub until: Option<u64>,
+}
+
+/// Why an answer wasn't submitted.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Refusal {
+    /// The part is already solved, with this answer
+    Solved(String),
+    /// The answer was already submitted, and was wrong
+    KnownWrong(Verdict),
+    /// The answer is at least this answer, which was too high
+    TooHigh(String),
+    /// The answer is at most this answer, which was too low
+    TooLow(String),
+    /// The server won't take another answer for this long
+    Wait(Duration),
+}
+
+impl Display for Refusal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Solved(answer) => write!(f, "already solved, with {answer}"),
+            Self::KnownWrong(verdict) => write!(f, "already submitted, and was {verdict}"),
+            Self::TooHigh(answer) => write!(f, "{answer} was already too high"),
+            Self::TooLow(answer) => write!(f, "{answer} was already too low"),
+            Self::Wait(wait) => write!(f, "the server won't take another answer for {wait:?}"),
+        }
+    }
+}
+
+/// The journal couldn't be read or written, the server couldn't be asked, or the answer was
+/// refused without asking it.
+#[derive(Debug)]
+pub enum JournalError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    /// The `index`th attempt doesn't have `key`, or it isn't what the journal's layout has there
+    Invalid {
+        index: usize,
+        key: &'static str,
+    },
+    Client(ClientError),
+    Refused(Refusal),
+}
+
+impl From<io::Error> for JournalError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for JournalError {
+    fn from(err: toml::de::Error) -> Self {
+        Self::Toml(err)
+    }
+}
+
+impl From<ClientError> for JournalError {
+    fn from(err: ClientError) -> Self {
+        Self::Client(err)
+    }
+}
+
+impl Display for JournalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Toml(err) => write!(f, "{err}"),
+            Self::Invalid { index, key } => {
+                write!(f, "attempt {index} has a missing or invalid `{key}`")
+            }
+            Self::Client(err) => write!(f, "{err}"),
+            Self::Refused(refusal) => write!(f, "not submitted: {refusal}"),
+        }
+    }
+}
+
+impl Error for JournalError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Toml(err) => Some(err),
+            Self::Client(err) => Some(err),
+            Self::Invalid { .. } | Self::Refused(_) => None,
+        }
+    }
+}
+
+/// Every answer submitted for one year, oldest first. The file has an `[[attempt]]` table for
+/// each, so new attempts are appended without rewriting it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Journal(Vec<Attempt>);
+
+impl Journal {
+    pub fn parse(text: &str) -> Result<Self, JournalError> {
+        let mut table = text.parse::<toml::Table>()?;
+        let Some(attempts) = table.remove("attempt") else {
+            return Ok(Self::default());
+        };
+        let toml::Value::Array(attempts) = attempts else {
+            return Err(JournalError::Invalid {
+                index: 0,
+                key: "attempt",
+            });
+        };
+
+        attempts
+            .iter()
+            .enumerate()
+            .map(|(index, attempt)| {
+                let invalid = |key| JournalError::Invalid { index, key };
+                let get = |key| attempt.get(key).ok_or_else(|| invalid(key));
+                let int = |key| -> Result<u64, JournalError> {
+                    get(key)?
+                        .as_integer()
+                        .and_then(|n| n.try_into().ok())
+                        .ok_or_else(|| invalid(key))
+                };
+                let str = |key| get(key)?.as_str().ok_or_else(|| invalid(key));
+
+                Ok(Attempt {
+                    day: int("day")?.try_into().map_err(|_| invalid("day"))?,
+                    part: int("part")?.try_into().map_err(|_| invalid("part"))?,
+                    answer: str("answer")?.to_owned(),
+                    verdict: str("verdict")?.parse().map_err(|()| invalid("verdict"))?,
+                    at: int("at")?,
+                    until: attempt.get("until").map(|_| int("until")).transpose()?,
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+
+    /// Reads the journal at `path`, which is empty if there's no file.
+    pub fn load(path: &Path) -> Result<Self, JournalError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn attempts(&self) -> &[Attempt] {
+        &self.0
+    }
+
+    /// Checks whether `answer` for part `part` of `day` is worth submitting at time `now`, given
+    /// the earlier attempts at that part.
+    pub fn check(&self, day: u8, part: u8, answer: &str, now: u64) -> Result<(), Refusal> {
+        let number = |answer: &str| answer.parse::<i128>().ok();
+        let mut too_high: Option<&str> = None;
+        let mut too_low: Option<&str> = None;
+
+        for attempt in self.0.iter().filter(|a| (a.day, a.part) == (day, part)) {
+            match attempt.verdict {
+                Verdict::Right => return Err(Refusal::Solved(attempt.answer.clone())),
+                verdict if verdict.is_wrong() && attempt.answer == answer => {
+                    return Err(Refusal::KnownWrong(verdict));
+                }
+                Verdict::TooHigh => {
+                    if too_high.is_none_or(|high| number(&attempt.answer) < number(high)) {
+                        too_high = Some(&attempt.answer);
+                    }
+                }
+                Verdict::TooLow => {
+                    if too_low.is_none_or(|low| number(&attempt.answer) > number(low)) {
+                        too_low = Some(&attempt.answer);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(answer) = number(answer) {
+            if let Some(high) = too_high.filter(|&high| number(high).is_some_and(|h| h <= answer)) {
+                return Err(Refusal::TooHigh(high.to_owned()));
+            }
+            if let Some(low) = too_low.filter(|&low| number(low).is_some_and(|l| l >= answer)) {
+                return Err(Refusal::TooLow(low.to_owned()));
+            }
+        }
+
+        let until = self
+            .0
+            .iter()
+            .filter(|a| (a.day, a.part) == (day, part))
+            .filter_map(|a| a.until)
+            .max();
+        match until {
+            Some(until) if until > now => Err(Refusal::Wait(Duration::from_secs(until - now))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Adds `attempt`, appending it to the file at `path`.
+    pub fn record(&mut self, path: &Path, attempt: Attempt) -> io::Result<()> {
+        let mut text = format!(
+            "[[attempt]]\nday = {}\npart = {}\nanswer = {}\nverdict = \"{}\"\nat = {}\n",
+            attempt.day,
+            attempt.part,
+            toml::Value::String(attempt.answer.clone()),
+            attempt.verdict,
+            attempt.at
+        );
+        if let Some(until) = attempt.until {
+            text += &format!("until = {until}\n");
+        }
+        text += "\n";
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?
+            .write_all(text.as_bytes())?;
+        self.0.push(attempt);
+        Ok(())
+    }
+}
+
+/// Submits `answer` for part `part` of `day` of `year`, unless the journal at `path` rules it
+/// out, then records the server's feedback there.
+pub fn submit(
+    client: &Client,
+    path: &Path,
+    year: u16,
+    day: u8,
+    part: u8,
+    answer: &str,
+) -> Result<Feedback, JournalError> {
+    let mut journal = Journal::load(path)?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_secs());
+    journal
+        .check(day, part, answer, now)
+        .map_err(JournalError::Refused)?;
+
+    let feedback = client.submit(year, day, part, answer)?;
+    journal.record(
+        path,
+        Attempt {
+            day,
+            part,
+            answer: answer.to_owned(),
+            verdict: feedback.verdict,
+            at: now,
+            until: feedback.wait.map(|wait| now + wait.as_secs()),
+        },
+    )?;
+    Ok(feedback)
+}
+
+#[cfg(test)]
+mod tests {
+    use indoc::indoc;
+
+    use super::*;
+    use crate::client::tests::serve;
+
+    fn attempt(part: u8, answer: &str, verdict: Verdict) -> Attempt {
+        Attempt {
+            day: 1,
+            part,
+            answer: answer.to_owned(),
+            verdict,
+            at: 100,
+            until: None,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let journal = Journal::parse(indoc! {r#"
+            [[attempt]]
+            day = 1
+            part = 1
+            answer = "12"
+            verdict = "too low"
+            at = 100
+            until = 160
+
+            [[attempt]]
+            day = 1
+            part = 1
+            answer = "34"
+            verdict = "right"
+            at = 200
+        "#})
+        .unwrap();
+        assert_eq!(
+            journal.attempts(),
+            [
+                Attempt {
+                    until: Some(160),
+                    ..attempt(1, "12", Verdict::TooLow)
+                },
+                Attempt {
+                    at: 200,
+                    ..attempt(1, "34", Verdict::Right)
+                }
+            ]
+        );
+
+        assert_eq!(Journal::parse("").unwrap(), Journal::default());
+        assert!(matches!(
+            Journal::parse(
+                "[[attempt]]\nday = 1\npart = 1\nanswer = \"1\"\nverdict = \"close\"\nat = 0\n"
+            ),
+            Err(JournalError::Invalid {
+                index: 0,
+                key: "verdict"
+            })
+        ));
+    }
+
+    #[test]
+    fn check() {
+        let journal = Journal(vec![
+            attempt(1, "100", Verdict::TooHigh),
+            attempt(1, "80", Verdict::TooHigh),
+            attempt(1, "20", Verdict::TooLow),
+            attempt(1, "50", Verdict::Wrong),
+            attempt(2, "7", Verdict::Right),
+        ]);
+        assert_eq!(journal.check(1, 1, "60", 200), Ok(()));
+        assert_eq!(
+            journal.check(1, 1, "50", 200),
+            Err(Refusal::KnownWrong(Verdict::Wrong))
+        );
+        assert_eq!(
+            journal.check(1, 1, "90", 200),
+            Err(Refusal::TooHigh("80".to_owned()))
+        );
+        assert_eq!(
+            journal.check(1, 1, "20", 200),
+            Err(Refusal::KnownWrong(Verdict::TooLow))
+        );
+        assert_eq!(
+            journal.check(1, 1, "3", 200),
+            Err(Refusal::TooLow("20".to_owned()))
+        );
+        assert_eq!(journal.check(1, 1, "abc", 200), Ok(()));
+        assert_eq!(
+            journal.check(1, 2, "8", 200),
+            Err(Refusal::Solved("7".to_owned()))
+        );
+        assert_eq!(journal.check(2, 1, "90", 200), Ok(()));
+
+        let journal = Journal(vec![Attempt {
+            until: Some(160),
+            ..attempt(1, "12", Verdict::TooRecent)
+        }]);
+        assert_eq!(
+            journal.check(1, 1, "12", 130),
+            Err(Refusal::Wait(Duration::from_secs(30)))
+        );
+        assert_eq!(journal.check(1, 1, "12", 160), Ok(()));
+    }
+
+    #[test]
+    fn submits_and_records() {
+        let path = std::env::temp_dir().join(format!("aoc-journal-{}.toml", std::process::id()));
+        let _ = fs::remove_file(&path);
+        let (base_url, server) = serve(vec![(
+            200,
+            "<p>That's not the right answer; your answer is too high. Please wait one minute \
+             before trying again.</p>",
+        )]);
+        let client = Client::new(&base_url, "abc");
+
+        let feedback = submit(&client, &path, 2024, 1, 2, "1000").unwrap();
+        assert_eq!(
+            feedback,
+            Feedback {
+                verdict: Verdict::TooHigh,
+                wait: Some(Duration::from_secs(60))
+            }
+        );
+        assert!(matches!(
+            submit(&client, &path, 2024, 1, 2, "2000"),
+            Err(JournalError::Refused(Refusal::TooHigh(answer))) if answer == "1000"
+        ));
+
+        let journal = Journal::load(&path).unwrap();
+        let [attempt] = journal.attempts() else {
+            panic!("expected one attempt, found {journal:?}");
+        };
+        assert_eq!(
+            (attempt.part, attempt.answer.as_str(), attempt.verdict),
+            (2, "1000", Verdict::TooHigh)
+        );
+        assert_eq!(attempt.until, Some(attempt.at + 60));
+
+        let requests = server.join().unwrap();
+        assert!(requests[0].starts_with("POST /2024/day/1/answer HTTP/1.1\r\n"));
+        assert!(requests[0].ends_with("\r\n\r\nlevel=2&answer=1000"));
+        fs::remove_file(&path).unwrap();
+    }
+}
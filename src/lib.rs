@@ -7,6 +7,7 @@
     static_mut_refs
 )]
 #![feature(
+    const_type_name,
     core_intrinsics,
     generic_const_exprs,
     generic_arg_infer,
@@ -14,23 +15,30 @@
     maybe_uninit_array_assume_init,
     maybe_uninit_uninit_array,
     never_type,
+    new_zeroed_alloc,
     portable_simd,
     ptr_as_ref_unchecked,
     stmt_expr_attributes,
     unchecked_shifts
 )]
 use std::{
+    cell::Cell,
     cmp::max,
-    fmt::Debug,
+    error::Error,
+    fmt::{Debug, Display},
     hash::Hash,
     hint::unreachable_unchecked,
     intrinsics::{unchecked_add, unchecked_div, unchecked_mul, unchecked_rem, unchecked_shl},
     mem::MaybeUninit,
     ops::{Add, AddAssign, Sub, SubAssign},
+    time::{Duration, Instant},
 };
 
 use aoc_runner_derive::aoc_lib;
 
+pub mod answers;
+pub mod batch;
+pub mod client;
 pub mod day1;
 pub mod day13;
 pub mod day14;
@@ -43,6 +51,28 @@ pub mod day5;
 pub mod day6;
 pub mod day7;
 pub mod day8;
+pub mod examples;
+pub mod generate;
+pub mod journal;
+pub mod rank;
+pub mod scaffold;
+pub mod timing;
+
+#[cfg(any(test, feature = "small-lut14"))]
+mod lut14;
+
+pub use day1::validate as validate_day1;
+pub use day13::validate as validate_day13;
+pub use day14::validate as validate_day14;
+pub use day15::validate as validate_day15;
+pub use day16::validate as validate_day16;
+pub use day2::validate as validate_day2;
+pub use day3::validate as validate_day3;
+pub use day4::validate as validate_day4;
+pub use day5::validate as validate_day5;
+pub use day6::validate as validate_day6;
+pub use day7::validate as validate_day7;
+pub use day8::validate as validate_day8;
 
 aoc_lib! { year = 2024 }
 
@@ -80,6 +110,65 @@ where
     const DEFAULT: Self = (T::DEFAULT, U::DEFAULT);
 }
 
+thread_local! {
+    static FORCE_SCALAR: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Whether the CPU supports every feature the fast path of each solver is compiled for.
+#[inline]
+pub fn has_fast_path() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        is_x86_feature_detected!("avx2")
+            && is_x86_feature_detected!("bmi1")
+            && is_x86_feature_detected!("bmi2")
+            && is_x86_feature_detected!("cmpxchg16b")
+            && is_x86_feature_detected!("lzcnt")
+            && is_x86_feature_detected!("movbe")
+            && is_x86_feature_detected!("popcnt")
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+/// Runs `f` with every solver called on this thread restricted to the portable path.
+pub fn with_scalar<T>(f: impl FnOnce() -> T) -> T {
+    /// Restores the previous setting even if `f` unwinds
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            FORCE_SCALAR.set(self.0);
+        }
+    }
+
+    let _restore = Restore(FORCE_SCALAR.replace(true));
+    f()
+}
+
+/// Runs `f` compiled for AVX2 if the CPU supports it, falling back to a portable build otherwise.
+///
+/// `f` and everything it should specialise must be inlined into it, so solver bodies are
+/// `#[inline(always)]` rather than carrying their own `#[target_feature]`.
+#[inline(always)]
+pub fn dispatch<T>(f: impl FnOnce() -> T) -> T {
+    #[cfg(target_arch = "x86_64")]
+    {
+        #[target_feature(enable = "avx2,bmi1,bmi2,cmpxchg16b,lzcnt,movbe,popcnt")]
+        unsafe fn fast<T>(f: impl FnOnce() -> T) -> T {
+            f()
+        }
+
+        if !FORCE_SCALAR.get() && has_fast_path() {
+            return unsafe { fast(f) };
+        }
+    }
+
+    f()
+}
+
 #[inline]
 unsafe fn ptr_add(ptr: *const u8, val: usize) -> *const u8 {
     (ptr as usize).unchecked_add(val) as _
@@ -411,6 +500,491 @@ impl Assume for bool {
     }
 }
 
+/// Whether a solver validates its input or assumes it is well formed.
+pub trait Mode {
+    const CHECKED: bool;
+}
+
+/// Report malformed input as a [`ParseError`].
+pub struct Checked;
+
+impl Mode for Checked {
+    const CHECKED: bool = true;
+}
+
+/// Assume the input is well formed, as the puzzle guarantees.
+pub struct Unchecked;
+
+impl Mode for Unchecked {
+    const CHECKED: bool = false;
+}
+
+/// Malformed puzzle input found by a solver running in [`Checked`] mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset into the input
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column, in bytes
+    pub column: usize,
+    pub expected: &'static str,
+    /// `None` if the input ended early
+    pub found: Option<u8>,
+}
+
+impl ParseError {
+    #[cold]
+    #[inline(never)]
+    pub fn new(input: &[u8], offset: usize, expected: &'static str) -> Self {
+        let offset = offset.min(input.len());
+        let before = &input[..offset];
+        let line_start = memchr::memrchr(EOL, before).map_or(0, |eol| eol + 1);
+
+        Self {
+            offset,
+            line: memchr::memchr_iter(EOL, before).count() + 1,
+            column: offset - line_start + 1,
+            expected,
+            found: input.get(offset).copied(),
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {} (byte {}): expected {}, found ",
+            self.line, self.column, self.offset, self.expected
+        )?;
+        match self.found {
+            Some(c) => write!(f, "'{}'", c.escape_ascii()),
+            None => write!(f, "end of input"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Puzzle input that breaks an assumption made by the unchecked solvers, found by one of the
+/// `validate_dayN` passes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputError {
+    UnexpectedByte(ParseError),
+    /// `line` is 1-based, and lengths don't include the new line
+    LineLength {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    LineCount {
+        expected: usize,
+        found: usize,
+    },
+    NumberOutOfRange {
+        line: usize,
+        column: usize,
+        found: i64,
+        min: i64,
+        max: i64,
+    },
+    MissingTrailingNewline,
+}
+
+impl InputError {
+    #[cold]
+    pub fn out_of_range(input: &[u8], offset: usize, found: i64, (min, max): (i64, i64)) -> Self {
+        let ParseError { line, column, .. } = ParseError::new(input, offset, "");
+        Self::NumberOutOfRange {
+            line,
+            column,
+            found,
+            min,
+            max,
+        }
+    }
+}
+
+impl From<ParseError> for InputError {
+    fn from(err: ParseError) -> Self {
+        Self::UnexpectedByte(err)
+    }
+}
+
+impl Display for InputError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedByte(err) => write!(f, "{err}"),
+            Self::LineLength {
+                line,
+                expected,
+                found,
+            } => write!(f, "line {line}: expected {expected} bytes, found {found}"),
+            Self::LineCount { expected, found } => {
+                write!(f, "expected {expected} lines, found {found}")
+            }
+            Self::NumberOutOfRange {
+                line,
+                column,
+                found,
+                min,
+                max,
+            } => write!(
+                f,
+                "line {line}, column {column}: expected a number in {min}..={max}, found {found}"
+            ),
+            Self::MissingTrailingNewline => write!(f, "expected a trailing new line"),
+        }
+    }
+}
+
+impl Error for InputError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::UnexpectedByte(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// The layout of a puzzle input that passed validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InputShape {
+    /// Number of lines, including blank ones
+    pub lines: usize,
+    /// Length of every line, if they're all the same
+    pub width: Option<usize>,
+    /// Number of items the puzzle is made of, e.g. reports, equations or robots
+    pub records: usize,
+}
+
+impl InputShape {
+    /// Measures `input`, which must end in a new line.
+    pub fn of(input: &[u8]) -> Result<Self, InputError> {
+        let Some(body) = input.strip_suffix(&[EOL]) else {
+            return if input.is_empty() {
+                Ok(Self {
+                    lines: 0,
+                    width: None,
+                    records: 0,
+                })
+            } else {
+                Err(InputError::MissingTrailingNewline)
+            };
+        };
+
+        let mut lines = 0;
+        let mut width = None;
+        for (index, line) in body.split(|&c| c == EOL).enumerate() {
+            lines += 1;
+            width = match (index, width) {
+                (0, _) => Some(line.len()),
+                (_, Some(width)) if width == line.len() => Some(width),
+                _ => None,
+            };
+        }
+
+        Ok(Self {
+            lines,
+            width,
+            records: lines,
+        })
+    }
+
+    pub fn expect_lines(self, expected: usize) -> Result<Self, InputError> {
+        if self.lines == expected {
+            Ok(self)
+        } else {
+            Err(InputError::LineCount {
+                expected,
+                found: self.lines,
+            })
+        }
+    }
+
+    /// Checks the first `lines` lines of `input` are `expected` bytes long.
+    pub fn expect_width(
+        self,
+        input: &[u8],
+        lines: usize,
+        expected: usize,
+    ) -> Result<Self, InputError> {
+        for (index, line) in input
+            .split(|&c| c == EOL)
+            .take(lines.min(self.lines))
+            .enumerate()
+        {
+            if line.len() != expected {
+                return Err(InputError::LineLength {
+                    line: index + 1,
+                    expected,
+                    found: line.len(),
+                });
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Checks `input` is a grid of `lines` lines, each `width` bytes long.
+    pub fn grid(input: &[u8], width: usize, lines: usize) -> Result<Self, InputError> {
+        Self::of(input)?
+            .expect_width(input, lines, width)?
+            .expect_lines(lines)
+    }
+}
+
+/// A day's puzzle, with the unchecked and checked solvers for both its parts.
+pub trait Solution {
+    const DAY: u8;
+    /// The input the unchecked parts assume, which [`Solution::validate`] checks
+    const INPUT: &'static str;
+    /// What else the unchecked parts assume of input [`Solution::validate`] accepts, which only
+    /// solving it finds out, so callers must solve it with the checked parts first
+    const ASSUMES: Option<&'static str> = None;
+    /// Whether the parts only run on CPUs with AVX2. Those going through [`dispatch`] fall back
+    /// to a portable path instead.
+    const NEEDS_AVX2: bool = false;
+    /// Whether the parts keep state in statics between calls, so mustn't run concurrently
+    const GLOBAL_STATE: bool = false;
+
+    type Answer1: Display;
+    type Answer2: Display;
+
+    fn validate(input: &str) -> Result<InputShape, InputError>;
+    fn part1(input: &str) -> Self::Answer1;
+    fn try_part1(input: &str) -> Result<Self::Answer1, ParseError>;
+    fn part2(input: &str) -> Self::Answer2;
+    fn try_part2(input: &str) -> Result<Self::Answer2, ParseError>;
+}
+
+/// The [`Solution`] for day `N`.
+pub struct Day<const N: u8>;
+
+/// One part of one day in the [`SOLUTIONS`] registry, with its answer formatted as a string.
+#[derive(Clone, Copy)]
+pub struct Part {
+    pub day: u8,
+    pub part: u8,
+    /// The name of the answer's type
+    pub answer: &'static str,
+    /// [`Solution::INPUT`]
+    pub input: &'static str,
+    /// [`Solution::ASSUMES`]
+    pub assumes: Option<&'static str>,
+    /// [`Solution::NEEDS_AVX2`]
+    pub needs_avx2: bool,
+    /// [`Solution::GLOBAL_STATE`]
+    pub global_state: bool,
+    /// The day's `validate`, which checks the input is structured as `solve` assumes
+    pub validate: fn(&str) -> Result<InputShape, InputError>,
+    /// The day's `partN`, which must only be given input that [`Part::check`] accepts
+    pub solve: fn(&str) -> String,
+    /// The day's `try_partN`
+    pub try_solve: fn(&str) -> Result<String, ParseError>,
+}
+
+impl Part {
+    /// Part 1 or 2 of `S`.
+    pub const fn of<S: Solution>(part: u8) -> Self {
+        fn solve1<S: Solution>(input: &str) -> String {
+            S::part1(input).to_string()
+        }
+        fn try_solve1<S: Solution>(input: &str) -> Result<String, ParseError> {
+            S::try_part1(input).map(|answer| answer.to_string())
+        }
+        fn solve2<S: Solution>(input: &str) -> String {
+            S::part2(input).to_string()
+        }
+        fn try_solve2<S: Solution>(input: &str) -> Result<String, ParseError> {
+            S::try_part2(input).map(|answer| answer.to_string())
+        }
+
+        let part1 = part == 1;
+        Self {
+            day: S::DAY,
+            part,
+            answer: if part1 {
+                std::any::type_name::<S::Answer1>()
+            } else {
+                std::any::type_name::<S::Answer2>()
+            },
+            input: S::INPUT,
+            assumes: S::ASSUMES,
+            needs_avx2: S::NEEDS_AVX2,
+            global_state: S::GLOBAL_STATE,
+            validate: S::validate,
+            solve: if part1 { solve1::<S> } else { solve2::<S> },
+            try_solve: if part1 {
+                try_solve1::<S>
+            } else {
+                try_solve2::<S>
+            },
+        }
+    }
+
+    /// Checks `input` is safe to give `solve`: that `validate` accepts it and, for days with
+    /// [`Part::assumes`], that `try_solve` does too.
+    pub fn check(&self, input: &str) -> Result<(), InputError> {
+        (self.validate)(input)?;
+        if self.assumes.is_some() {
+            (self.try_solve)(input)?;
+        }
+        Ok(())
+    }
+
+    /// Validates then solves `input`, timing each step. Input that fails [`Part::check`] is
+    /// solved with `try_solve` instead, to report where it is malformed. The checked solve of
+    /// days with [`Part::assumes`] isn't timed.
+    pub fn run(&self, input: &str) -> (Result<String, ParseError>, Duration, Duration) {
+        let start = Instant::now();
+        let valid = (self.validate)(input).is_ok();
+        let validate_time = start.elapsed();
+
+        let safe = valid && (self.assumes.is_none() || (self.try_solve)(input).is_ok());
+        let start = Instant::now();
+        let answer = if safe {
+            Ok((self.solve)(input))
+        } else {
+            (self.try_solve)(input)
+        };
+        (answer, validate_time, start.elapsed())
+    }
+}
+
+macro_rules! solutions {
+    ($($day:literal: $answer1:ty, $answer2:ty, $input:literal $(assuming $assumes:literal)?;)*) => {
+        paste::paste! {
+            $(
+                impl Solution for Day<$day> {
+                    const DAY: u8 = $day;
+                    const INPUT: &'static str = $input;
+                    $(const ASSUMES: Option<&'static str> = Some($assumes);)?
+
+                    type Answer1 = $answer1;
+                    type Answer2 = $answer2;
+
+                    fn validate(input: &str) -> Result<InputShape, InputError> {
+                        [<day $day>]::validate(input)
+                    }
+
+                    fn part1(input: &str) -> $answer1 {
+                        [<day $day>]::part1(input)
+                    }
+
+                    fn try_part1(input: &str) -> Result<$answer1, ParseError> {
+                        [<day $day>]::try_part1(input)
+                    }
+
+                    fn part2(input: &str) -> $answer2 {
+                        [<day $day>]::part2(input)
+                    }
+
+                    fn try_part2(input: &str) -> Result<$answer2, ParseError> {
+                        [<day $day>]::try_part2(input)
+                    }
+                }
+            )*
+
+            /// Every solved part, in order of day then part.
+            pub static SOLUTIONS: &[Part] = &[$(Part::of::<Day<$day>>(1), Part::of::<Day<$day>>(2)),*];
+        }
+    };
+}
+
+solutions! {
+    1: i32, i32, "lines of two numbers separated by spaces";
+    2: i32, i32, "reports of at least 4 levels that fit in an i32";
+    3: u32, u32, "corrupted memory with `mul(X,Y)`, `do()` and `don't()` instructions";
+    4: u32, u32, "a rectangular grid of `XMAS` letters";
+    5: i32, i32, "rules of the form `X|Y`, a blank line, then updates of comma separated pages";
+    6: i32, i32, "a rectangular map with a single guard"
+        assuming "the guard's patrol leaves the map";
+    7: u64, u64, "equations of at most 12 numbers under 1000, one per line";
+    8: i32, i32, "a rectangular map of `.` and alphanumeric antennae";
+    13: i64, i64, "claw machines separated by blank lines";
+    14: i32, i32, "500 robots inside a 101 by 103 room";
+    15: usize, usize, "a 50 by 50 warehouse followed by the robot's moves"
+        assuming "no move pushes more than 1000 tiles";
+    16: u32, u32, "a 141 by 141 maze from the bottom left to the top right"
+        assuming "the best paths leave at most 200 branches to walk at once";
+}
+
+/// Looks up part `part` of day `day` in [`SOLUTIONS`].
+pub fn solution(day: u8, part: u8) -> Option<&'static Part> {
+    SOLUTIONS
+        .binary_search_by_key(&(day, part), |p| (p.day, p.part))
+        .ok()
+        .map(|index| &SOLUTIONS[index])
+}
+
+/// Returns a [`ParseError`] from the enclosing function if `$cond` doesn't hold in [`Checked`]
+/// mode. `$cond` isn't evaluated in [`Unchecked`] mode.
+#[macro_export]
+macro_rules! ensure {
+    ($mode:ty, $cond:expr, $input:expr, $offset:expr, $expected:expr) => {
+        if <$mode as $crate::Mode>::CHECKED && !$cond {
+            return Err($crate::ParseError::new($input, $offset, $expected).into());
+        }
+    };
+}
+
+/// Reads `input[offset]`, which must be in bounds in [`Unchecked`] mode.
+#[inline(always)]
+pub unsafe fn byte<M: Mode>(
+    input: &[u8],
+    offset: usize,
+    expected: &'static str,
+) -> Result<u8, ParseError> {
+    if M::CHECKED {
+        match input.get(offset) {
+            Some(&c) => Ok(c),
+            None => Err(ParseError::new(input, offset, expected)),
+        }
+    } else {
+        Ok(*input.get_unchecked(offset))
+    }
+}
+
+/// Pushes `item` onto `stack`, which must have room in [`Unchecked`] mode. In [`Checked`] mode a
+/// full stack is reported as `input[offset]` not being `expected`.
+#[inline(always)]
+pub unsafe fn push<M: Mode, const N: usize, T: Copy>(
+    stack: &mut ArrayVec<N, T>,
+    item: T,
+    input: &[u8],
+    offset: usize,
+    expected: &'static str,
+) -> Result<(), ParseError> {
+    if M::CHECKED {
+        stack
+            .try_push(item)
+            .map_err(|_| ParseError::new(input, offset, expected))
+    } else {
+        stack.push_unchecked(item);
+        Ok(())
+    }
+}
+
+/// Checks `input[offset..offset + len]` is all ASCII digits in [`Checked`] mode.
+#[inline(always)]
+pub fn digits<M: Mode>(input: &[u8], offset: usize, len: usize) -> Result<(), ParseError> {
+    if M::CHECKED {
+        for pos in offset..offset + len {
+            ensure!(
+                M,
+                input.get(pos).is_some_and(u8::is_ascii_digit),
+                input,
+                pos,
+                "digit"
+            );
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Clone, Copy)]
 pub struct ArrayVec<const N: usize, T> {
     inner: [T; N],
@@ -468,6 +1042,17 @@ where
         self.len += 1;
     }
 
+    /// Pushes `item`, or gives it back if the vector is full
+    #[inline]
+    pub fn try_push(&mut self, item: T) -> Result<(), T> {
+        if self.len == N {
+            return Err(item);
+        }
+
+        unsafe { self.push_unchecked(item) };
+        Ok(())
+    }
+
     #[inline]
     pub unsafe fn get_unchecked(&self, index: usize) -> T {
         *self.inner.get_unchecked(index)
@@ -810,3 +1395,77 @@ impl Direction {
 impl ConstDefault for Direction {
     const DEFAULT: Self = Self::North;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry() {
+        assert!(SOLUTIONS.is_sorted_by_key(|p| (p.day, p.part)));
+        assert!(SOLUTIONS.iter().all(|p| !p.needs_avx2 && !p.global_state));
+
+        let part = solution(7, 2).unwrap();
+        assert_eq!((part.day, part.part, part.answer), (7, 2, "u64"));
+        assert_eq!(part.input, <Day<7> as Solution>::INPUT);
+        assert_eq!((part.solve)("190: 10 19\n"), "190");
+        assert_eq!(
+            (part.try_solve)("190: 10 x\n").unwrap_err().to_string(),
+            "line 1, column 9 (byte 8): expected digit, found 'x'"
+        );
+
+        assert_eq!(solution(15, 1).unwrap().answer, "usize");
+        assert!(solution(9, 1).is_none());
+        assert!(solution(1, 3).is_none());
+    }
+
+    #[test]
+    fn run_checks_assumptions() {
+        let looping = ".#..\n...#\n#^..\n..#.\n";
+        let part = solution(6, 1).unwrap();
+        assert!(part.assumes.is_some());
+        assert!((part.validate)(looping).is_ok());
+        assert!(part.check(looping).is_err());
+
+        let (answer, _, _) = part.run(looping);
+        assert_eq!(
+            answer.unwrap_err().to_string(),
+            (part.try_solve)(looping).unwrap_err().to_string()
+        );
+        assert_eq!(part.run(".#..\n.^.#\n....\n").0.unwrap(), "3");
+    }
+
+    #[test]
+    fn with_scalar_unwinding() {
+        let res = std::panic::catch_unwind(|| with_scalar(|| panic!("solver failed")));
+        assert!(res.is_err());
+        assert!(!FORCE_SCALAR.get());
+
+        with_scalar(|| {
+            assert!(FORCE_SCALAR.get());
+            with_scalar(|| ());
+            assert!(FORCE_SCALAR.get());
+        });
+        assert!(!FORCE_SCALAR.get());
+    }
+
+    #[test]
+    fn full_stack() {
+        let mut stack = ArrayVec::<2, u8>::new();
+        assert_eq!(stack.try_push(1), Ok(()));
+        assert_eq!(stack.try_push(2), Ok(()));
+        assert_eq!(stack.try_push(3), Err(3));
+        assert_eq!(unsafe { stack.as_slice() }, [1, 2]);
+
+        let input = b"ab\n";
+        let err = unsafe { push::<Checked, 2, u8>(&mut stack, 3, input, 1, "room") }.unwrap_err();
+        assert_eq!(
+            (err.offset, err.expected, err.found),
+            (1, "room", Some(b'b'))
+        );
+
+        assert_eq!(unsafe { stack.pop() }, Some(2));
+        assert!(unsafe { push::<Checked, 2, u8>(&mut stack, 3, input, 1, "room") }.is_ok());
+        assert_eq!(unsafe { stack.as_slice() }, [1, 3]);
+    }
+}
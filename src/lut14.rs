@@ -0,0 +1,27 @@
+//! Reading the numbers in day 14's input, shared by `build.rs`, which tabulates them for every
+//! three bytes, and the smaller table [`crate::day14`] builds with the `small-lut14` feature.
+
+/// The number at the start of `text` and how many bytes it takes up, if there is one.
+///
+/// A number is 1 to 3 digits up to 127, or `-` and 1 or 2 digits, with no leading zeroes. It ends
+/// at the first byte that isn't a digit, or the end of `text`, so a number cut short by the end
+/// of `text` is read as far as it goes.
+pub const fn number(text: &[u8]) -> Option<(i8, usize)> {
+    let neg = !text.is_empty() && text[0] == b'-';
+    let start = neg as usize;
+
+    let mut len = start;
+    let mut value = 0_i32;
+    while len < text.len() && text[len].is_ascii_digit() {
+        value = value * 10 + (text[len] - b'0') as i32;
+        len += 1;
+    }
+
+    let digits = len - start;
+    let leading_zero = digits > 1 && text[start] == b'0';
+    if digits == 0 || leading_zero || (neg && value == 0) || value > i8::MAX as i32 {
+        return None;
+    }
+
+    Some((if neg { -value } else { value } as i8, len))
+}
@@ -1,3 +1,427 @@
-use aoc_runner_derive::aoc_main;
+use std::{env, path::PathBuf, process::ExitCode, thread};
 
-aoc_main! { lib = aoc_2024 }
+use aoc_2024::{
+    batch,
+    client::{self, Client, Fetched},
+    examples, generate, journal,
+    rank::{self, Ranks},
+    scaffold, timing, SOLUTIONS,
+};
+
+mod aoc {
+    use aoc_runner_derive::aoc_main;
+
+    aoc_main! { lib = aoc_2024 }
+
+    /// Solves the local inputs in `input/2024`, as `aoc_main!` does.
+    pub(super) fn run() {
+        main()
+    }
+}
+
+const USAGE: &str = "\
+usage: aoc-2024 [COMMAND]
+
+With no command, solves the local inputs in input/2024.
+
+commands:
+    examples DAY PAGE
+        save the examples in PAGE, a saved puzzle description, as input/2024/examples/dayN-K.txt,
+        with the answers it gives for them in input/2024/examples/answers.toml
+    fetch [DAY...] [--year YEAR] [--base-url URL] [--refresh]
+        download each day's input (default: every solved day) to input/YEAR/dayN.txt (default
+        YEAR: 2024) from URL (default: $AOC_BASE_URL or https://adventofcode.com), logged in with
+        the session cookie in $AOC_SESSION. Cached inputs are only downloaded again with
+        --refresh, and never overwritten
+    generate DAY [--seed N]
+        print a random input for the day generated from N (default 0), in the format its solvers
+        assume, with the answers to it on stderr
+    list
+        list every solved part, with its answer type and the input it expects
+    new-day DAY
+        add src/dayN.rs with stubs and tests to fill in, and register it in src/lib.rs
+    rank DAY [--name NAME] [--out FILE] [--needle TEXT...] [--runs N]
+        rank bytes by how often they appear in every input for the day found as batch does, and
+        write a module with a HeuristicFrequencyRank struct NAME (default: DayN) ranking them to
+        FILE (default: src/ranks/dayN.rs). For each TEXT, report how much faster a memmem finder
+        is with these ranks than the default, over N runs (default 100)
+    solve DAY[.PART] [FILE]
+        solve the day's parts (default: both) for FILE (default: input/2024/dayN.txt), reporting
+        where the input is malformed
+    batch [DIR] [--threads N] [--format table|jsonl|csv]
+        solve DIR/2024/dayN.txt and every DIR/<user>/2024/dayN.txt (default DIR: input), printing
+        the answers and timings as a table, JSON Lines or CSV
+    submit DAY.PART [ANSWER] [--year YEAR] [--base-url URL]
+        submit ANSWER (default: the answer for input/YEAR/dayN.txt) to URL, as for fetch, unless
+        input/YEAR/journal.toml shows it's already ruled out or the server wants a wait. Every
+        answer submitted is recorded there
+    time [DAY[.PART]...] [--runs N] [--warmup N]
+        time each part (default: all) on its local input, input/2024/dayN.txt, reporting the min,
+        median and 99th percentile of N runs (default 100) after a warm up (default 10 runs)";
+
+fn batch(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut root = PathBuf::from("input");
+    let mut threads = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut format = "table".to_owned();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => {
+                threads = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--threads needs a number")?;
+            }
+            "--format" => format = args.next().ok_or("--format needs a format")?,
+            _ => root = PathBuf::from(arg),
+        }
+    }
+
+    let inputs =
+        batch::discover_all(&root).map_err(|err| format!("reading {}: {err}", root.display()))?;
+    let outcomes = batch::solve_all(&inputs, threads);
+    match format.as_str() {
+        "table" => print!("{}", batch::Table(&outcomes)),
+        "jsonl" => print!("{}", batch::JsonLines(&outcomes)),
+        "csv" => print!("{}", batch::Csv(&outcomes)),
+        _ => {
+            return Err(format!(
+                "unknown format {format}, expected table, jsonl or csv"
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Parses `DAY[.PART]`
+fn day_part(arg: &str) -> Result<(u8, Option<u8>), String> {
+    let (day, part) = match arg.split_once('.') {
+        Some((day, part)) => (day, Some(part)),
+        None => (arg, None),
+    };
+    let invalid = |_| format!("{arg} isn't DAY[.PART]");
+    let day = day.parse().map_err(invalid)?;
+    let part = part.map(|part| part.parse().map_err(invalid)).transpose()?;
+    Ok((day, part))
+}
+
+fn examples(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let (Some(day), Some(page)) = (args.next(), args.next()) else {
+        return Err("examples needs DAY and PAGE".to_owned());
+    };
+    let day = day.parse().map_err(|_| format!("{day} isn't a day"))?;
+    let page = std::fs::read_to_string(&page).map_err(|err| format!("reading {page}: {err}"))?;
+
+    let found = examples::extract(&page);
+    examples::save("input/2024/examples".as_ref(), day, &found)
+        .map_err(|err| format!("saving the examples: {err}"))?;
+    for (index, example) in found.iter().enumerate() {
+        let answer = |answer: &Option<String>| answer.clone().unwrap_or_else(|| "-".to_owned());
+        println!(
+            "day{day}-{}: {} lines, part 1: {}, part 2: {}",
+            index + 1,
+            example.text.lines().count(),
+            answer(&example.answers[0]),
+            answer(&example.answers[1])
+        );
+    }
+    Ok(())
+}
+
+/// Logs in to `base_url` with the session cookie in `$AOC_SESSION`
+fn client(base_url: &str) -> Result<Client, String> {
+    let session = env::var("AOC_SESSION").map_err(|_| "set AOC_SESSION to a session cookie")?;
+    Ok(Client::new(base_url, &session))
+}
+
+fn fetch(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut year = 2024;
+    let mut base_url =
+        env::var("AOC_BASE_URL").unwrap_or_else(|_| client::DEFAULT_BASE_URL.to_owned());
+    let mut refresh = false;
+    let mut days = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--year" => {
+                year = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--year needs a year")?;
+            }
+            "--base-url" => base_url = args.next().ok_or("--base-url needs a URL")?,
+            "--refresh" => refresh = true,
+            _ => days.push(arg.parse().map_err(|_| format!("{arg} isn't a day"))?),
+        }
+    }
+    if days.is_empty() {
+        days = SOLUTIONS.iter().map(|part| part.day).collect();
+        days.dedup();
+    }
+
+    let client = client(&base_url)?;
+    for day in days {
+        let fetched = client::fetch(&client, "input".as_ref(), year, day, refresh)
+            .map_err(|err| format!("day {day}: {err}"))?;
+        let status = match fetched {
+            Fetched::Downloaded => "downloaded",
+            Fetched::Cached => "cached",
+            Fetched::Unchanged => "unchanged",
+        };
+        println!("day {day}: {status}");
+    }
+    Ok(())
+}
+
+fn generate(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut day = None;
+    let mut seed = 0;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--seed" => {
+                seed = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--seed needs a number")?;
+            }
+            _ => day = Some(arg.parse().map_err(|_| format!("{arg} isn't a day"))?),
+        }
+    }
+    let day = day.ok_or("generate needs a DAY")?;
+
+    let generated = generate::generate(day, seed).ok_or(format!("day {day} has no generator"))?;
+    print!("{}", generated.text);
+    for (part, answer) in (1..).zip(&generated.answers) {
+        if let Some(answer) = answer {
+            eprintln!("day {day} part {part}: {answer}");
+        }
+    }
+    Ok(())
+}
+
+fn list() -> Result<(), String> {
+    println!("day  part  answer  input");
+    for part in SOLUTIONS {
+        println!(
+            "{:>3}  {:>4}  {:6}  {}{}{}{}",
+            part.day,
+            part.part,
+            part.answer,
+            part.input,
+            part.assumes
+                .map_or(String::new(), |assumes| format!(", assuming {assumes}")),
+            if part.needs_avx2 { " (needs AVX2)" } else { "" },
+            if part.global_state {
+                " (global state)"
+            } else {
+                ""
+            }
+        );
+    }
+    Ok(())
+}
+
+fn new_day(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let day = args.next().ok_or("new-day needs a DAY")?;
+    let day: u8 = day.parse().map_err(|_| format!("{day} isn't a day"))?;
+    scaffold::new_day(".".as_ref(), day).map_err(|err| format!("adding day {day}: {err}"))?;
+
+    println!("added src/day{day}.rs and registered it in src/lib.rs");
+    if !PathBuf::from(format!("input/2024/day{day}.txt")).exists() {
+        println!("its tests need the input, so run `aoc-2024 fetch {day}` next");
+    }
+    Ok(())
+}
+
+fn rank(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut day = None;
+    let mut name = None;
+    let mut out = None;
+    let mut needles = Vec::new();
+    let mut runs = 100;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--name" => name = Some(args.next().ok_or("--name needs a name")?),
+            "--out" => out = Some(args.next().ok_or("--out needs a file")?),
+            "--needle" => needles.push(args.next().ok_or("--needle needs a text")?),
+            "--runs" => {
+                runs = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--runs needs a number")?;
+            }
+            _ => day = Some(arg.parse().map_err(|_| format!("{arg} isn't a day"))?),
+        }
+    }
+    let day: u8 = day.ok_or("rank needs a DAY")?;
+    let name = name.unwrap_or_else(|| format!("Day{day}"));
+    let out = PathBuf::from(out.unwrap_or_else(|| format!("src/ranks/day{day}.rs")));
+
+    let corpus: Vec<String> = batch::discover_all("input".as_ref())
+        .map_err(|err| format!("reading inputs: {err}"))?
+        .into_iter()
+        .filter(|input| input.day == day)
+        .map(|input| input.text)
+        .collect();
+    if corpus.is_empty() {
+        return Err(format!("there are no inputs for day {day}"));
+    }
+
+    let ranks = Ranks::of(&corpus);
+    if let Some(dir) = out.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| format!("creating {}: {err}", dir.display()))?;
+    }
+    std::fs::write(&out, ranks.module(&name))
+        .map_err(|err| format!("writing {}: {err}", out.display()))?;
+    println!(
+        "ranked {} inputs for day {day} as {name} in {}",
+        corpus.len(),
+        out.display()
+    );
+
+    if !needles.is_empty() {
+        let needles: Vec<&[u8]> = needles.iter().map(|needle| needle.as_bytes()).collect();
+        print!(
+            "{}",
+            rank::Table(&rank::compare(&ranks, &corpus, &needles, runs))
+        );
+    }
+    Ok(())
+}
+
+fn solve(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let (day, part) = day_part(&args.next().ok_or(USAGE)?)?;
+    let parts: Vec<_> = SOLUTIONS
+        .iter()
+        .filter(|p| p.day == day && part.is_none_or(|part| part == p.part))
+        .collect();
+    if parts.is_empty() {
+        return Err(format!("day {day} has no such part solved"));
+    }
+
+    let path = args
+        .next()
+        .unwrap_or_else(|| format!("input/2024/day{day}.txt"));
+    let input = std::fs::read_to_string(&path).map_err(|err| format!("reading {path}: {err}"))?;
+    for part in parts {
+        let answer = (part.try_solve)(&input)
+            .map_err(|err| format!("day {day} part {}: {err}", part.part))?;
+        println!("day {day} part {}: {answer}", part.part);
+    }
+    Ok(())
+}
+
+fn submit(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let mut year = 2024;
+    let mut base_url =
+        env::var("AOC_BASE_URL").unwrap_or_else(|_| client::DEFAULT_BASE_URL.to_owned());
+    let mut day_part = None;
+    let mut answer = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--year" => {
+                year = args
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("--year needs a year")?;
+            }
+            "--base-url" => base_url = args.next().ok_or("--base-url needs a URL")?,
+            _ if day_part.is_none() => day_part = Some(self::day_part(&arg)?),
+            _ => answer = Some(arg),
+        }
+    }
+    let Some((day, Some(part))) = day_part else {
+        return Err("submit needs DAY.PART".to_owned());
+    };
+
+    let answer = match answer {
+        Some(answer) => answer,
+        None => {
+            let solution = aoc_2024::solution(day, part)
+                .filter(|_| year == 2024)
+                .ok_or(format!(
+                    "day {day} part {part} of {year} isn't solved, so give an answer"
+                ))?;
+            let path = format!("input/{year}/day{day}.txt");
+            let input =
+                std::fs::read_to_string(&path).map_err(|err| format!("reading {path}: {err}"))?;
+            (solution.try_solve)(&input).map_err(|err| format!("{path}: {err}"))?
+        }
+    };
+
+    let path = PathBuf::from(format!("input/{year}/journal.toml"));
+    let feedback = journal::submit(&client(&base_url)?, &path, year, day, part, &answer)
+        .map_err(|err| format!("day {day} part {part}, {answer}: {err}"))?;
+    print!("day {day} part {part}, {answer}: {}", feedback.verdict);
+    match feedback.wait {
+        Some(wait) => println!(" (wait {wait:?} before submitting again)"),
+        None => println!(),
+    }
+    Ok(())
+}
+
+fn time(mut args: impl Iterator<Item = String>) -> Result<(), String> {
+    let number = |arg: Option<String>, flag: &str| {
+        arg.and_then(|n| n.parse().ok())
+            .ok_or(format!("{flag} needs a number"))
+    };
+
+    let mut runs = 100;
+    let mut warmup = 10;
+    let mut selected = Vec::new();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--runs" => runs = number(args.next(), "--runs")?,
+            "--warmup" => warmup = number(args.next(), "--warmup")?,
+            _ => selected.push(day_part(&arg)?),
+        }
+    }
+
+    let mut summaries = Vec::new();
+    for part in SOLUTIONS {
+        if !selected.is_empty()
+            && !selected
+                .iter()
+                .any(|&(day, p)| day == part.day && p.is_none_or(|p| p == part.part))
+        {
+            continue;
+        }
+
+        let path = format!("input/2024/day{}.txt", part.day);
+        let input =
+            std::fs::read_to_string(&path).map_err(|err| format!("reading {path}: {err}"))?;
+        let summary = timing::time(part, &input, warmup, runs)
+            .map_err(|err| format!("day {} part {}: {err}", part.day, part.part))?;
+        summaries.push(summary);
+    }
+
+    print!("{}", timing::Table(&summaries));
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let res = match args.next().as_deref() {
+        None => {
+            aoc::run();
+            Ok(())
+        }
+        Some("batch") => batch(args),
+        Some("examples") => examples(args),
+        Some("fetch") => fetch(args),
+        Some("generate") => generate(args),
+        Some("list") => list(),
+        Some("new-day") => new_day(args),
+        Some("rank") => rank(args),
+        Some("solve") => solve(args),
+        Some("submit") => submit(args),
+        Some("time") => time(args),
+        Some(_) => Err(USAGE.to_owned()),
+    };
+
+    match res {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
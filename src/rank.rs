@@ -0,0 +1,177 @@
+//! Ranking bytes by how often they appear in a corpus, such as every input for a day, so that
+//! `memmem` finders pick rare bytes of a needle to search for, and generating the ranker as a
+//! module solvers can include.
+
+use std::{
+    fmt::{self, Display, Write as _},
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use memchr::{arch::all::packedpair::HeuristicFrequencyRank, memmem::FinderBuilder};
+
+/// How common each byte is, from 0 for bytes that never appear to 255 for the most common.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Ranks(pub [u8; 256]);
+
+impl Ranks {
+    /// Ranks bytes by how often they appear in `corpus`, scaled so the most common is 255.
+    pub fn of<T: AsRef<[u8]>>(corpus: impl IntoIterator<Item = T>) -> Self {
+        let mut freq = [0_usize; 256];
+        for text in corpus {
+            for &byte in text.as_ref() {
+                freq[byte as usize] += 1;
+            }
+        }
+
+        let max_freq = freq.iter().copied().max().unwrap_or_default().max(1);
+        Self(freq.map(|freq| (freq * 255 / max_freq) as u8))
+    }
+
+    /// The source of a module with a unit struct `name` ranking bytes as these do.
+    pub fn module(&self, name: &str) -> String {
+        let mut source = format!(
+            "//! Generated by `aoc-2024 rank`.\n\
+             \n\
+             use memchr::arch::all::packedpair::HeuristicFrequencyRank;\n\
+             \n\
+             /// Ranks bytes by how often they appear in the inputs it was generated from\n\
+             pub struct {name};\n\
+             \n\
+             impl HeuristicFrequencyRank for {name} {{\n\
+             \x20   fn rank(&self, byte: u8) -> u8 {{\n\
+             \x20       RANKS[byte as usize]\n\
+             \x20   }}\n\
+             }}\n\
+             \n\
+             const RANKS: [u8; 256] = ["
+        );
+        // Lines of values up to 99 columns wide, as rustfmt lays them out
+        let mut line = String::new();
+        for rank in self.0 {
+            let value = format!("{rank},");
+            if !line.is_empty() && 4 + line.len() + 1 + value.len() > 99 {
+                write!(source, "\n    {line}").unwrap();
+                line.clear();
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(&value);
+        }
+        write!(source, "\n    {line}\n];\n").unwrap();
+        source
+    }
+}
+
+impl HeuristicFrequencyRank for Ranks {
+    fn rank(&self, byte: u8) -> u8 {
+        self.0[byte as usize]
+    }
+}
+
+/// How long finding every occurrence of a needle in a corpus took with memchr's default ranker and
+/// with custom ranks.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Comparison {
+    pub needle: Vec<u8>,
+    /// How many times the needle occurs in the corpus
+    pub matches: usize,
+    /// The median time with the default ranker
+    pub default: Duration,
+    /// The median time with the custom ranks
+    pub ranked: Duration,
+}
+
+impl Comparison {
+    /// How many times faster the custom ranks are than the default
+    pub fn speedup(&self) -> f64 {
+        self.default.as_nanos() as f64 / self.ranked.as_nanos().max(1) as f64
+    }
+}
+
+/// Times finding every occurrence of each of `needles` in `corpus` `runs` times, with the default
+/// ranker and with `ranks`.
+pub fn compare<T: AsRef<[u8]>>(
+    ranks: &Ranks,
+    corpus: &[T],
+    needles: &[&[u8]],
+    runs: usize,
+) -> Vec<Comparison> {
+    needles
+        .iter()
+        .map(|&needle| {
+            let default = FinderBuilder::new().build_forward(needle);
+            let ranked = FinderBuilder::new().build_forward_with_ranker(ranks, needle);
+            let matches = corpus
+                .iter()
+                .map(|text| default.find_iter(text.as_ref()).count())
+                .sum();
+
+            let median = |find: &dyn Fn(&[u8]) -> usize| {
+                let mut samples: Vec<Duration> = (0..runs.max(1))
+                    .map(|_| {
+                        let start = Instant::now();
+                        for text in corpus {
+                            black_box(find(black_box(text.as_ref())));
+                        }
+                        start.elapsed()
+                    })
+                    .collect();
+                samples.sort_unstable();
+                samples[samples.len() / 2]
+            };
+            Comparison {
+                needle: needle.to_vec(),
+                matches,
+                default: median(&|text| default.find_iter(text).count()),
+                ranked: median(&|text| ranked.find_iter(text).count()),
+            }
+        })
+        .collect()
+}
+
+/// Displays comparisons as an aligned table.
+pub struct Table<'a>(pub &'a [Comparison]);
+
+impl Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "needle        matches     default      ranked  speedup")?;
+        for comparison in self.0 {
+            let needle = format!("{:?}", String::from_utf8_lossy(&comparison.needle));
+            writeln!(
+                f,
+                "{needle:<12}  {:>7}  {:>10?}  {:>10?}  {:>6.2}x",
+                comparison.matches,
+                comparison.default,
+                comparison.ranked,
+                comparison.speedup()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranks() {
+        let ranks = Ranks::of(["aab", "a"]);
+        assert_eq!(ranks.0[b'a' as usize], 255);
+        assert_eq!(ranks.0[b'b' as usize], 85);
+        assert_eq!(ranks.0[b'c' as usize], 0);
+        assert_eq!(Ranks::of([""; 0]), Ranks([0; 256]));
+    }
+
+    #[test]
+    fn compare() {
+        let corpus = ["mul(1,2)don't()mul(3,4)do()"; 4];
+        let comparisons = super::compare(&Ranks::of(corpus), &corpus, &[b"mul(", b"do("], 3);
+        assert_eq!(comparisons.len(), 2);
+        assert_eq!(comparisons[0].matches, 8);
+        assert_eq!(comparisons[1].matches, 4);
+        assert!(comparisons.iter().all(|c| c.speedup() > 0.0));
+    }
+}
@@ -0,0 +1,24 @@
+//! Generated by `aoc-2024 rank`.
+
+use memchr::arch::all::packedpair::HeuristicFrequencyRank;
+
+/// Ranks bytes by how often they appear in the inputs it was generated from
+pub struct Aoc3;
+
+impl HeuristicFrequencyRank for Aoc3 {
+    fn rank(&self, byte: u8) -> u8 {
+        RANKS[byte as usize]
+    }
+}
+
+const RANKS: [u8; 256] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    15, 15, 0, 13, 16, 17, 16, 20, 255, 247, 14, 17, 118, 17, 0, 16, 42, 59, 66, 65, 66, 60, 70,
+    62, 63, 59, 17, 14, 15, 0, 16, 13, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 15, 0, 17, 15, 0, 0, 17, 0, 15, 9, 82, 17, 0, 101, 0, 0, 0, 112, 114, 20,
+    61, 0, 0, 35, 15, 38, 96, 0, 101, 0, 15, 0, 18, 0, 16, 16, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+];
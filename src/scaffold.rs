@@ -0,0 +1,206 @@
+//! Generating the module for a new day from `src/dayN.rs.tmpl` and registering it in `lib.rs`.
+//!
+//! The single bench target and [`SOLUTIONS`](crate::SOLUTIONS) pick up registered days, so
+//! neither `benches/` nor `Cargo.toml` need to change.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs, io,
+    path::Path,
+};
+
+const TEMPLATE: &str = include_str!("dayN.rs.tmpl");
+
+/// A new day couldn't be added.
+#[derive(Debug)]
+pub enum ScaffoldError {
+    Io(io::Error),
+    /// The day already has a module
+    Exists(u8),
+    /// `lib.rs` doesn't have the line this would go after
+    Missing(&'static str),
+}
+
+impl From<io::Error> for ScaffoldError {
+    fn from(err: io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl Display for ScaffoldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Exists(day) => write!(f, "day {day} already exists"),
+            Self::Missing(what) => write!(f, "lib.rs has no {what} to add the new day to"),
+        }
+    }
+}
+
+impl Error for ScaffoldError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Exists(_) | Self::Missing(_) => None,
+        }
+    }
+}
+
+/// The source of the module for `day`, with `#[aoc]` stubs and tests to fill in.
+pub fn module(day: u8) -> String {
+    TEMPLATE.replace("{day}", &day.to_string())
+}
+
+/// Adds `day`'s `mod`, its `validate_dayN` export and its [`SOLUTIONS`](crate::SOLUTIONS) entry
+/// to `lib`, the source of `lib.rs`.
+pub fn register(lib: &str, day: u8) -> Result<String, ScaffoldError> {
+    let module = format!("pub mod day{day};");
+    if lib.lines().any(|line| line == module) {
+        return Err(ScaffoldError::Exists(day));
+    }
+
+    let mut lines: Vec<&str> = lib.lines().collect();
+    let export = format!("pub use day{day}::validate as validate_day{day};");
+    let entry = format!("    {day}: i32, i32, \"TODO: the input the unchecked parts assume\";");
+    insert_sorted(
+        &mut lines,
+        &module,
+        |line| line.starts_with("pub mod "),
+        "`pub mod`",
+    )?;
+    insert_sorted(
+        &mut lines,
+        &export,
+        |line| line.starts_with("pub use day"),
+        "`pub use`",
+    )?;
+
+    // The registry's entries are in order of day, rather than sorted as text
+    let start = lines
+        .iter()
+        .position(|&line| line == "solutions! {")
+        .ok_or(ScaffoldError::Missing("`solutions!` registry"))?;
+    let end = start
+        + lines[start..]
+            .iter()
+            .position(|&line| line == "}")
+            .ok_or(ScaffoldError::Missing("`solutions!` registry"))?;
+    let at = (start + 1..end)
+        .find(|&index| {
+            let entry_day = lines[index].trim().split(':').next();
+            entry_day.and_then(|d| d.parse::<u8>().ok()) > Some(day)
+        })
+        .unwrap_or(end);
+    lines.insert(at, &entry);
+
+    let mut lib = lines.join("\n");
+    lib.push('\n');
+    Ok(lib)
+}
+
+/// Inserts `line` into the first run of lines matching `run`, keeping it sorted as rustfmt does
+fn insert_sorted<'a>(
+    lines: &mut Vec<&'a str>,
+    line: &'a str,
+    run: impl Fn(&str) -> bool,
+    what: &'static str,
+) -> Result<(), ScaffoldError> {
+    let start = lines
+        .iter()
+        .position(|&l| run(l))
+        .ok_or(ScaffoldError::Missing(what))?;
+    let end = start + lines[start..].iter().take_while(|&&l| run(l)).count();
+    // rustfmt sorts by the module name, so `day1` comes before `day13`, whatever follows it
+    let name = |line: &'a str| {
+        let path = line.split_once(' ').map_or(line, |(_, rest)| rest);
+        let path = path.split_once(' ').map_or(path, |(_, rest)| rest);
+        path.split(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+            .next()
+            .unwrap_or(path)
+    };
+    let at = start + lines[start..end].partition_point(|&l| name(l) < name(line));
+    lines.insert(at, line);
+    Ok(())
+}
+
+/// Writes `root/src/dayN.rs` for `day` and registers it in `root/src/lib.rs`.
+pub fn new_day(root: &Path, day: u8) -> Result<(), ScaffoldError> {
+    let path = root.join(format!("src/day{day}.rs"));
+    if path.exists() {
+        return Err(ScaffoldError::Exists(day));
+    }
+
+    let lib_path = root.join("src/lib.rs");
+    let lib = register(&fs::read_to_string(&lib_path)?, day)?;
+    fs::write(path, module(day))?;
+    fs::write(lib_path, lib)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIB: &str = include_str!("lib.rs");
+
+    #[test]
+    fn module() {
+        let module = super::module(9);
+        assert!(!module.contains("{day}"));
+        assert!(module.contains("#[aoc(day9, part1)]"));
+        assert!(module.contains("#[aoc(day9, part2)]"));
+        assert!(module.contains(r#"include_str!("../input/2024/day9.txt")"#));
+    }
+
+    fn line_of(lines: &[&str], text: &str) -> usize {
+        lines.iter().position(|&line| line == text).unwrap()
+    }
+
+    #[test]
+    fn register() {
+        let lib = super::register(LIB, 9).unwrap();
+        let lines: Vec<_> = lib.lines().collect();
+        let line = |text| line_of(&lines, text);
+
+        assert_eq!(line("pub mod day9;"), line("pub mod day8;") + 1);
+        assert_eq!(
+            line("pub use day9::validate as validate_day9;"),
+            line("pub use day8::validate as validate_day8;") + 1
+        );
+        let entry = lines
+            .iter()
+            .position(|line| line.starts_with("    9: "))
+            .unwrap();
+        assert!(lines[entry - 1].starts_with("    8: "));
+        assert!(lines[entry + 1].starts_with("    13: "));
+        assert_eq!(lines.len(), LIB.lines().count() + 3);
+
+        let lib = super::register(LIB, 25).unwrap();
+        let lines: Vec<_> = lib.lines().collect();
+        let entry = lines
+            .iter()
+            .position(|line| line.starts_with("    25: "))
+            .unwrap();
+        assert!(lines[entry - 2].starts_with("    16: "));
+        assert!(lines[entry - 1].starts_with("        assuming "));
+        assert_eq!(lines[entry + 1], "}");
+        assert_eq!(
+            line_of(&lines, "pub mod day25;"),
+            line_of(&lines, "pub mod day2;") + 1
+        );
+        assert_eq!(
+            line_of(&lines, "pub use day25::validate as validate_day25;"),
+            line_of(&lines, "pub use day2::validate as validate_day2;") + 1
+        );
+
+        assert!(matches!(
+            super::register(LIB, 7),
+            Err(ScaffoldError::Exists(7))
+        ));
+        assert!(matches!(
+            super::register("pub mod day1;\n", 2),
+            Err(ScaffoldError::Missing("`pub use`"))
+        ));
+    }
+}
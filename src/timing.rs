@@ -0,0 +1,149 @@
+//! Timing solvers in process, for checking performance locally without a bench build.
+
+use std::{
+    fmt::{self, Display},
+    hint::black_box,
+    time::{Duration, Instant},
+};
+
+use crate::{InputError, Part};
+
+/// Statistics over repeated runs of one part on one input.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Summary {
+    pub day: u8,
+    pub part: u8,
+    pub runs: usize,
+    pub min: Duration,
+    pub median: Duration,
+    pub p99: Duration,
+    /// The size of the input
+    pub bytes: usize,
+}
+
+impl Summary {
+    /// Summarises `samples`, which must not be empty, sorting them in place.
+    pub fn of(day: u8, part: u8, samples: &mut [Duration], bytes: usize) -> Self {
+        samples.sort_unstable();
+        // Nearest rank, so that the p99 of fewer than 100 samples is the slowest
+        let rank = |p: usize| samples[(samples.len() * p).div_ceil(100).max(1) - 1];
+        Self {
+            day,
+            part,
+            runs: samples.len(),
+            min: samples[0],
+            median: rank(50),
+            p99: rank(99),
+            bytes,
+        }
+    }
+
+    /// Bytes of input solved per nanosecond, at the median time
+    pub fn throughput(&self) -> f64 {
+        self.bytes as f64 / self.median.as_nanos().max(1) as f64
+    }
+}
+
+/// Checks `input`, then runs `part` on it `warmup` times untimed and `runs` times timed.
+pub fn time(part: &Part, input: &str, warmup: usize, runs: usize) -> Result<Summary, InputError> {
+    part.check(input)?;
+
+    for _ in 0..warmup {
+        black_box((part.solve)(black_box(input)));
+    }
+
+    let mut samples: Vec<Duration> = (0..runs.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            black_box((part.solve)(black_box(input)));
+            start.elapsed()
+        })
+        .collect();
+    Ok(Summary::of(part.day, part.part, &mut samples, input.len()))
+}
+
+/// Displays summaries as an aligned table.
+pub struct Table<'a>(pub &'a [Summary]);
+
+impl Display for Table<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "day  part   runs         min      median         p99  bytes/ns"
+        )?;
+        for summary in self.0 {
+            writeln!(
+                f,
+                "{:>3}  {:>4}  {:>5}  {:>10?}  {:>10?}  {:>10?}  {:>8.3}",
+                summary.day,
+                summary.part,
+                summary.runs,
+                summary.min,
+                summary.median,
+                summary.p99,
+                summary.throughput()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let mut samples: Vec<_> = (1..=200).rev().map(Duration::from_nanos).collect();
+        let summary = Summary::of(3, 1, &mut samples, 1_000);
+        assert_eq!(
+            (summary.runs, summary.min, summary.median, summary.p99),
+            (
+                200,
+                Duration::from_nanos(1),
+                Duration::from_nanos(100),
+                Duration::from_nanos(198)
+            )
+        );
+        assert_eq!(summary.throughput(), 10.0);
+
+        let summary = Summary::of(3, 1, &mut [Duration::from_nanos(7)], 0);
+        assert_eq!(
+            (summary.median, summary.p99),
+            (Duration::from_nanos(7), Duration::from_nanos(7))
+        );
+    }
+
+    #[test]
+    fn times_local_input() {
+        let part = crate::solution(7, 2).unwrap();
+        let input = include_str!("../input/2024/day7.txt");
+        let summary = time(part, input, 1, 5).unwrap();
+        assert_eq!((summary.runs, summary.bytes), (5, input.len()));
+        assert!(summary.min <= summary.median && summary.median <= summary.p99);
+
+        assert!(matches!(
+            time(part, "1: 2 0\n", 1, 5),
+            Err(InputError::UnexpectedByte(_))
+        ));
+    }
+
+    #[test]
+    fn table() {
+        let summary = Summary {
+            day: 16,
+            part: 2,
+            runs: 100,
+            min: Duration::from_micros(10),
+            median: Duration::from_micros(20),
+            p99: Duration::from_micros(35),
+            bytes: 20_000,
+        };
+        assert_eq!(
+            Table(&[summary]).to_string(),
+            "day  part   runs         min      median         p99  bytes/ns\n \
+             16     2    100        10µs        20µs        35µs     1.000\n"
+        );
+    }
+}